@@ -0,0 +1,167 @@
+use crate::manager::sanitize_file_name;
+use crate::models::{DownloadJob, DownloadTask, TaskStatus};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Component, Path, PathBuf};
+
+/// Version written by `QueueBundle::from_jobs`; newer bundles are rejected
+pub const QUEUE_BUNDLE_VERSION: u32 = 1;
+
+/// Machine-independent copy of the download queue, for moving it to another install.
+/// Holds where each episode comes from, not the resolved (expiring) links or absolute paths.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueueBundle {
+    pub version: u32,
+    pub jobs: Vec<PortableJob>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PortableJob {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub anime_url: Option<String>,
+    #[serde(default)]
+    pub cover_url: Option<String>,
+    pub tasks: Vec<PortableTask>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PortableTask {
+    pub id: String,
+    /// Output file relative to the download directory, `/`-separated
+    pub path: String,
+    #[serde(default)]
+    pub episode_url: Option<String>,
+    #[serde(default)]
+    pub gate_id: Option<String>,
+    #[serde(default)]
+    pub episode_number: Option<u32>,
+    /// Direct video URL, only kept for tasks that can't be re-resolved from an episode page
+    #[serde(default)]
+    pub source_url: Option<String>,
+}
+
+impl QueueBundle {
+    /// Unfinished tasks of `jobs`. Paths under `download_dir` keep their relative location;
+    /// anything else is filed under the job's folder.
+    pub fn from_jobs(jobs: &[DownloadJob], download_dir: &Path) -> Self {
+        let jobs = jobs
+            .iter()
+            .filter_map(|job| {
+                let tasks: Vec<PortableTask> = job
+                    .tasks
+                    .iter()
+                    .filter(|t| t.status != TaskStatus::Completed)
+                    .map(|t| portable_task(job, t, download_dir))
+                    .collect();
+                (!tasks.is_empty()).then(|| PortableJob {
+                    id: job.id.clone(),
+                    name: job.name.clone(),
+                    anime_url: job.anime_url.clone(),
+                    cover_url: job.cover_url.clone(),
+                    tasks,
+                })
+            })
+            .collect();
+        Self {
+            version: QUEUE_BUNDLE_VERSION,
+            jobs,
+        }
+    }
+
+    /// Jobs for this machine: paths under `download_dir`, links resolved again when each
+    /// task starts (`url == "pending"`), progress reset
+    pub fn into_jobs(self, download_dir: &Path) -> Result<Vec<DownloadJob>> {
+        if self.version > QUEUE_BUNDLE_VERSION {
+            return Err(anyhow!(
+                "Queue bundle version {} is newer than supported ({})",
+                self.version,
+                QUEUE_BUNDLE_VERSION
+            ));
+        }
+
+        self.jobs
+            .into_iter()
+            .map(|job| {
+                let tasks = job
+                    .tasks
+                    .into_iter()
+                    .map(|task| {
+                        let url = match (&task.episode_url, &task.gate_id, &task.source_url) {
+                            (Some(_), Some(_), _) => "pending".to_string(),
+                            (_, _, Some(source)) => source.clone(),
+                            _ => return Err(anyhow!("Task {} has no episode page or source URL", task.id)),
+                        };
+                        let filename = local_path(download_dir, &job.name, &task.path)
+                            .ok_or_else(|| anyhow!("Task {} has no file name", task.id))?;
+                        Ok(DownloadTask {
+                            id: task.id,
+                            url,
+                            filename: filename.to_string_lossy().to_string(),
+                            status: TaskStatus::Pending,
+                            progress_bytes: 0,
+                            total_bytes: 0,
+                            episode_url: task.episode_url,
+                            gate_id: task.gate_id,
+                            episode_number: task.episode_number,
+                            segments: vec![],
+                        })
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                Ok(DownloadJob {
+                    id: job.id,
+                    name: job.name,
+                    tasks,
+                    anime_url: job.anime_url,
+                    cover_url: job.cover_url,
+                })
+            })
+            .collect()
+    }
+}
+
+fn portable_task(job: &DownloadJob, task: &DownloadTask, download_dir: &Path) -> PortableTask {
+    let file = Path::new(&task.filename);
+    let path = match file.strip_prefix(download_dir) {
+        Ok(relative) if file.is_absolute() => relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy().to_string())
+            .collect::<Vec<_>>()
+            .join("/"),
+        _ => {
+            let name = file
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_else(|| task.filename.clone());
+            format!("{}/{}", sanitize_file_name(&job.name), name)
+        }
+    };
+    let resolvable = task.episode_url.is_some() && task.gate_id.is_some();
+
+    PortableTask {
+        id: task.id.clone(),
+        path,
+        episode_url: task.episode_url.clone(),
+        gate_id: task.gate_id.clone(),
+        episode_number: task.episode_number,
+        source_url: (!resolvable).then(|| task.url.clone()),
+    }
+}
+
+/// Join a bundle path onto `download_dir`, dropping anything that could escape it
+fn local_path(download_dir: &Path, job_name: &str, path: &str) -> Option<PathBuf> {
+    let parts: Vec<String> = Path::new(path)
+        .components()
+        .filter_map(|c| match c {
+            Component::Normal(part) => Some(sanitize_file_name(&part.to_string_lossy())),
+            _ => None,
+        })
+        .filter(|p| !p.is_empty())
+        .collect();
+    match parts.as_slice() {
+        [] => None,
+        [file] => Some(download_dir.join(sanitize_file_name(job_name)).join(file)),
+        _ => Some(parts.iter().fold(download_dir.to_path_buf(), |dir, p| dir.join(p))),
+    }
+}
@@ -0,0 +1,359 @@
+use crate::config::{CacheSettings, Settings};
+use crate::models::{AnimeInfo, ListEntry, Page, ScrapeResult, SearchResult};
+use crate::scraper::{season_path, AnimeScraper};
+use crate::search::{self, SearchQuery};
+use crate::unix_timestamp;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, RwLock};
+
+/// Page size of `search_page`, `get_new_page` and `get_popular_page` when none is given
+pub const DEFAULT_PAGE_LIMIT: usize = 30;
+
+/// Most site pages read for one page of a listing, in case the site never runs out
+const MAX_SITE_PAGES: u32 = 50;
+
+#[derive(Serialize, Deserialize)]
+struct CacheEntry {
+    key: String,
+    /// Unix timestamp (seconds) when the value was fetched
+    stored_at: u64,
+    /// JSON-encoded response
+    value: String,
+}
+
+/// Least-recently-used map of JSON-encoded responses
+struct LruStore {
+    entries: HashMap<String, CacheEntry>,
+    order: VecDeque<String>,
+}
+
+impl LruStore {
+    fn new() -> Self {
+        Self {
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    fn touch(&mut self, key: &str) {
+        if let Some(pos) = self.order.iter().position(|k| k == key) {
+            self.order.remove(pos);
+        }
+        self.order.push_back(key.to_string());
+    }
+
+    fn get(&mut self, key: &str) -> Option<&CacheEntry> {
+        if self.entries.contains_key(key) {
+            self.touch(key);
+        }
+        self.entries.get(key)
+    }
+
+    fn insert(&mut self, entry: CacheEntry, capacity: usize) {
+        self.touch(&entry.key);
+        self.entries.insert(entry.key.clone(), entry);
+        while self.entries.len() > capacity.max(1) {
+            match self.order.pop_front() {
+                Some(oldest) => {
+                    self.entries.remove(&oldest);
+                }
+                None => break,
+            }
+        }
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+    }
+}
+
+/// Caches `AnimeScraper` responses in memory and optionally on disk, with a TTL per endpoint.
+/// Download links are never cached since they expire.
+pub struct CachedScraper {
+    scraper: Arc<AnimeScraper>,
+    settings: Arc<RwLock<Settings>>,
+    memory: Mutex<LruStore>,
+    disk_dir: PathBuf,
+}
+
+impl CachedScraper {
+    /// `disk_dir` is only used when `CacheSettings::persist_to_disk` is enabled
+    pub fn new(scraper: Arc<AnimeScraper>, settings: Arc<RwLock<Settings>>, disk_dir: PathBuf) -> Self {
+        Self {
+            scraper,
+            settings,
+            memory: Mutex::new(LruStore::new()),
+            disk_dir,
+        }
+    }
+
+    /// The uncached scraper, e.g. for download links
+    pub fn inner(&self) -> Arc<AnimeScraper> {
+        self.scraper.clone()
+    }
+
+    /// Search, reusing a cached result unless `refresh` is set. Results are ranked by relevance.
+    pub async fn search(&self, query: &str, refresh: bool) -> ScrapeResult<Vec<SearchResult>> {
+        self.search_with(&SearchQuery::new(query), refresh).await
+    }
+
+    /// Search by `query.text`, then keep the results whose season pages pass the filters and
+    /// rank them by relevance. Season pages are only fetched when a filter is set; otherwise
+    /// Japanese titles come from season pages already in the cache.
+    pub async fn search_with(&self, query: &SearchQuery, refresh: bool) -> ScrapeResult<Vec<SearchResult>> {
+        Ok(self.search_site_page(query, 1, refresh).await?.1)
+    }
+
+    /// Page `page` of `limit` results of `search_with`, reading as many of the site's result
+    /// pages as needed. Results are ranked within each site page, so earlier pages keep their
+    /// order as later ones are loaded.
+    pub async fn search_page(
+        &self,
+        query: &SearchQuery,
+        page: u32,
+        limit: usize,
+        refresh: bool,
+    ) -> ScrapeResult<Page<SearchResult>> {
+        self.paged(page, limit, |n| self.search_site_page(query, n, refresh), |r| &r.url)
+            .await
+    }
+
+    /// URLs of all results on one page of the site's search, and the results kept by `query`
+    async fn search_site_page(
+        &self,
+        query: &SearchQuery,
+        site_page: u32,
+        refresh: bool,
+    ) -> ScrapeResult<(Vec<String>, Vec<SearchResult>)> {
+        let text = query.text.trim();
+        let key = page_key(&format!("search:{}", text.to_lowercase()), site_page);
+        let ttl = self.cache_settings().search_ttl_secs;
+        let found: Vec<SearchResult> = self
+            .get_or_fetch(&key, ttl, refresh, || self.scraper.search_page(text, site_page))
+            .await?;
+        let urls = found.iter().map(|r| r.url.clone()).collect();
+
+        let mut results = Vec::with_capacity(found.len());
+        if query.has_filters() {
+            for mut result in found {
+                match self.get_season(&result.url, refresh).await {
+                    Ok(info) if query.matches(&info) => {
+                        result.japanese_title = info.japanese_title;
+                        results.push(result);
+                    }
+                    Ok(_) => {}
+                    Err(e) => tracing::warn!("Skipping search result {}: {}", result.url, e),
+                }
+            }
+        } else {
+            let season_ttl = self.cache_settings().season_ttl_secs;
+            for mut result in found {
+                let key = format!("season:{}", season_path(&self.scraper.provider(), &result.url));
+                if let Some(info) = self.lookup::<AnimeInfo>(&key, season_ttl) {
+                    result.japanese_title = info.japanese_title;
+                }
+                results.push(result);
+            }
+        }
+
+        search::rank(text, &mut results);
+        Ok((urls, results))
+    }
+
+    pub async fn get_season(&self, url: &str, refresh: bool) -> ScrapeResult<AnimeInfo> {
+        let key = format!("season:{}", season_path(&self.scraper.provider(), url));
+        let ttl = self.cache_settings().season_ttl_secs;
+        self.get_or_fetch(&key, ttl, refresh, || self.scraper.get_season(url))
+            .await
+    }
+
+    pub async fn get_new(&self, refresh: bool) -> ScrapeResult<Vec<ListEntry>> {
+        Ok(self.new_site_page(1, refresh).await?.1)
+    }
+
+    /// Page `page` of `limit` entries of `/new.php`, reading as many site pages as needed
+    pub async fn get_new_page(&self, page: u32, limit: usize, refresh: bool) -> ScrapeResult<Page<ListEntry>> {
+        self.paged(page, limit, |n| self.new_site_page(n, refresh), |e| &e.url)
+            .await
+    }
+
+    async fn new_site_page(&self, site_page: u32, refresh: bool) -> ScrapeResult<(Vec<String>, Vec<ListEntry>)> {
+        let ttl = self.cache_settings().new_ttl_secs;
+        let entries: Vec<ListEntry> = self
+            .get_or_fetch(&page_key("new", site_page), ttl, refresh, || self.scraper.get_new_page(site_page))
+            .await?;
+        Ok((entries.iter().map(|e| e.url.clone()).collect(), entries))
+    }
+
+    pub async fn get_popular(&self, refresh: bool) -> ScrapeResult<Vec<ListEntry>> {
+        Ok(self.popular_site_page(1, refresh).await?.1)
+    }
+
+    /// Page `page` of `limit` entries of `/popular.php`, ranked from the top of the chart
+    pub async fn get_popular_page(&self, page: u32, limit: usize, refresh: bool) -> ScrapeResult<Page<ListEntry>> {
+        let mut result = self
+            .paged(page, limit, |n| self.popular_site_page(n, refresh), |e| &e.url)
+            .await?;
+        let first_rank = (result.page - 1) * limit.max(1) as u32 + 1;
+        for (rank, entry) in (first_rank..).zip(result.items.iter_mut()) {
+            entry.rank = Some(rank);
+        }
+        Ok(result)
+    }
+
+    async fn popular_site_page(&self, site_page: u32, refresh: bool) -> ScrapeResult<(Vec<String>, Vec<ListEntry>)> {
+        let ttl = self.cache_settings().popular_ttl_secs;
+        let entries: Vec<ListEntry> = self
+            .get_or_fetch(&page_key("popular", site_page), ttl, refresh, || {
+                self.scraper.get_popular_page(site_page)
+            })
+            .await?;
+        Ok((entries.iter().map(|e| e.url.clone()).collect(), entries))
+    }
+
+    /// Page `page` (from 1) of `limit` entries, gathered from site pages 1, 2, ... fetched by
+    /// `site_page` as (URLs of every entry, entries to list). Reading stops one entry past the
+    /// page, so `has_more` is exact, or at a site page with no new URLs: past the end, or a site
+    /// that ignores the page number. Entries seen on an earlier site page are dropped.
+    async fn paged<T, F, Fut>(&self, page: u32, limit: usize, site_page: F, url: fn(&T) -> &str) -> ScrapeResult<Page<T>>
+    where
+        F: Fn(u32) -> Fut,
+        Fut: std::future::Future<Output = ScrapeResult<(Vec<String>, Vec<T>)>>,
+    {
+        let page = page.max(1);
+        let limit = limit.max(1);
+        let end = page as usize * limit;
+        let mut seen = HashSet::new();
+        let mut entries = Vec::new();
+
+        for n in 1..=MAX_SITE_PAGES {
+            let (urls, items) = site_page(n).await?;
+            let fresh: HashSet<String> = urls.into_iter().filter(|u| !seen.contains(u)).collect();
+            if fresh.is_empty() {
+                break;
+            }
+            entries.extend(items.into_iter().filter(|item| fresh.contains(url(item))));
+            seen.extend(fresh);
+            if entries.len() > end {
+                break;
+            }
+        }
+
+        let has_more = entries.len() > end;
+        let items = entries.into_iter().skip(end - limit).take(limit).collect();
+        Ok(Page { items, page, has_more })
+    }
+
+    /// Drop every cached response, in memory and on disk
+    pub fn clear(&self) {
+        self.memory.lock().unwrap().clear();
+        let _ = fs::remove_dir_all(&self.disk_dir);
+    }
+
+    fn cache_settings(&self) -> CacheSettings {
+        self.settings.read().unwrap().cache.clone()
+    }
+
+    /// Return the cached value for `key` if younger than `ttl` seconds, otherwise run `fetch`
+    /// and cache its result. `refresh` skips the lookup but still stores the fresh value.
+    pub async fn get_or_fetch<T, F, Fut>(
+        &self,
+        key: &str,
+        ttl: u64,
+        refresh: bool,
+        fetch: F,
+    ) -> ScrapeResult<T>
+    where
+        T: Serialize + DeserializeOwned,
+        F: FnOnce() -> Fut,
+        Fut: std::future::Future<Output = ScrapeResult<T>>,
+    {
+        if ttl == 0 {
+            return fetch().await;
+        }
+
+        if !refresh {
+            if let Some(value) = self.lookup(key, ttl) {
+                tracing::debug!("Cache hit: {}", key);
+                return Ok(value);
+            }
+        }
+
+        let value = fetch().await?;
+        self.store(key, &value);
+        Ok(value)
+    }
+
+    fn lookup<T: DeserializeOwned>(&self, key: &str, ttl: u64) -> Option<T> {
+        let now = unix_timestamp();
+        let fresh = |entry: &CacheEntry| now.saturating_sub(entry.stored_at) < ttl;
+
+        {
+            let mut memory = self.memory.lock().unwrap();
+            if let Some(entry) = memory.get(key).filter(|e| fresh(e)) {
+                return serde_json::from_str(&entry.value).ok();
+            }
+        }
+
+        let settings = self.cache_settings();
+        if !settings.persist_to_disk {
+            return None;
+        }
+        let data = fs::read_to_string(self.disk_path(key)).ok()?;
+        let entry: CacheEntry = serde_json::from_str(&data).ok()?;
+        if entry.key != key || !fresh(&entry) {
+            return None;
+        }
+        let value = serde_json::from_str(&entry.value).ok()?;
+        self.memory.lock().unwrap().insert(entry, settings.capacity);
+        value
+    }
+
+    fn store<T: Serialize>(&self, key: &str, value: &T) {
+        let Ok(json) = serde_json::to_string(value) else {
+            return;
+        };
+        let entry = CacheEntry {
+            key: key.to_string(),
+            stored_at: unix_timestamp(),
+            value: json,
+        };
+
+        let settings = self.cache_settings();
+        if settings.persist_to_disk {
+            if let Ok(data) = serde_json::to_string(&entry) {
+                let _ = fs::create_dir_all(&self.disk_dir);
+                let _ = fs::write(self.disk_path(key), data);
+            }
+        }
+        self.memory.lock().unwrap().insert(entry, settings.capacity);
+    }
+
+    fn disk_path(&self, key: &str) -> PathBuf {
+        self.disk_dir.join(format!("{:016x}.json", fnv1a(key.as_bytes())))
+    }
+}
+
+/// Cache key of one site page of a listing; page 1 keeps the key it had before paging
+fn page_key(key: &str, site_page: u32) -> String {
+    if site_page == 1 {
+        key.to_string()
+    } else {
+        format!("{}:page:{}", key, site_page)
+    }
+}
+
+/// Stable 64-bit FNV-1a hash for cache file names
+fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in bytes {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
@@ -1,12 +1,281 @@
+use crate::migrations::{self, SETTINGS_VERSION};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
+/// Keys missing from a file take their default, so files written by older versions still load
 #[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
 pub struct Settings {
+    /// Schema version of the file, see `migrations`
+    pub config_version: u32,
     pub download_dir: PathBuf,
     pub max_concurrent_downloads: usize,
     pub segments_per_file: usize,
     pub user_agent: String,
+    /// How often followed shows are checked for new episodes
+    pub subscription_check_interval_secs: u64,
+    /// Scrape result cache (search, season pages, listings)
+    pub cache: CacheSettings,
+    /// Sidecar metadata for media servers (Jellyfin, Kodi, Plex)
+    pub metadata: MetadataSettings,
+    /// LAN HTTP server for the library (requires the `streaming` feature)
+    pub streaming: StreamingSettings,
+    /// Local control API of `aura-cli daemon` (requires the `daemon` feature)
+    pub daemon: DaemonSettings,
+    /// Proxy, timeouts, headers and TLS roots for the scraper and downloads
+    pub network: NetworkSettings,
+}
+
+/// A setting value the manager can't work with
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum SettingsError {
+    #[error("Max concurrent downloads must be at least 1")]
+    ZeroConcurrency,
+
+    #[error("Segments per file must be at least 1")]
+    ZeroSegments,
+
+    #[error("Subscription check interval must be at least 1 second")]
+    ZeroCheckInterval,
+
+    #[error("Download directory is empty")]
+    EmptyDownloadDir,
+
+    #[error("User agent is empty")]
+    EmptyUserAgent,
+
+    #[error("Settings version {found} is newer than supported ({supported}); update Aura")]
+    UnsupportedVersion { found: u32, supported: u32 },
+
+    #[error("Proxy: {0}")]
+    InvalidProxy(String),
+
+    #[error("Connect timeout must be at least 1 second")]
+    ZeroConnectTimeout,
+
+    #[error("Read timeout must be at least 1 second")]
+    ZeroReadTimeout,
+
+    #[error("Header '{0}' is not a valid HTTP header")]
+    InvalidHeader(String),
+
+    #[error("CA bundle: {0}")]
+    InvalidCaBundle(String),
+
+    #[error("Requests per host must be at least 1")]
+    ZeroHostConcurrency,
+}
+
+impl SettingsError {
+    /// Dotted key of the offending setting, for pointing at it in a settings form.
+    /// A bad header names its own key (`network.headers.<name>`).
+    pub fn field(&self) -> String {
+        let field = match self {
+            SettingsError::ZeroConcurrency => "max_concurrent_downloads",
+            SettingsError::ZeroSegments => "segments_per_file",
+            SettingsError::ZeroCheckInterval => "subscription_check_interval_secs",
+            SettingsError::EmptyDownloadDir => "download_dir",
+            SettingsError::EmptyUserAgent => "user_agent",
+            SettingsError::UnsupportedVersion { .. } => "config_version",
+            SettingsError::InvalidProxy(_) => "network.proxy",
+            SettingsError::ZeroConnectTimeout => "network.connect_timeout_secs",
+            SettingsError::ZeroReadTimeout => "network.read_timeout_secs",
+            SettingsError::InvalidHeader(name) => return format!("network.headers.{}", name),
+            SettingsError::InvalidCaBundle(_) => "network.ca_bundle",
+            SettingsError::ZeroHostConcurrency => "network.max_requests_per_host",
+        };
+        field.to_string()
+    }
+}
+
+fn default_subscription_check_interval() -> u64 {
+    60 * 60
+}
+
+/// Time-to-live per scraper endpoint, in seconds. A TTL of 0 disables caching for that endpoint.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct CacheSettings {
+    /// Maximum number of responses kept in memory
+    pub capacity: usize,
+    /// Also keep responses under `<config dir>/cache/scrape` across restarts
+    pub persist_to_disk: bool,
+    pub search_ttl_secs: u64,
+    pub season_ttl_secs: u64,
+    pub new_ttl_secs: u64,
+    pub popular_ttl_secs: u64,
+}
+
+impl Default for CacheSettings {
+    fn default() -> Self {
+        Self {
+            capacity: 256,
+            persist_to_disk: true,
+            search_ttl_secs: 30 * 60,
+            season_ttl_secs: 6 * 60 * 60,
+            new_ttl_secs: 5 * 60,
+            popular_ttl_secs: 30 * 60,
+        }
+    }
+}
+
+/// What to write next to downloaded episodes so media servers can identify them
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct MetadataSettings {
+    /// Write `tvshow.nfo` and per-episode `.nfo` files when an episode completes
+    pub write_nfo: bool,
+    /// Save the cover as `poster.jpg` in the show folder
+    pub save_poster: bool,
+    /// Name new episodes `<Title> S01E01.mp4` instead of `Ep01.mp4`
+    pub jellyfin_naming: bool,
+    /// Write M3U8 and XSPF playlists of the completed episodes when a job finishes
+    pub write_playlists: bool,
+}
+
+impl Default for MetadataSettings {
+    fn default() -> Self {
+        Self {
+            write_nfo: false,
+            save_poster: false,
+            jellyfin_naming: false,
+            write_playlists: true,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct StreamingSettings {
+    /// Start the server with the app
+    pub enabled: bool,
+    /// Address to listen on. The server has no authentication: `0.0.0.0` makes the library
+    /// readable by anyone on the network.
+    pub bind_address: String,
+    pub port: u16,
+}
+
+impl Default for StreamingSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bind_address: "127.0.0.1".to_string(),
+            port: 8377,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct DaemonSettings {
+    /// Keep this on loopback unless the API must be reachable from other machines
+    pub bind_address: String,
+    pub port: u16,
+}
+
+impl Default for DaemonSettings {
+    fn default() -> Self {
+        Self {
+            bind_address: "127.0.0.1".to_string(),
+            port: 8378,
+        }
+    }
+}
+
+/// Which address family to connect over
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum IpVersion {
+    /// Whatever the system resolver returns first
+    #[default]
+    Auto,
+    Ipv4,
+    Ipv6,
+}
+
+/// Shared by the scraper and the downloader (see `network::HttpClients`)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct NetworkSettings {
+    /// `http://`, `https://`, `socks5://` or `socks5h://` (names resolved by the proxy) URL;
+    /// empty for a direct connection
+    pub proxy: String,
+    pub proxy_username: String,
+    pub proxy_password: String,
+    pub connect_timeout_secs: u64,
+    /// Longest wait for a page, or for the next chunk of a download
+    pub read_timeout_secs: u64,
+    /// Sent with every request, e.g. `Accept-Language`
+    pub headers: std::collections::BTreeMap<String, String>,
+    /// PEM file with extra root certificates (for TLS-intercepting proxies); empty for none
+    pub ca_bundle: PathBuf,
+    pub ip_version: IpVersion,
+    /// Scraper requests in flight to one site at a time
+    pub max_requests_per_host: usize,
+    /// Least time between the starts of two scraper requests to one site; 0 for no spacing
+    pub min_request_interval_ms: u64,
+}
+
+impl Default for NetworkSettings {
+    fn default() -> Self {
+        Self {
+            proxy: String::new(),
+            proxy_username: String::new(),
+            proxy_password: String::new(),
+            connect_timeout_secs: 10,
+            read_timeout_secs: 30,
+            headers: Default::default(),
+            ca_bundle: PathBuf::new(),
+            ip_version: IpVersion::Auto,
+            max_requests_per_host: 4,
+            min_request_interval_ms: 250,
+        }
+    }
+}
+
+impl NetworkSettings {
+    fn validate(&self) -> Result<(), SettingsError> {
+        if self.connect_timeout_secs == 0 {
+            return Err(SettingsError::ZeroConnectTimeout);
+        }
+        if self.read_timeout_secs == 0 {
+            return Err(SettingsError::ZeroReadTimeout);
+        }
+        if self.max_requests_per_host == 0 {
+            return Err(SettingsError::ZeroHostConcurrency);
+        }
+        let proxy = self.proxy.trim();
+        if !proxy.is_empty() {
+            let scheme = proxy.split_once("://").map(|(scheme, _)| scheme.to_lowercase());
+            if !matches!(scheme.as_deref(), Some("http" | "https" | "socks5" | "socks5h")) {
+                return Err(SettingsError::InvalidProxy(format!(
+                    "'{}' must start with http://, https://, socks5:// or socks5h://",
+                    proxy
+                )));
+            }
+            reqwest::Proxy::all(proxy).map_err(|e| SettingsError::InvalidProxy(e.to_string()))?;
+        }
+        for (name, value) in &self.headers {
+            let valid = reqwest::header::HeaderName::from_bytes(name.as_bytes()).is_ok()
+                && reqwest::header::HeaderValue::from_str(value).is_ok();
+            if !valid {
+                return Err(SettingsError::InvalidHeader(name.clone()));
+            }
+        }
+        if !self.ca_bundle.as_os_str().is_empty() {
+            let path = &self.ca_bundle;
+            let pem = std::fs::read(path)
+                .map_err(|e| SettingsError::InvalidCaBundle(format!("{}: {}", path.display(), e)))?;
+            match reqwest::Certificate::from_pem_bundle(&pem) {
+                Ok(certs) if !certs.is_empty() => {}
+                Ok(_) => {
+                    return Err(SettingsError::InvalidCaBundle(format!("{}: no certificates", path.display())))
+                }
+                Err(e) => return Err(SettingsError::InvalidCaBundle(format!("{}: {}", path.display(), e))),
+            }
+        }
+        Ok(())
+    }
 }
 
 impl Default for Settings {
@@ -18,15 +287,49 @@ impl Default for Settings {
         };
 
         Self {
+            config_version: SETTINGS_VERSION,
             download_dir,
             max_concurrent_downloads: 3,
             segments_per_file: 4,
             user_agent: "Mozilla/5.0 (Windows NT 10.0; Win64; x64) Aura/1.0".to_string(),
+            subscription_check_interval_secs: default_subscription_check_interval(),
+            cache: CacheSettings::default(),
+            metadata: MetadataSettings::default(),
+            streaming: StreamingSettings::default(),
+            daemon: DaemonSettings::default(),
+            network: NetworkSettings::default(),
         }
     }
 }
 
 impl Settings {
+    /// Check the values the download manager depends on (a zero concurrency would never start
+    /// a download, zero segments would divide by zero)
+    pub fn validate(&self) -> Result<(), SettingsError> {
+        if self.config_version > SETTINGS_VERSION {
+            return Err(SettingsError::UnsupportedVersion {
+                found: self.config_version,
+                supported: SETTINGS_VERSION,
+            });
+        }
+        if self.max_concurrent_downloads == 0 {
+            return Err(SettingsError::ZeroConcurrency);
+        }
+        if self.segments_per_file == 0 {
+            return Err(SettingsError::ZeroSegments);
+        }
+        if self.subscription_check_interval_secs == 0 {
+            return Err(SettingsError::ZeroCheckInterval);
+        }
+        if self.download_dir.as_os_str().is_empty() {
+            return Err(SettingsError::EmptyDownloadDir);
+        }
+        if self.user_agent.trim().is_empty() {
+            return Err(SettingsError::EmptyUserAgent);
+        }
+        self.network.validate()
+    }
+
     pub fn load(custom_path: Option<&std::path::Path>) -> anyhow::Result<Self> {
         let config_dir = if let Some(path) = custom_path {
             path.to_path_buf()
@@ -39,11 +342,15 @@ impl Settings {
         std::fs::create_dir_all(&config_dir)?;
 
         let config_file = config_dir.join("settings.toml");
-        println!("[Aura] Loading settings from: {:?}", config_file);
+        tracing::debug!("Loading settings from: {:?}", config_file);
 
         if config_file.exists() {
-            let contents = std::fs::read_to_string(&config_file)?;
-            let settings: Settings = toml::from_str(&contents)?;
+            let mut table = read_table(&config_file)?;
+            if migrations::migrate_settings(&mut table)? {
+                std::fs::write(&config_file, toml::to_string_pretty(&table)?)?;
+            }
+            let settings: Settings = toml::Value::Table(table).try_into()?;
+            settings.validate()?;
             Ok(settings)
         } else {
             let settings = Settings::default();
@@ -65,10 +372,428 @@ impl Settings {
         std::fs::create_dir_all(&config_dir)?;
 
         let config_file = config_dir.join("settings.toml");
-        println!("[Aura] Saving settings to: {:?}", config_file);
+        tracing::debug!("Saving settings to: {:?}", config_file);
         
         let toml_string = toml::to_string_pretty(self)?;
         std::fs::write(&config_file, toml_string)?;
         Ok(())
     }
 }
+
+/// Where an effective setting came from, lowest precedence first
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "layer", content = "detail", rename_all = "snake_case")]
+pub enum ConfigSource {
+    Default,
+    /// Machine-wide file (`/etc/aura/settings.toml` and equivalents)
+    System(PathBuf),
+    /// `settings.toml` in the config directory
+    User(PathBuf),
+    /// `aura.toml` in the working directory or one of its parents
+    Project(PathBuf),
+    /// `AURA_*` environment variable
+    Env(String),
+    /// Command line flag
+    Cli(String),
+}
+
+impl std::fmt::Display for ConfigSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigSource::Default => write!(f, "default"),
+            ConfigSource::System(path) => write!(f, "system file {}", path.display()),
+            ConfigSource::User(path) => write!(f, "user file {}", path.display()),
+            ConfigSource::Project(path) => write!(f, "project file {}", path.display()),
+            ConfigSource::Env(var) => write!(f, "environment {}", var),
+            ConfigSource::Cli(flag) => write!(f, "command line {}", flag),
+        }
+    }
+}
+
+impl ConfigSource {
+    /// Layers above the user file; their values are never written back to `settings.toml`
+    fn is_override(&self) -> bool {
+        matches!(self, ConfigSource::Project(_) | ConfigSource::Env(_) | ConfigSource::Cli(_))
+    }
+}
+
+/// Origin of every effective setting, keyed by dotted path (`cache.capacity`)
+pub type SettingsOrigins = std::collections::BTreeMap<String, ConfigSource>;
+
+/// Everything layered on top of the defaults and the user's `settings.toml`.
+///
+/// Precedence, lowest first: defaults, system file, user file, project file,
+/// `AURA_*` environment variables, command line overrides.
+#[derive(Debug, Clone, Default)]
+pub struct ConfigLayers {
+    pub system_file: Option<PathBuf>,
+    pub project_file: Option<PathBuf>,
+    /// Environment variables to consider; only `AURA_<KEY>` names of known settings are used
+    pub env: Vec<(String, String)>,
+    /// `(dotted key, value, flag name)` from the command line
+    pub cli: Vec<(String, String, String)>,
+}
+
+impl ConfigLayers {
+    /// The system file, the nearest `aura.toml` above the working directory and the process environment
+    pub fn discover() -> Self {
+        Self {
+            system_file: system_config_file(),
+            project_file: std::env::current_dir().ok().and_then(|dir| {
+                dir.ancestors()
+                    .map(|d| d.join(PROJECT_CONFIG_FILE))
+                    .find(|p| p.is_file())
+            }),
+            env: std::env::vars().filter(|(k, _)| k.starts_with(ENV_PREFIX)).collect(),
+            cli: vec![],
+        }
+    }
+
+    /// Override `key` (dotted path) from the command line flag `flag`
+    pub fn with_cli(mut self, key: &str, value: impl Into<String>, flag: &str) -> Self {
+        self.cli.push((key.to_string(), value.into(), flag.to_string()));
+        self
+    }
+}
+
+/// Settings file looked up in the working directory and its parents
+pub const PROJECT_CONFIG_FILE: &str = "aura.toml";
+const ENV_PREFIX: &str = "AURA_";
+
+/// Machine-wide settings shared by all users, if the platform has such a location
+pub fn system_config_file() -> Option<PathBuf> {
+    if cfg!(target_os = "windows") {
+        std::env::var_os("ProgramData").map(|dir| PathBuf::from(dir).join("aura").join("settings.toml"))
+    } else if cfg!(target_os = "macos") {
+        Some(PathBuf::from("/Library/Application Support/aura/settings.toml"))
+    } else if cfg!(target_os = "android") {
+        None
+    } else {
+        Some(PathBuf::from("/etc/aura/settings.toml"))
+    }
+}
+
+/// Environment variable of a dotted key: `cache.capacity` is `AURA_CACHE__CAPACITY`
+pub fn env_var_name(key: &str) -> String {
+    format!("{}{}", ENV_PREFIX, key.replace('.', "__").to_uppercase())
+}
+
+/// Settings merged from all layers, with what is needed to save user changes
+/// without copying overrides into `settings.toml`
+#[derive(Debug, Clone)]
+pub struct LayeredSettings {
+    pub settings: Settings,
+    pub origins: SettingsOrigins,
+    /// Defaults, system and user file merged: what `settings.toml` should keep for overridden keys
+    base: toml::Table,
+    user_file: PathBuf,
+    layers: ConfigLayers,
+    /// `settings.toml` as last read or written, to notice edits made by hand
+    user_contents: String,
+    /// Why `settings.toml` could not be read on the last check, so it is logged once
+    read_error: Option<std::io::ErrorKind>,
+    /// Invalid values replaced by their defaults in `load_or_defaults`, until valid settings
+    /// are saved or reloaded
+    pub rejected: Vec<String>,
+}
+
+impl LayeredSettings {
+    /// Merge every layer for the config directory `config_dir`. A missing user file is
+    /// created with the defaults, like `Settings::load`.
+    pub fn load(config_dir: &std::path::Path, layers: &ConfigLayers) -> anyhow::Result<Self> {
+        Self::load_layers(config_dir, layers, false)
+    }
+
+    /// Like `load`, but a value that fails `Settings::validate` is replaced by its default
+    /// instead of failing, so a front-end can start and show the error from `rejected`.
+    /// Unreadable files and unknown keys still fail.
+    pub fn load_or_defaults(config_dir: &std::path::Path, layers: &ConfigLayers) -> anyhow::Result<Self> {
+        Self::load_layers(config_dir, layers, true)
+    }
+
+    fn load_layers(config_dir: &std::path::Path, layers: &ConfigLayers, fallback: bool) -> anyhow::Result<Self> {
+        std::fs::create_dir_all(config_dir)?;
+        let user_file = config_dir.join("settings.toml");
+        if !user_file.exists() {
+            Settings::default().save(Some(config_dir))?;
+        }
+
+        let defaults = toml::Table::try_from(Settings::default())?;
+        let mut merged = defaults.clone();
+        let mut origins = SettingsOrigins::new();
+        for (key, _) in leaves(&merged) {
+            origins.insert(key, ConfigSource::Default);
+        }
+
+        if let Some(path) = layers.system_file.as_ref().filter(|p| p.is_file()) {
+            merge(&mut merged, read_layer(path)?, &ConfigSource::System(path.clone()), &mut origins, "");
+        }
+        tracing::debug!("Loading settings from: {:?}", user_file);
+        let mut user_contents = std::fs::read_to_string(&user_file)?;
+        let mut user: toml::Table =
+            toml::from_str(&user_contents).map_err(|e| anyhow::anyhow!("{}: {}", user_file.display(), e))?;
+        if migrations::migrate_settings(&mut user)? {
+            tracing::info!("Migrated {:?} to settings version {}", user_file, SETTINGS_VERSION);
+            user_contents = toml::to_string_pretty(&user)?;
+            std::fs::write(&user_file, &user_contents)?;
+        }
+        merge(&mut merged, user, &ConfigSource::User(user_file.clone()), &mut origins, "");
+        let base = merged.clone();
+
+        if let Some(path) = layers.project_file.as_ref().filter(|p| p.is_file()) {
+            merge(&mut merged, read_layer(path)?, &ConfigSource::Project(path.clone()), &mut origins, "");
+        }
+
+        let known: Vec<(String, toml::Value)> = leaves(&merged);
+        for (key, current) in &known {
+            let var = env_var_name(key);
+            if let Some((_, raw)) = layers.env.iter().find(|(name, _)| *name == var) {
+                let value = parse_like(current, raw).map_err(|e| anyhow::anyhow!("{}: {}", var, e))?;
+                set_path(&mut merged, key, value);
+                origins.insert(key.clone(), ConfigSource::Env(var));
+            }
+        }
+
+        for (key, raw, flag) in &layers.cli {
+            let current = known
+                .iter()
+                .find(|(k, _)| k == key)
+                .map(|(_, v)| v)
+                .ok_or_else(|| anyhow::anyhow!("{}: unknown setting '{}'", flag, key))?;
+            let value = parse_like(current, raw).map_err(|e| anyhow::anyhow!("{}: {}", flag, e))?;
+            set_path(&mut merged, key, value);
+            origins.insert(key.clone(), ConfigSource::Cli(flag.clone()));
+        }
+
+        let mut rejected = Vec::new();
+        let settings = loop {
+            let settings: Settings = toml::Value::Table(merged.clone()).try_into()?;
+            let Err(e) = settings.validate() else {
+                break settings;
+            };
+            let field = e.field();
+            let source = origins.get(&field).cloned().unwrap_or(ConfigSource::Default);
+            let error = anyhow::Error::new(e).context(format!("Invalid setting from {}", source));
+            // A default that is itself rejected would loop
+            if !fallback || source == ConfigSource::Default {
+                return Err(error);
+            }
+            tracing::warn!("{:#}; using the default", error);
+            rejected.push(format!("{:#}", error));
+            // Keys without a default (a header) are dropped
+            match get_path(&defaults, &field).cloned() {
+                Some(default) => {
+                    set_path(&mut merged, &field, default);
+                    origins.insert(field, ConfigSource::Default);
+                }
+                None => {
+                    remove_path(&mut merged, &field);
+                    origins.remove(&field);
+                }
+            }
+        };
+        Ok(Self {
+            settings,
+            origins,
+            base,
+            user_file,
+            layers: layers.clone(),
+            user_contents,
+            read_error: None,
+            rejected,
+        })
+    }
+
+    /// Load the layers again if `settings.toml` was changed by someone else. Returns the keys
+    /// whose effective value changed, or None when the file is as last seen. An invalid file is
+    /// reported once and the current settings are kept. A missing or unreadable file is not an
+    /// edit: editors that save by renaming remove it for a moment, and a deleted file keeps the
+    /// current settings until it is written again.
+    pub fn reload_if_changed(&mut self) -> anyhow::Result<Option<Vec<String>>> {
+        let contents = match std::fs::read_to_string(&self.user_file) {
+            Ok(contents) => contents,
+            Err(e) => {
+                if e.kind() != std::io::ErrorKind::NotFound && self.read_error != Some(e.kind()) {
+                    tracing::warn!("Cannot read {:?}, keeping the current settings: {}", self.user_file, e);
+                }
+                self.read_error = Some(e.kind());
+                return Ok(None);
+            }
+        };
+        self.read_error = None;
+        if contents == self.user_contents {
+            return Ok(None);
+        }
+        let config_dir = self.user_file.parent().map(|p| p.to_path_buf()).unwrap_or_default();
+        match LayeredSettings::load(&config_dir, &self.layers) {
+            Ok(reloaded) => {
+                let keys = changed_keys(&self.settings, &reloaded.settings)?;
+                *self = reloaded;
+                Ok(Some(keys))
+            }
+            Err(e) => {
+                self.user_contents = contents;
+                Err(e)
+            }
+        }
+    }
+
+    /// Write `settings` to the user file and make it the effective value. Keys that come from
+    /// a project file, the environment or the command line keep their `settings.toml` value
+    /// unless `settings` changes them.
+    /// Returns the keys whose value changed.
+    pub fn save(&mut self, settings: Settings) -> anyhow::Result<Vec<String>> {
+        settings.validate()?;
+        let effective = toml::Table::try_from(&self.settings)?;
+        let mut to_save = toml::Table::try_from(&settings)?;
+        for (key, source) in self.origins.iter_mut() {
+            if !source.is_override() {
+                continue;
+            }
+            let unchanged = get_path(&to_save, key) == get_path(&effective, key);
+            match get_path(&self.base, key).cloned() {
+                Some(previous) if unchanged => set_path(&mut to_save, key, previous),
+                _ => *source = ConfigSource::User(self.user_file.clone()),
+            }
+        }
+
+        if let Some(dir) = self.user_file.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        tracing::debug!("Saving settings to: {:?}", self.user_file);
+        let contents = toml::to_string_pretty(&to_save)?;
+        std::fs::write(&self.user_file, &contents)?;
+        let keys = changed_keys(&self.settings, &settings)?;
+        self.user_contents = contents;
+        self.base = to_save;
+        self.settings = settings;
+        self.rejected.clear();
+        Ok(keys)
+    }
+}
+
+/// Dotted keys whose value differs between `old` and `new`
+fn changed_keys(old: &Settings, new: &Settings) -> anyhow::Result<Vec<String>> {
+    let old = toml::Table::try_from(old)?;
+    let new = leaves(&toml::Table::try_from(new)?);
+    Ok(new
+        .into_iter()
+        .filter(|(key, value)| get_path(&old, key) != Some(value))
+        .map(|(key, _)| key)
+        .collect())
+}
+
+fn read_table(path: &std::path::Path) -> anyhow::Result<toml::Table> {
+    let contents = std::fs::read_to_string(path)?;
+    toml::from_str(&contents).map_err(|e| anyhow::anyhow!("{}: {}", path.display(), e))
+}
+
+/// A system or project file, migrated in memory (Aura doesn't own those files)
+fn read_layer(path: &std::path::Path) -> anyhow::Result<toml::Table> {
+    let mut table = read_table(path)?;
+    let versioned = table.contains_key("config_version");
+    migrations::migrate_settings(&mut table).map_err(|e| anyhow::anyhow!("{}: {}", path.display(), e))?;
+    if !versioned {
+        table.remove("config_version");
+    }
+    Ok(table)
+}
+
+/// Overlay `layer` onto `base`, recording `source` for every value it sets
+fn merge(base: &mut toml::Table, layer: toml::Table, source: &ConfigSource, origins: &mut SettingsOrigins, prefix: &str) {
+    for (name, value) in layer {
+        let key = if prefix.is_empty() { name.clone() } else { format!("{}.{}", prefix, name) };
+        match (base.get_mut(&name), value) {
+            (Some(toml::Value::Table(existing)), toml::Value::Table(table)) => {
+                merge(existing, table, source, origins, &key)
+            }
+            (_, value) => {
+                if let toml::Value::Table(table) = &value {
+                    for (leaf, _) in leaves(table) {
+                        origins.insert(format!("{}.{}", key, leaf), source.clone());
+                    }
+                } else {
+                    origins.insert(key, source.clone());
+                }
+                base.insert(name, value);
+            }
+        }
+    }
+}
+
+/// Dotted paths and values of every non-table value
+fn leaves(table: &toml::Table) -> Vec<(String, toml::Value)> {
+    let mut out = Vec::new();
+    for (name, value) in table {
+        match value {
+            toml::Value::Table(inner) => {
+                out.extend(leaves(inner).into_iter().map(|(k, v)| (format!("{}.{}", name, k), v)))
+            }
+            value => out.push((name.clone(), value.clone())),
+        }
+    }
+    out
+}
+
+fn get_path<'a>(table: &'a toml::Table, key: &str) -> Option<&'a toml::Value> {
+    let (head, rest) = match key.split_once('.') {
+        Some((head, rest)) => (head, Some(rest)),
+        None => (key, None),
+    };
+    match (table.get(head), rest) {
+        (Some(toml::Value::Table(inner)), Some(rest)) => get_path(inner, rest),
+        (value, None) => value,
+        _ => None,
+    }
+}
+
+fn set_path(table: &mut toml::Table, key: &str, value: toml::Value) {
+    match key.split_once('.') {
+        Some((head, rest)) => {
+            let inner = table
+                .entry(head.to_string())
+                .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+            if let toml::Value::Table(inner) = inner {
+                set_path(inner, rest, value);
+            }
+        }
+        None => {
+            table.insert(key.to_string(), value);
+        }
+    }
+}
+
+fn remove_path(table: &mut toml::Table, key: &str) {
+    match key.split_once('.') {
+        Some((head, rest)) => {
+            if let Some(toml::Value::Table(inner)) = table.get_mut(head) {
+                remove_path(inner, rest);
+            }
+        }
+        None => {
+            table.remove(key);
+        }
+    }
+}
+
+/// Parse a raw override into the type of the value it replaces
+fn parse_like(current: &toml::Value, raw: &str) -> anyhow::Result<toml::Value> {
+    let raw = raw.trim();
+    Ok(match current {
+        toml::Value::String(_) => toml::Value::String(raw.to_string()),
+        toml::Value::Integer(_) => toml::Value::Integer(
+            raw.parse().map_err(|_| anyhow::anyhow!("'{}' is not a whole number", raw))?,
+        ),
+        toml::Value::Float(_) => toml::Value::Float(
+            raw.parse().map_err(|_| anyhow::anyhow!("'{}' is not a number", raw))?,
+        ),
+        toml::Value::Boolean(_) => toml::Value::Boolean(match raw.to_lowercase().as_str() {
+            "1" | "true" | "yes" | "on" => true,
+            "0" | "false" | "no" | "off" => false,
+            _ => anyhow::bail!("'{}' is not true or false", raw),
+        }),
+        _ => toml::from_str::<toml::Table>(&format!("value = {}", raw))?
+            .remove("value")
+            .ok_or_else(|| anyhow::anyhow!("'{}' is not a valid value", raw))?,
+    })
+}
@@ -0,0 +1,1121 @@
+use crate::cache::DEFAULT_PAGE_LIMIT;
+use crate::config::Settings;
+use crate::manager::DownloadManager;
+use crate::import::{self, ImportEntry, ImportReport};
+use crate::models::{
+    AnimeInfo, DownloadJob, ListEntry, ListStatus, ManagerEvent, Notification, Page, ScrapeError,
+    SearchResult, ShowProgress, Subscription, TaskStatus, WatchEntry, WatchlistEntry,
+};
+use crate::notifications::NotificationCenter;
+use crate::playlist::{self, PlaylistFormat};
+use crate::subscriptions::{QueuedEpisode, SubscriptionManager};
+use crate::search::{AnimeKind, SearchQuery};
+use crate::watch_state::WatchStateStore;
+use crate::watchlist::WatchlistStore;
+use anyhow::{anyhow, Result};
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Path as UrlPath, Query, Request, State};
+use axum::http::{header, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use futures_util::StreamExt;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::sync::{broadcast, mpsc, Notify};
+
+/// Written to the config directory while a daemon runs, so clients can find it
+pub const DAEMON_FILE: &str = "daemon.json";
+
+/// Address and token of a running daemon
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DaemonInfo {
+    pub address: String,
+    pub token: String,
+    pub pid: u32,
+}
+
+impl DaemonInfo {
+    pub fn path(config_dir: &Path) -> PathBuf {
+        config_dir.join(DAEMON_FILE)
+    }
+
+    pub fn load(config_dir: &Path) -> Option<Self> {
+        let data = std::fs::read_to_string(Self::path(config_dir)).ok()?;
+        serde_json::from_str(&data).ok()
+    }
+
+    /// The token grants full control over the queue, so the file is created private to the
+    /// user (0600) before the token is written, then renamed into place
+    fn save(&self, config_dir: &Path) -> Result<()> {
+        use std::io::Write;
+
+        let path = Self::path(config_dir);
+        let temp = path.with_extension("json.tmp");
+        let _ = std::fs::remove_file(&temp);
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut file = options.open(&temp)?;
+        file.write_all(serde_json::to_string_pretty(self)?.as_bytes())?;
+        file.sync_all()?;
+        drop(file);
+        std::fs::rename(&temp, &path)?;
+        Ok(())
+    }
+}
+
+/// Services shared by the API handlers
+pub struct DaemonState {
+    manager: Arc<DownloadManager>,
+    notifications: Arc<NotificationCenter>,
+    subscriptions: Arc<SubscriptionManager>,
+    watch_state: WatchStateStore,
+    watchlist: WatchlistStore,
+    token: String,
+    shutdown: Notify,
+}
+
+/// Host the manager until `/api/shutdown` is called or the process receives Ctrl+C.
+/// Unfinished jobs are resumed on start. Background services (subscription poller,
+/// notifications, metadata) are spawned by the caller like in the other front-ends;
+/// `subscriptions` is the store its poller uses, so clients edit it through the API.
+/// Watch state and the watchlist are only written by the daemon while it runs.
+pub async fn run(
+    manager: Arc<DownloadManager>,
+    notifications: Arc<NotificationCenter>,
+    subscriptions: Arc<SubscriptionManager>,
+    listener: TcpListener,
+) -> Result<()> {
+    let config_dir = manager.config_dir();
+    let watch_state = WatchStateStore::new(&config_dir)?;
+    let watchlist = WatchlistStore::new(&config_dir)?;
+    let info = DaemonInfo {
+        address: listener.local_addr()?.to_string(),
+        token: uuid::Uuid::new_v4().simple().to_string(),
+        pid: std::process::id(),
+    };
+    info.save(&config_dir)?;
+    tracing::info!("Daemon listening on {}", info.address);
+
+    for job in manager.get_jobs() {
+        if job.tasks.iter().any(|t| t.status == TaskStatus::Pending) {
+            if let Err(e) = manager.start_download(job.id.clone()).await {
+                tracing::warn!("Failed to resume {}: {}", job.name, e);
+            }
+        }
+    }
+
+    let state = Arc::new(DaemonState {
+        manager,
+        notifications,
+        subscriptions,
+        watch_state,
+        watchlist,
+        token: info.token.clone(),
+        shutdown: Notify::new(),
+    });
+    let shutdown_state = state.clone();
+    let result = axum::serve(listener, router(state))
+        .with_graceful_shutdown(async move {
+            tokio::select! {
+                _ = shutdown_state.shutdown.notified() => {}
+                _ = tokio::signal::ctrl_c() => {}
+            }
+        })
+        .await;
+
+    let _ = std::fs::remove_file(DaemonInfo::path(&config_dir));
+    result?;
+    Ok(())
+}
+
+/// Control API. Everything except `/api/health` needs `Authorization: Bearer <token>`,
+/// the WebSocket included. The token is never read from the query string, which ends up in logs.
+pub fn router(state: Arc<DaemonState>) -> Router {
+    let api = Router::new()
+        .route("/api/search", get(search_handler))
+        .route("/api/search/page", get(search_page_handler))
+        .route("/api/season", get(season_handler))
+        .route("/api/new", get(new_handler))
+        .route("/api/new/page", get(new_page_handler))
+        .route("/api/popular", get(popular_handler))
+        .route("/api/popular/page", get(popular_page_handler))
+        .route("/api/jobs", get(jobs_handler).post(queue_handler).delete(clear_completed_handler))
+        .route("/api/import", post(import_handler))
+        .route("/api/jobs/:job_id", axum::routing::delete(remove_job_handler))
+        .route("/api/jobs/:job_id/pause", post(pause_handler))
+        .route("/api/jobs/:job_id/resume", post(resume_handler))
+        .route("/api/jobs/:job_id/cancel", post(cancel_handler))
+        .route("/api/jobs/:job_id/move", post(move_handler))
+        .route("/api/settings", get(settings_handler).put(update_settings_handler))
+        .route("/api/settings/errors", get(settings_errors_handler))
+        .route(
+            "/api/subscriptions",
+            get(subscriptions_handler).post(subscribe_handler).delete(unsubscribe_handler),
+        )
+        .route("/api/subscriptions/check", post(check_subscriptions_handler))
+        .route("/api/playlists", post(playlist_handler))
+        .route("/api/notifications", get(notifications_handler).delete(clear_notifications_handler))
+        .route("/api/notifications/read", post(read_notifications_handler))
+        .route("/api/watch", get(watch_state_handler))
+        .route("/api/watch/watched", post(mark_watched_handler))
+        .route("/api/watch/position", post(watch_position_handler))
+        .route("/api/watch/shows", get(watch_shows_handler))
+        .route(
+            "/api/watchlist",
+            get(watchlist_handler).put(watchlist_status_handler).delete(watchlist_remove_handler),
+        )
+        .route("/api/watchlist/entry", get(watchlist_entry_handler))
+        .route("/api/watchlist/score", post(watchlist_score_handler))
+        .route("/api/watchlist/notes", post(watchlist_notes_handler))
+        .route("/api/watchlist/export", get(watchlist_export_handler))
+        .route("/api/watchlist/import", post(watchlist_import_handler))
+        .route("/api/events", get(events_handler))
+        .route("/api/shutdown", post(shutdown_handler))
+        .route_layer(middleware::from_fn_with_state(state.clone(), require_token));
+
+    Router::new()
+        .route("/api/health", get(health_handler))
+        .merge(api)
+        .with_state(state)
+}
+
+// ============ HANDLERS ============
+
+/// Error body returned by every endpoint: `{"error": "..."}`
+struct ApiError(StatusCode, String);
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.0, Json(serde_json::json!({ "error": self.1 }))).into_response()
+    }
+}
+
+impl From<anyhow::Error> for ApiError {
+    fn from(e: anyhow::Error) -> Self {
+        ApiError(StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    }
+}
+
+impl From<ScrapeError> for ApiError {
+    fn from(e: ScrapeError) -> Self {
+        ApiError(StatusCode::BAD_GATEWAY, e.to_string())
+    }
+}
+
+type ApiResult<T> = std::result::Result<Json<T>, ApiError>;
+
+#[derive(Deserialize)]
+struct SearchParams {
+    query: String,
+    #[serde(default)]
+    refresh: bool,
+    year_from: Option<u32>,
+    year_to: Option<u32>,
+    /// Comma-separated
+    tags: Option<String>,
+    kind: Option<AnimeKind>,
+    min_episodes: Option<u32>,
+    /// Only read by `/api/search/page`
+    page: Option<u32>,
+    limit: Option<usize>,
+}
+
+impl SearchParams {
+    fn search_query(&self) -> SearchQuery {
+        SearchQuery {
+            text: self.query.clone(),
+            year_from: self.year_from,
+            year_to: self.year_to,
+            tags: self
+                .tags
+                .iter()
+                .flat_map(|t| t.split(','))
+                .map(|t| t.trim().to_string())
+                .filter(|t| !t.is_empty())
+                .collect(),
+            kind: self.kind,
+            min_episodes: self.min_episodes,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct SeasonParams {
+    url: String,
+    #[serde(default)]
+    refresh: bool,
+}
+
+#[derive(Deserialize)]
+struct RefreshParams {
+    #[serde(default)]
+    refresh: bool,
+}
+
+/// `page` counts from 1; `limit` defaults to `DEFAULT_PAGE_LIMIT`
+#[derive(Deserialize)]
+struct PageParams {
+    #[serde(default)]
+    refresh: bool,
+    page: Option<u32>,
+    limit: Option<usize>,
+}
+
+#[derive(Deserialize, Serialize)]
+struct SubscribeRequest {
+    url: String,
+    /// Episodes up to this one are not queued; defaults to the latest released one
+    last_seen_episode: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct UrlParams {
+    url: String,
+}
+
+#[derive(Deserialize, Serialize)]
+struct PlaylistRequest {
+    /// Show folder, or id, season URL or title of a job
+    target: String,
+    /// `m3u8` or `xspf`; both when missing
+    format: Option<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+struct ImportRequest {
+    entries: Vec<ImportEntry>,
+    /// Season pages and download links fetched at once
+    concurrency: usize,
+}
+
+#[derive(Deserialize, Serialize)]
+struct WatchedRequest {
+    url: String,
+    title: String,
+    episode_number: u32,
+    watched: bool,
+}
+
+#[derive(Deserialize, Serialize)]
+struct PositionRequest {
+    url: String,
+    title: String,
+    episode_number: u32,
+    position_secs: u64,
+    duration_secs: Option<u64>,
+}
+
+#[derive(Deserialize)]
+struct ListParams {
+    status: Option<ListStatus>,
+}
+
+#[derive(Deserialize, Serialize)]
+struct ListStatusRequest {
+    url: String,
+    title: String,
+    status: ListStatus,
+}
+
+#[derive(Deserialize, Serialize)]
+struct ScoreRequest {
+    url: String,
+    /// 1 to 10; `None` clears the score
+    score: Option<u8>,
+}
+
+#[derive(Deserialize, Serialize)]
+struct NotesRequest {
+    url: String,
+    notes: String,
+}
+
+/// `json` (the default) or `mal` for the MyAnimeList XML format
+#[derive(Deserialize, Serialize)]
+struct ListExchange {
+    format: Option<String>,
+    /// Only read by the import endpoint
+    #[serde(default)]
+    data: String,
+}
+
+impl ListExchange {
+    fn is_mal(&self) -> bool {
+        self.format.as_deref() == Some("mal")
+    }
+}
+
+#[derive(Deserialize, Serialize, Default)]
+struct TaskSelector {
+    task_id: Option<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+struct MoveRequest {
+    offset: isize,
+}
+
+async fn require_token(State(state): State<Arc<DaemonState>>, request: Request, next: Next) -> Response {
+    let authorized = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(|token| constant_time_eq(token.as_bytes(), state.token.as_bytes()))
+        .unwrap_or(false);
+    if !authorized {
+        return ApiError(StatusCode::UNAUTHORIZED, "Missing or invalid token".to_string()).into_response();
+    }
+    next.run(request).await
+}
+
+async fn health_handler() -> Json<serde_json::Value> {
+    Json(serde_json::json!({
+        "version": env!("CARGO_PKG_VERSION"),
+        "pid": std::process::id(),
+    }))
+}
+
+async fn search_handler(
+    State(state): State<Arc<DaemonState>>,
+    Query(params): Query<SearchParams>,
+) -> ApiResult<Vec<SearchResult>> {
+    let cache = state.manager.get_cached_scraper();
+    Ok(Json(cache.search_with(&params.search_query(), params.refresh).await?))
+}
+
+async fn search_page_handler(
+    State(state): State<Arc<DaemonState>>,
+    Query(params): Query<SearchParams>,
+) -> ApiResult<Page<SearchResult>> {
+    let cache = state.manager.get_cached_scraper();
+    let (page, limit) = (params.page.unwrap_or(1), params.limit.unwrap_or(DEFAULT_PAGE_LIMIT));
+    Ok(Json(cache.search_page(&params.search_query(), page, limit, params.refresh).await?))
+}
+
+async fn season_handler(
+    State(state): State<Arc<DaemonState>>,
+    Query(params): Query<SeasonParams>,
+) -> ApiResult<AnimeInfo> {
+    let cache = state.manager.get_cached_scraper();
+    Ok(Json(cache.get_season(&params.url, params.refresh).await?))
+}
+
+async fn new_handler(
+    State(state): State<Arc<DaemonState>>,
+    Query(params): Query<RefreshParams>,
+) -> ApiResult<Vec<ListEntry>> {
+    Ok(Json(state.manager.get_cached_scraper().get_new(params.refresh).await?))
+}
+
+async fn popular_handler(
+    State(state): State<Arc<DaemonState>>,
+    Query(params): Query<RefreshParams>,
+) -> ApiResult<Vec<ListEntry>> {
+    Ok(Json(state.manager.get_cached_scraper().get_popular(params.refresh).await?))
+}
+
+async fn new_page_handler(
+    State(state): State<Arc<DaemonState>>,
+    Query(params): Query<PageParams>,
+) -> ApiResult<Page<ListEntry>> {
+    let (page, limit) = (params.page.unwrap_or(1), params.limit.unwrap_or(DEFAULT_PAGE_LIMIT));
+    Ok(Json(state.manager.get_cached_scraper().get_new_page(page, limit, params.refresh).await?))
+}
+
+async fn popular_page_handler(
+    State(state): State<Arc<DaemonState>>,
+    Query(params): Query<PageParams>,
+) -> ApiResult<Page<ListEntry>> {
+    let (page, limit) = (params.page.unwrap_or(1), params.limit.unwrap_or(DEFAULT_PAGE_LIMIT));
+    Ok(Json(state.manager.get_cached_scraper().get_popular_page(page, limit, params.refresh).await?))
+}
+
+async fn jobs_handler(State(state): State<Arc<DaemonState>>) -> Json<Vec<DownloadJob>> {
+    Json(state.manager.get_jobs())
+}
+
+/// Add (or merge) a job and start downloading it
+async fn queue_handler(
+    State(state): State<Arc<DaemonState>>,
+    Json(job): Json<DownloadJob>,
+) -> std::result::Result<StatusCode, ApiError> {
+    let job_id = job.id.clone();
+    state.manager.add_job(job);
+    state.manager.start_download(job_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Resolve a download list and queue its jobs; responds with what happened to every entry
+async fn import_handler(
+    State(state): State<Arc<DaemonState>>,
+    Json(body): Json<ImportRequest>,
+) -> ApiResult<ImportReport> {
+    let report = import::resolve_entries(state.manager.clone(), body.entries, body.concurrency).await;
+    for job in &report.jobs {
+        for task in &job.tasks {
+            if let Some(parent) = Path::new(&task.filename).parent() {
+                std::fs::create_dir_all(parent).map_err(anyhow::Error::from)?;
+            }
+        }
+        let job_id = job.id.clone();
+        state.manager.add_job(job.clone());
+        state.manager.start_download(job_id).await?;
+    }
+    Ok(Json(report))
+}
+
+async fn clear_completed_handler(State(state): State<Arc<DaemonState>>) -> StatusCode {
+    state.manager.clear_completed_jobs();
+    StatusCode::NO_CONTENT
+}
+
+async fn remove_job_handler(
+    State(state): State<Arc<DaemonState>>,
+    UrlPath(job_id): UrlPath<String>,
+) -> StatusCode {
+    state.manager.remove_job(&job_id);
+    StatusCode::NO_CONTENT
+}
+
+async fn pause_handler(
+    State(state): State<Arc<DaemonState>>,
+    UrlPath(job_id): UrlPath<String>,
+    body: Option<Json<TaskSelector>>,
+) -> StatusCode {
+    let task_id = body.and_then(|Json(b)| b.task_id);
+    state.manager.pause(job_id, task_id);
+    StatusCode::NO_CONTENT
+}
+
+async fn resume_handler(
+    State(state): State<Arc<DaemonState>>,
+    UrlPath(job_id): UrlPath<String>,
+    body: Option<Json<TaskSelector>>,
+) -> std::result::Result<StatusCode, ApiError> {
+    let task_id = body.and_then(|Json(b)| b.task_id);
+    state
+        .manager
+        .resume(job_id, task_id)
+        .await
+        .map_err(|e| ApiError(StatusCode::NOT_FOUND, e.to_string()))?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn cancel_handler(
+    State(state): State<Arc<DaemonState>>,
+    UrlPath(job_id): UrlPath<String>,
+    body: Option<Json<TaskSelector>>,
+) -> StatusCode {
+    let task_id = body.and_then(|Json(b)| b.task_id);
+    state.manager.cancel(&job_id, task_id.as_deref());
+    StatusCode::NO_CONTENT
+}
+
+async fn move_handler(
+    State(state): State<Arc<DaemonState>>,
+    UrlPath(job_id): UrlPath<String>,
+    Json(body): Json<MoveRequest>,
+) -> StatusCode {
+    state.manager.move_job(&job_id, body.offset);
+    StatusCode::NO_CONTENT
+}
+
+async fn settings_handler(State(state): State<Arc<DaemonState>>) -> Json<Settings> {
+    Json(state.manager.get_settings())
+}
+
+async fn settings_errors_handler(State(state): State<Arc<DaemonState>>) -> Json<Vec<String>> {
+    Json(state.manager.settings_errors())
+}
+
+async fn update_settings_handler(
+    State(state): State<Arc<DaemonState>>,
+    Json(settings): Json<Settings>,
+) -> std::result::Result<StatusCode, ApiError> {
+    settings
+        .validate()
+        .map_err(|e| ApiError(StatusCode::UNPROCESSABLE_ENTITY, e.to_string()))?;
+    state.manager.update_settings(settings)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn subscriptions_handler(State(state): State<Arc<DaemonState>>) -> Json<Vec<Subscription>> {
+    Json(state.subscriptions.list())
+}
+
+async fn subscribe_handler(
+    State(state): State<Arc<DaemonState>>,
+    Json(body): Json<SubscribeRequest>,
+) -> ApiResult<Subscription> {
+    let scraper = state.manager.get_scraper();
+    Ok(Json(state.subscriptions.subscribe(&scraper, &body.url, body.last_seen_episode).await?))
+}
+
+/// Responds with whether the show was followed
+async fn unsubscribe_handler(State(state): State<Arc<DaemonState>>, Query(params): Query<UrlParams>) -> ApiResult<bool> {
+    Ok(Json(state.subscriptions.unsubscribe(&params.url)?))
+}
+
+async fn check_subscriptions_handler(State(state): State<Arc<DaemonState>>) -> Json<Vec<QueuedEpisode>> {
+    Json(state.subscriptions.check_all(&state.manager).await)
+}
+
+/// Write playlists of a show's completed episodes; responds with the written paths
+async fn playlist_handler(
+    State(state): State<Arc<DaemonState>>,
+    Json(body): Json<PlaylistRequest>,
+) -> ApiResult<Vec<PathBuf>> {
+    let formats = match &body.format {
+        Some(format) => vec![format
+            .parse::<PlaylistFormat>()
+            .map_err(|e| ApiError(StatusCode::UNPROCESSABLE_ENTITY, e.to_string()))?],
+        None => PlaylistFormat::ALL.to_vec(),
+    };
+    let folder = playlist::resolve_target(&state.manager, &body.target)
+        .map_err(|e| ApiError(StatusCode::NOT_FOUND, e.to_string()))?;
+    Ok(Json(playlist::export_folder(&state.manager, &folder, &formats).await?))
+}
+
+async fn notifications_handler(State(state): State<Arc<DaemonState>>) -> Json<Vec<Notification>> {
+    Json(state.notifications.list())
+}
+
+async fn read_notifications_handler(State(state): State<Arc<DaemonState>>) -> std::result::Result<StatusCode, ApiError> {
+    state.notifications.mark_all_read()?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn clear_notifications_handler(State(state): State<Arc<DaemonState>>) -> std::result::Result<StatusCode, ApiError> {
+    state.notifications.clear()?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Watch state of every tracked episode of the show at `url`
+async fn watch_state_handler(State(state): State<Arc<DaemonState>>, Query(params): Query<UrlParams>) -> Json<Vec<WatchEntry>> {
+    Json(state.watch_state.episodes(&params.url))
+}
+
+async fn mark_watched_handler(
+    State(state): State<Arc<DaemonState>>,
+    Json(body): Json<WatchedRequest>,
+) -> ApiResult<WatchEntry> {
+    let entry = state.watch_state.mark_watched(&body.url, &body.title, body.episode_number, body.watched)?;
+    Ok(Json(entry))
+}
+
+async fn watch_position_handler(
+    State(state): State<Arc<DaemonState>>,
+    Json(body): Json<PositionRequest>,
+) -> ApiResult<WatchEntry> {
+    let entry = state.watch_state.set_position(
+        &body.url,
+        &body.title,
+        body.episode_number,
+        body.position_secs,
+        body.duration_secs,
+    )?;
+    Ok(Json(entry))
+}
+
+async fn watch_shows_handler(State(state): State<Arc<DaemonState>>) -> Json<Vec<ShowProgress>> {
+    Json(state.watch_state.in_progress_shows())
+}
+
+async fn watchlist_handler(State(state): State<Arc<DaemonState>>, Query(params): Query<ListParams>) -> Json<Vec<WatchlistEntry>> {
+    Json(state.watchlist.list(params.status))
+}
+
+async fn watchlist_entry_handler(
+    State(state): State<Arc<DaemonState>>,
+    Query(params): Query<UrlParams>,
+) -> Json<Option<WatchlistEntry>> {
+    Json(state.watchlist.get(&params.url))
+}
+
+/// Add a show or change its status
+async fn watchlist_status_handler(
+    State(state): State<Arc<DaemonState>>,
+    Json(body): Json<ListStatusRequest>,
+) -> ApiResult<WatchlistEntry> {
+    Ok(Json(state.watchlist.set_status(&body.url, &body.title, body.status)?))
+}
+
+/// Responds with whether the show was listed
+async fn watchlist_remove_handler(State(state): State<Arc<DaemonState>>, Query(params): Query<UrlParams>) -> ApiResult<bool> {
+    Ok(Json(state.watchlist.remove(&params.url)?))
+}
+
+async fn watchlist_score_handler(
+    State(state): State<Arc<DaemonState>>,
+    Json(body): Json<ScoreRequest>,
+) -> ApiResult<WatchlistEntry> {
+    let entry = state
+        .watchlist
+        .set_score(&body.url, body.score)
+        .map_err(|e| ApiError(StatusCode::UNPROCESSABLE_ENTITY, e.to_string()))?;
+    Ok(Json(entry))
+}
+
+async fn watchlist_notes_handler(
+    State(state): State<Arc<DaemonState>>,
+    Json(body): Json<NotesRequest>,
+) -> ApiResult<WatchlistEntry> {
+    let entry = state
+        .watchlist
+        .set_notes(&body.url, &body.notes)
+        .map_err(|e| ApiError(StatusCode::UNPROCESSABLE_ENTITY, e.to_string()))?;
+    Ok(Json(entry))
+}
+
+/// Responds with the exported document as a JSON string
+async fn watchlist_export_handler(
+    State(state): State<Arc<DaemonState>>,
+    Query(params): Query<ListExchange>,
+) -> ApiResult<String> {
+    if params.is_mal() {
+        return Ok(Json(state.watchlist.export_mal_xml()));
+    }
+    Ok(Json(state.watchlist.export_json()?))
+}
+
+/// Merge an exported list; responds with the number of imported entries
+async fn watchlist_import_handler(
+    State(state): State<Arc<DaemonState>>,
+    Json(body): Json<ListExchange>,
+) -> ApiResult<usize> {
+    let imported = if body.is_mal() {
+        state.watchlist.import_mal_xml(&body.data)
+    } else {
+        state.watchlist.import_json(&body.data)
+    };
+    Ok(Json(imported.map_err(|e| ApiError(StatusCode::UNPROCESSABLE_ENTITY, e.to_string()))?))
+}
+
+/// WebSocket that forwards every `ManagerEvent` as a JSON text message
+async fn events_handler(State(state): State<Arc<DaemonState>>, ws: WebSocketUpgrade) -> Response {
+    let events = state.manager.subscribe_events();
+    ws.on_upgrade(move |socket| forward_events(socket, events))
+}
+
+async fn forward_events(mut socket: WebSocket, mut events: broadcast::Receiver<ManagerEvent>) {
+    loop {
+        tokio::select! {
+            event = events.recv() => match event {
+                Ok(event) => {
+                    let Ok(text) = serde_json::to_string(&event) else { continue };
+                    if socket.send(Message::Text(text)).await.is_err() {
+                        break;
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    tracing::warn!("Event stream client skipped {} events", skipped);
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+            incoming = socket.recv() => match incoming {
+                Some(Ok(Message::Close(_))) | None | Some(Err(_)) => break,
+                _ => {}
+            },
+        }
+    }
+}
+
+async fn shutdown_handler(State(state): State<Arc<DaemonState>>) -> StatusCode {
+    state.shutdown.notify_one();
+    StatusCode::NO_CONTENT
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+// ============ CLIENT ============
+
+/// Thin client for a running daemon, used by the CLI and the desktop app
+#[derive(Clone)]
+pub struct DaemonClient {
+    base: reqwest::Url,
+    token: String,
+    http: reqwest::Client,
+}
+
+impl DaemonClient {
+    pub fn new(info: &DaemonInfo) -> Result<Self> {
+        Ok(Self {
+            base: reqwest::Url::parse(&format!("http://{}/", info.address))?,
+            token: info.token.clone(),
+            http: reqwest::Client::new(),
+        })
+    }
+
+    /// Connect to the daemon registered in `daemon.json`, if one is answering
+    pub async fn discover(config_dir: &Path) -> Option<Self> {
+        let info = DaemonInfo::load(config_dir)?;
+        let client = Self::new(&info).ok()?;
+        let health = client
+            .http
+            .get(client.url(&["api", "health"]))
+            .timeout(Duration::from_secs(1))
+            .send()
+            .await
+            .ok()?;
+        health.status().is_success().then_some(client)
+    }
+
+    /// Address the daemon is listening on
+    pub fn address(&self) -> String {
+        self.base.as_str().trim_end_matches('/').to_string()
+    }
+
+    pub async fn search(&self, query: &str, refresh: bool) -> Result<Vec<SearchResult>> {
+        self.search_with(&SearchQuery::new(query), refresh).await
+    }
+
+    pub async fn search_with(&self, query: &SearchQuery, refresh: bool) -> Result<Vec<SearchResult>> {
+        let params = search_params(query, refresh);
+        let params: Vec<(&str, &str)> = params.iter().map(|(name, value)| (*name, value.as_str())).collect();
+        self.get(&["api", "search"], &params).await
+    }
+
+    pub async fn search_page(
+        &self,
+        query: &SearchQuery,
+        page: u32,
+        limit: usize,
+        refresh: bool,
+    ) -> Result<Page<SearchResult>> {
+        let mut params = search_params(query, refresh);
+        params.extend([("page", page.to_string()), ("limit", limit.to_string())]);
+        let params: Vec<(&str, &str)> = params.iter().map(|(name, value)| (*name, value.as_str())).collect();
+        self.get(&["api", "search", "page"], &params).await
+    }
+
+    pub async fn get_season(&self, url: &str, refresh: bool) -> Result<AnimeInfo> {
+        self.get(&["api", "season"], &[("url", url), ("refresh", bool_param(refresh))]).await
+    }
+
+    pub async fn get_new(&self, refresh: bool) -> Result<Vec<ListEntry>> {
+        self.get(&["api", "new"], &[("refresh", bool_param(refresh))]).await
+    }
+
+    pub async fn get_popular(&self, refresh: bool) -> Result<Vec<ListEntry>> {
+        self.get(&["api", "popular"], &[("refresh", bool_param(refresh))]).await
+    }
+
+    pub async fn get_new_page(&self, page: u32, limit: usize, refresh: bool) -> Result<Page<ListEntry>> {
+        let (page, limit) = (page.to_string(), limit.to_string());
+        let params = [("page", page.as_str()), ("limit", limit.as_str()), ("refresh", bool_param(refresh))];
+        self.get(&["api", "new", "page"], &params).await
+    }
+
+    pub async fn get_popular_page(&self, page: u32, limit: usize, refresh: bool) -> Result<Page<ListEntry>> {
+        let (page, limit) = (page.to_string(), limit.to_string());
+        let params = [("page", page.as_str()), ("limit", limit.as_str()), ("refresh", bool_param(refresh))];
+        self.get(&["api", "popular", "page"], &params).await
+    }
+
+    pub async fn get_jobs(&self) -> Result<Vec<DownloadJob>> {
+        self.get(&["api", "jobs"], &[]).await
+    }
+
+    /// Add a job to the daemon's queue and start it
+    pub async fn queue(&self, job: &DownloadJob) -> Result<()> {
+        self.send(self.http.post(self.url(&["api", "jobs"])).json(job)).await
+    }
+
+    pub async fn pause(&self, job_id: &str, task_id: Option<&str>) -> Result<()> {
+        let body = TaskSelector { task_id: task_id.map(str::to_string) };
+        self.send(self.http.post(self.url(&["api", "jobs", job_id, "pause"])).json(&body)).await
+    }
+
+    pub async fn resume(&self, job_id: &str, task_id: Option<&str>) -> Result<()> {
+        let body = TaskSelector { task_id: task_id.map(str::to_string) };
+        self.send(self.http.post(self.url(&["api", "jobs", job_id, "resume"])).json(&body)).await
+    }
+
+    /// Cancel a task, or the whole job when `task_id` is None
+    pub async fn cancel(&self, job_id: &str, task_id: Option<&str>) -> Result<()> {
+        let body = TaskSelector { task_id: task_id.map(str::to_string) };
+        self.send(self.http.post(self.url(&["api", "jobs", job_id, "cancel"])).json(&body)).await
+    }
+
+    /// Move a job towards the front (negative `offset`) or back of the queue
+    pub async fn move_job(&self, job_id: &str, offset: isize) -> Result<()> {
+        let body = MoveRequest { offset };
+        self.send(self.http.post(self.url(&["api", "jobs", job_id, "move"])).json(&body)).await
+    }
+
+    pub async fn remove_job(&self, job_id: &str) -> Result<()> {
+        self.send(self.http.delete(self.url(&["api", "jobs", job_id]))).await
+    }
+
+    /// Resolve a download list on the daemon and queue its jobs there
+    pub async fn import(&self, entries: Vec<ImportEntry>, concurrency: usize) -> Result<ImportReport> {
+        self.post(&["api", "import"], &ImportRequest { entries, concurrency }).await
+    }
+
+    pub async fn clear_completed_jobs(&self) -> Result<()> {
+        self.send(self.http.delete(self.url(&["api", "jobs"]))).await
+    }
+
+    pub async fn get_settings(&self) -> Result<Settings> {
+        self.get(&["api", "settings"], &[]).await
+    }
+
+    /// Invalid settings the daemon replaced by defaults at startup
+    pub async fn get_settings_errors(&self) -> Result<Vec<String>> {
+        self.get(&["api", "settings", "errors"], &[]).await
+    }
+
+    pub async fn update_settings(&self, settings: &Settings) -> Result<()> {
+        self.send(self.http.put(self.url(&["api", "settings"])).json(settings)).await
+    }
+
+    pub async fn get_notifications(&self) -> Result<Vec<Notification>> {
+        self.get(&["api", "notifications"], &[]).await
+    }
+
+    pub async fn mark_notifications_read(&self) -> Result<()> {
+        self.send(self.http.post(self.url(&["api", "notifications", "read"]))).await
+    }
+
+    pub async fn clear_notifications(&self) -> Result<()> {
+        self.send(self.http.delete(self.url(&["api", "notifications"]))).await
+    }
+
+    /// Watch state of every tracked episode of a show
+    pub async fn watch_state(&self, url: &str) -> Result<Vec<WatchEntry>> {
+        self.get(&["api", "watch"], &[("url", url)]).await
+    }
+
+    pub async fn mark_watched(&self, url: &str, title: &str, episode_number: u32, watched: bool) -> Result<WatchEntry> {
+        let body = WatchedRequest {
+            url: url.to_string(),
+            title: title.to_string(),
+            episode_number,
+            watched,
+        };
+        self.post(&["api", "watch", "watched"], &body).await
+    }
+
+    /// Save a playback position; see `WatchStateStore::set_position`
+    pub async fn set_watch_position(
+        &self,
+        url: &str,
+        title: &str,
+        episode_number: u32,
+        position_secs: u64,
+        duration_secs: Option<u64>,
+    ) -> Result<WatchEntry> {
+        let body = PositionRequest {
+            url: url.to_string(),
+            title: title.to_string(),
+            episode_number,
+            position_secs,
+            duration_secs,
+        };
+        self.post(&["api", "watch", "position"], &body).await
+    }
+
+    /// Shows with watch activity, most recent first
+    pub async fn in_progress_shows(&self) -> Result<Vec<ShowProgress>> {
+        self.get(&["api", "watch", "shows"], &[]).await
+    }
+
+    pub async fn get_watchlist(&self, status: Option<ListStatus>) -> Result<Vec<WatchlistEntry>> {
+        let status = status.map(serde_json::to_value).transpose()?;
+        let query: Vec<(&str, &str)> = status.iter().filter_map(|s| Some(("status", s.as_str()?))).collect();
+        self.get(&["api", "watchlist"], &query).await
+    }
+
+    pub async fn get_watchlist_entry(&self, url: &str) -> Result<Option<WatchlistEntry>> {
+        self.get(&["api", "watchlist", "entry"], &[("url", url)]).await
+    }
+
+    /// Add a show to the list or change its status
+    pub async fn set_watchlist_status(&self, url: &str, title: &str, status: ListStatus) -> Result<WatchlistEntry> {
+        let body = ListStatusRequest {
+            url: url.to_string(),
+            title: title.to_string(),
+            status,
+        };
+        let request = self.http.put(self.url(&["api", "watchlist"])).json(&body);
+        Ok(check(request.bearer_auth(&self.token).send().await?).await?.json().await?)
+    }
+
+    pub async fn set_watchlist_score(&self, url: &str, score: Option<u8>) -> Result<WatchlistEntry> {
+        let body = ScoreRequest { url: url.to_string(), score };
+        self.post(&["api", "watchlist", "score"], &body).await
+    }
+
+    pub async fn set_watchlist_notes(&self, url: &str, notes: &str) -> Result<WatchlistEntry> {
+        let body = NotesRequest {
+            url: url.to_string(),
+            notes: notes.to_string(),
+        };
+        self.post(&["api", "watchlist", "notes"], &body).await
+    }
+
+    /// Remove a show from the list. Returns false if it wasn't listed.
+    pub async fn remove_from_watchlist(&self, url: &str) -> Result<bool> {
+        let request = self.http.delete(self.url(&["api", "watchlist"])).query(&[("url", url)]);
+        Ok(check(request.bearer_auth(&self.token).send().await?).await?.json().await?)
+    }
+
+    /// The list as JSON, or in the MyAnimeList XML format when `mal` is set
+    pub async fn export_watchlist(&self, mal: bool) -> Result<String> {
+        self.get(&["api", "watchlist", "export"], &[("format", if mal { "mal" } else { "json" })]).await
+    }
+
+    /// Merge an exported list. Returns the number of imported entries.
+    pub async fn import_watchlist(&self, data: &str, mal: bool) -> Result<usize> {
+        let body = ListExchange {
+            format: Some(if mal { "mal" } else { "json" }.to_string()),
+            data: data.to_string(),
+        };
+        self.post(&["api", "watchlist", "import"], &body).await
+    }
+
+    pub async fn get_subscriptions(&self) -> Result<Vec<Subscription>> {
+        self.get(&["api", "subscriptions"], &[]).await
+    }
+
+    /// Follow a show; see `SubscriptionManager::subscribe`
+    pub async fn subscribe(&self, url: &str, last_seen_episode: Option<u32>) -> Result<Subscription> {
+        let body = SubscribeRequest {
+            url: url.to_string(),
+            last_seen_episode,
+        };
+        self.post(&["api", "subscriptions"], &body).await
+    }
+
+    /// Stop following a show. Returns false if it wasn't followed.
+    pub async fn unsubscribe(&self, url: &str) -> Result<bool> {
+        let request = self.http.delete(self.url(&["api", "subscriptions"])).query(&[("url", url)]);
+        Ok(check(request.bearer_auth(&self.token).send().await?).await?.json().await?)
+    }
+
+    /// Check followed shows now; the daemon queues and starts their new episodes
+    pub async fn check_subscriptions(&self) -> Result<Vec<QueuedEpisode>> {
+        self.post(&["api", "subscriptions", "check"], &serde_json::json!({})).await
+    }
+
+    /// Write playlists of a show folder, or of the job with this id, season URL or title
+    pub async fn export_playlist(&self, target: &str, format: Option<PlaylistFormat>) -> Result<Vec<PathBuf>> {
+        let body = PlaylistRequest {
+            target: target.to_string(),
+            format: format.map(|f| f.extension().to_string()),
+        };
+        self.post(&["api", "playlists"], &body).await
+    }
+
+    pub async fn shutdown(&self) -> Result<()> {
+        self.send(self.http.post(self.url(&["api", "shutdown"]))).await
+    }
+
+    /// Subscribe to the daemon's event stream. The channel closes when the connection drops.
+    pub async fn events(&self) -> Result<mpsc::Receiver<ManagerEvent>> {
+        use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+
+        let mut url = self.url(&["api", "events"]);
+        url.set_scheme("ws").map_err(|_| anyhow!("Invalid daemon address"))?;
+        let mut request = url.as_str().into_client_request()?;
+        request
+            .headers_mut()
+            .insert("Authorization", format!("Bearer {}", self.token).parse()?);
+
+        let (mut socket, _) = tokio_tungstenite::connect_async(request).await?;
+        let (tx, rx) = mpsc::channel(64);
+        tokio::spawn(async move {
+            while let Some(Ok(message)) = socket.next().await {
+                let tokio_tungstenite::tungstenite::Message::Text(text) = message else {
+                    continue;
+                };
+                if let Ok(event) = serde_json::from_str::<ManagerEvent>(&text) {
+                    if tx.send(event).await.is_err() {
+                        break;
+                    }
+                }
+            }
+        });
+        Ok(rx)
+    }
+
+    fn url(&self, segments: &[&str]) -> reqwest::Url {
+        let mut url = self.base.clone();
+        if let Ok(mut path) = url.path_segments_mut() {
+            path.pop_if_empty().extend(segments);
+        }
+        url
+    }
+
+    async fn get<T: DeserializeOwned>(&self, segments: &[&str], query: &[(&str, &str)]) -> Result<T> {
+        let response = self
+            .http
+            .get(self.url(segments))
+            .query(query)
+            .bearer_auth(&self.token)
+            .send()
+            .await?;
+        Ok(check(response).await?.json().await?)
+    }
+
+    async fn post<T: DeserializeOwned, B: Serialize>(&self, segments: &[&str], body: &B) -> Result<T> {
+        let request = self.http.post(self.url(segments)).json(body).bearer_auth(&self.token);
+        Ok(check(request.send().await?).await?.json().await?)
+    }
+
+    async fn send(&self, request: reqwest::RequestBuilder) -> Result<()> {
+        check(request.bearer_auth(&self.token).send().await?).await?;
+        Ok(())
+    }
+}
+
+/// Query parameters of `SearchParams` for `query`
+fn search_params(query: &SearchQuery, refresh: bool) -> Vec<(&'static str, String)> {
+    let mut params = vec![("query", query.text.clone()), ("refresh", bool_param(refresh).to_string())];
+    let numbers = [("year_from", query.year_from), ("year_to", query.year_to), ("min_episodes", query.min_episodes)];
+    params.extend(numbers.into_iter().filter_map(|(name, value)| Some((name, value?.to_string()))));
+    if !query.tags.is_empty() {
+        params.push(("tags", query.tags.join(",")));
+    }
+    if let Some(kind) = query.kind {
+        params.push(("kind", kind.to_string()));
+    }
+    params
+}
+
+fn bool_param(value: bool) -> &'static str {
+    if value {
+        "true"
+    } else {
+        "false"
+    }
+}
+
+/// Turn an `{"error": ...}` response into an error
+async fn check(response: reqwest::Response) -> Result<reqwest::Response> {
+    if response.status().is_success() {
+        return Ok(response);
+    }
+    let status = response.status();
+    let message = response
+        .json::<serde_json::Value>()
+        .await
+        .ok()
+        .and_then(|v| v.get("error").and_then(|e| e.as_str()).map(str::to_string))
+        .unwrap_or_else(|| status.to_string());
+    Err(anyhow!("Daemon error ({}): {}", status.as_u16(), message))
+}
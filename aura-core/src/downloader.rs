@@ -53,7 +53,7 @@ pub async fn get_content_length(
     if get_resp.status().is_success() {
         if let Some(range) = get_resp.headers().get(reqwest::header::CONTENT_RANGE) {
             let range_str = range.to_str()?;
-            if let Some(total) = range_str.split('/').last() {
+            if let Some(total) = range_str.split('/').next_back() {
                 return Ok(total.parse::<u64>()?);
             }
         }
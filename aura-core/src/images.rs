@@ -0,0 +1,225 @@
+use crate::network::HttpClients;
+use crate::provider::Provider;
+use crate::scraper::absolute_url;
+use anyhow::{bail, Result};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Thumbnail widths are clamped to this range to bound the number of variants on disk
+#[cfg(feature = "thumbnails")]
+const THUMBNAIL_WIDTH_RANGE: std::ops::RangeInclusive<u32> = 32..=1024;
+
+/// A cover image served from the local cache
+#[derive(Debug, Clone)]
+pub struct CachedImage {
+    pub path: PathBuf,
+    pub content_type: &'static str,
+    pub bytes: Vec<u8>,
+}
+
+/// Downloads cover images once and keeps them content-addressed on disk.
+/// `index.json` maps source URLs to stored files so covers work offline.
+pub struct ImageCache {
+    clients: Arc<HttpClients>,
+    dir: PathBuf,
+    index: Mutex<HashMap<String, String>>,
+    /// Resolves relative paths and names the site in `Referer`
+    provider: Provider,
+}
+
+impl ImageCache {
+    /// Store images under `<config dir>/cache/images`. Relative paths resolve against the
+    /// provider definition in `<config dir>/providers`. Downloads use `clients`, so they follow
+    /// the network settings and user agent.
+    pub fn new(config_dir: &Path, clients: Arc<HttpClients>) -> Result<Self> {
+        let dir = config_dir.join("cache").join("images");
+        fs::create_dir_all(&dir)?;
+
+        let index_path = dir.join("index.json");
+        let index: HashMap<String, String> = if index_path.exists() {
+            let data = fs::read_to_string(&index_path)?;
+            serde_json::from_str(&data).unwrap_or_default()
+        } else {
+            HashMap::new()
+        };
+
+        Ok(Self {
+            clients,
+            dir,
+            index: Mutex::new(index),
+            provider: Provider::load_or_builtin(config_dir),
+        })
+    }
+
+    /// Path of an already cached image, without touching the network
+    pub fn cached_path(&self, url: &str) -> Option<PathBuf> {
+        let url = absolute_url(&self.provider, url);
+        let file = self.index.lock().unwrap().get(&url).cloned()?;
+        let path = self.dir.join(file);
+        path.exists().then_some(path)
+    }
+
+    /// Return the cached image, downloading it first if needed.
+    /// Relative paths (as found in `SearchResult.image`) are resolved against the site.
+    pub async fn get(&self, url: &str) -> Result<CachedImage> {
+        if let Some(path) = self.cached_path(url) {
+            let bytes = tokio::fs::read(&path).await?;
+            return Ok(CachedImage {
+                content_type: sniff_content_type(&bytes).0,
+                path,
+                bytes,
+            });
+        }
+        self.download(url).await
+    }
+
+    /// Return a copy of the image scaled down to at most `max_width` pixels wide
+    #[cfg(feature = "thumbnails")]
+    pub async fn thumbnail(&self, url: &str, max_width: u32) -> Result<CachedImage> {
+        let original = self.get(url).await?;
+        let width = max_width.clamp(*THUMBNAIL_WIDTH_RANGE.start(), *THUMBNAIL_WIDTH_RANGE.end());
+
+        let stem = original
+            .path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let thumb_path = self.dir.join("thumbs").join(format!("{}_{}.jpg", stem, width));
+        if thumb_path.exists() {
+            let bytes = tokio::fs::read(&thumb_path).await?;
+            return Ok(CachedImage {
+                path: thumb_path,
+                content_type: "image/jpeg",
+                bytes,
+            });
+        }
+
+        let bytes = original.bytes.clone();
+        let resized = tokio::task::spawn_blocking(move || -> Result<Option<Vec<u8>>> {
+            let img = image::load_from_memory(&bytes)?;
+            if img.width() <= width {
+                return Ok(None);
+            }
+            let thumb = img.resize(width, u32::MAX, image::imageops::FilterType::Triangle);
+            let mut out = std::io::Cursor::new(Vec::new());
+            thumb.to_rgb8().write_to(&mut out, image::ImageFormat::Jpeg)?;
+            Ok(Some(out.into_inner()))
+        })
+        .await??;
+
+        match resized {
+            Some(bytes) => {
+                if let Some(parent) = thumb_path.parent() {
+                    tokio::fs::create_dir_all(parent).await?;
+                }
+                tokio::fs::write(&thumb_path, &bytes).await?;
+                Ok(CachedImage {
+                    path: thumb_path,
+                    content_type: "image/jpeg",
+                    bytes,
+                })
+            }
+            // Already small enough
+            None => Ok(original),
+        }
+    }
+
+    /// Without the `thumbnails` feature the original image is returned
+    #[cfg(not(feature = "thumbnails"))]
+    pub async fn thumbnail(&self, url: &str, _max_width: u32) -> Result<CachedImage> {
+        self.get(url).await
+    }
+
+    /// Size of the cache on disk in bytes
+    pub fn disk_usage(&self) -> u64 {
+        fn dir_size(path: &Path) -> u64 {
+            fs::read_dir(path)
+                .map(|entries| {
+                    entries
+                        .flatten()
+                        .map(|e| match e.metadata() {
+                            Ok(m) if m.is_dir() => dir_size(&e.path()),
+                            Ok(m) => m.len(),
+                            Err(_) => 0,
+                        })
+                        .sum()
+                })
+                .unwrap_or(0)
+        }
+        dir_size(&self.dir)
+    }
+
+    /// Remove every cached image
+    pub fn clear(&self) -> Result<()> {
+        self.index.lock().unwrap().clear();
+        fs::remove_dir_all(&self.dir)?;
+        fs::create_dir_all(&self.dir)?;
+        Ok(())
+    }
+
+    async fn download(&self, url: &str) -> Result<CachedImage> {
+        let url = absolute_url(&self.provider, url);
+        let _permit = self.clients.throttle(&url).await;
+        let resp = self
+            .clients
+            .scraper()?
+            .get(&url)
+            .header(reqwest::header::REFERER, &self.provider.base_url)
+            .send()
+            .await?;
+
+        let status = resp.status();
+        if !status.is_success() {
+            bail!("Image download failed: {}", status);
+        }
+        let bytes = resp.bytes().await?.to_vec();
+        let (content_type, ext) = sniff_content_type(&bytes);
+        if ext == "bin" {
+            bail!("Not an image: {}", url);
+        }
+
+        let hash = Sha256::digest(&bytes)
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect::<String>();
+        let file = format!("{}.{}", hash, ext);
+        let path = self.dir.join(&file);
+        if !path.exists() {
+            tokio::fs::write(&path, &bytes).await?;
+        }
+
+        self.index.lock().unwrap().insert(url, file);
+        self.save_index();
+
+        Ok(CachedImage {
+            path,
+            content_type,
+            bytes,
+        })
+    }
+
+    fn save_index(&self) {
+        let index = self.index.lock().unwrap();
+        if let Ok(data) = serde_json::to_string_pretty(&*index) {
+            let _ = fs::write(self.dir.join("index.json"), data);
+        }
+    }
+}
+
+/// Detect the image type from its magic bytes, returning (content type, file extension)
+fn sniff_content_type(bytes: &[u8]) -> (&'static str, &'static str) {
+    if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        ("image/jpeg", "jpg")
+    } else if bytes.starts_with(b"\x89PNG") {
+        ("image/png", "png")
+    } else if bytes.starts_with(b"GIF8") {
+        ("image/gif", "gif")
+    } else if bytes.len() >= 12 && &bytes[0..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+        ("image/webp", "webp")
+    } else {
+        ("application/octet-stream", "bin")
+    }
+}
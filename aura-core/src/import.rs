@@ -0,0 +1,371 @@
+use crate::manager::{safe_file_name, DownloadManager};
+use crate::metadata::jellyfin_episode_filename;
+use crate::models::{AnimeInfo, DownloadJob, DownloadTask, Episode, TaskStatus};
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+/// One line of a download list: a season page with an optional episode range,
+/// or a direct video URL saved under the given file name
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum ImportEntry {
+    Season { url: String, episodes: Option<String> },
+    Video { url: String, filename: String },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListFormat {
+    Toml,
+    Json,
+    Text,
+}
+
+impl ListFormat {
+    /// `.toml` and `.json` by extension, plain text otherwise
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()).map(|e| e.to_lowercase()).as_deref() {
+            Some("toml") => ListFormat::Toml,
+            Some("json") => ListFormat::Json,
+            _ => ListFormat::Text,
+        }
+    }
+}
+
+/// Entry as written in TOML (`[[entry]]` tables) and JSON (an array of objects)
+#[derive(Deserialize)]
+struct RawEntry {
+    url: String,
+    #[serde(default)]
+    episodes: Option<String>,
+    #[serde(default)]
+    filename: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TomlList {
+    #[serde(default)]
+    entry: Vec<RawEntry>,
+}
+
+impl RawEntry {
+    fn into_entry(self) -> ImportEntry {
+        match self.filename {
+            Some(filename) => ImportEntry::Video { url: self.url, filename },
+            None => ImportEntry::Season { url: self.url, episodes: self.episodes },
+        }
+    }
+}
+
+/// Parse a download list.
+///
+/// Text lists have one entry per line: `<season url> [episodes]` or `<video url> <file name>`.
+/// Blank lines and lines starting with `#` are ignored.
+pub fn parse_list(text: &str, format: ListFormat) -> Result<Vec<ImportEntry>> {
+    let raw: Vec<RawEntry> = match format {
+        ListFormat::Toml => toml::from_str::<TomlList>(text)?.entry,
+        ListFormat::Json => serde_json::from_str(text)?,
+        ListFormat::Text => return parse_text_list(text),
+    };
+    for entry in &raw {
+        check_url(&entry.url)?;
+    }
+    Ok(raw.into_iter().map(RawEntry::into_entry).collect())
+}
+
+fn parse_text_list(text: &str) -> Result<Vec<ImportEntry>> {
+    let mut entries = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (url, rest) = match line.split_once(char::is_whitespace) {
+            Some((url, rest)) => (url, rest.trim()),
+            None => (line, ""),
+        };
+        check_url(url).with_context(|| format!("Line {}", index + 1))?;
+
+        let is_range = rest.chars().all(|c| c.is_ascii_digit() || matches!(c, ',' | '-' | ' '));
+        entries.push(if rest.is_empty() || is_range {
+            ImportEntry::Season {
+                url: url.to_string(),
+                episodes: (!rest.is_empty()).then(|| rest.to_string()),
+            }
+        } else {
+            ImportEntry::Video {
+                url: url.to_string(),
+                filename: rest.to_string(),
+            }
+        });
+    }
+    Ok(entries)
+}
+
+fn check_url(url: &str) -> Result<()> {
+    if url.starts_with("http://") || url.starts_with("https://") {
+        Ok(())
+    } else {
+        Err(anyhow!("'{}' is not an http(s) URL", url))
+    }
+}
+
+/// Episode numbers from a selection like `1-4, 6`. Invalid parts are ignored.
+pub fn parse_episode_range(input: &str) -> HashSet<u32> {
+    let mut selection = HashSet::new();
+
+    for part in input.split(',') {
+        let part = part.trim();
+        if part.is_empty() {
+            continue;
+        }
+
+        if part.contains('-') {
+            let range_parts: Vec<&str> = part.split('-').collect();
+            if range_parts.len() == 2 {
+                if let (Ok(start), Ok(end)) = (
+                    range_parts[0].trim().parse::<u32>(),
+                    range_parts[1].trim().parse::<u32>(),
+                ) {
+                    for i in start..=end {
+                        selection.insert(i);
+                    }
+                }
+            }
+        } else if let Ok(num) = part.parse::<u32>() {
+            selection.insert(num);
+        }
+    }
+    selection
+}
+
+/// What happened to one episode (or video) of the list
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportOutcome {
+    /// URL of the list entry
+    pub source: String,
+    pub title: String,
+    pub episode: Option<u32>,
+    /// Why it was skipped or failed; the file path when queued
+    pub detail: String,
+}
+
+/// Jobs built from a list, ready to be queued, and a summary of every entry
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ImportReport {
+    #[serde(skip)]
+    pub jobs: Vec<DownloadJob>,
+    pub queued: Vec<ImportOutcome>,
+    pub skipped: Vec<ImportOutcome>,
+    pub failed: Vec<ImportOutcome>,
+}
+
+impl ImportReport {
+    fn merge(&mut self, other: ImportReport) {
+        self.jobs.extend(other.jobs);
+        self.queued.extend(other.queued);
+        self.skipped.extend(other.skipped);
+        self.failed.extend(other.failed);
+    }
+}
+
+/// Resolve every entry into `DownloadJob`s. Season pages and download links are fetched with
+/// at most `concurrency` requests in flight. Episodes that are already downloaded or queued
+/// are skipped. Nothing is added to the manager; queue `report.jobs` afterwards.
+pub async fn resolve_entries(
+    manager: Arc<DownloadManager>,
+    entries: Vec<ImportEntry>,
+    concurrency: usize,
+) -> ImportReport {
+    let limit = Arc::new(Semaphore::new(concurrency.max(1)));
+    let mut set = JoinSet::new();
+    for entry in entries {
+        let manager = manager.clone();
+        let limit = limit.clone();
+        set.spawn(async move {
+            match entry {
+                ImportEntry::Season { url, episodes } => {
+                    resolve_season(manager, limit, url, episodes).await
+                }
+                ImportEntry::Video { url, filename } => resolve_video(&manager, url, filename),
+            }
+        });
+    }
+
+    let mut report = ImportReport::default();
+    while let Some(result) = set.join_next().await {
+        match result {
+            Ok(part) => report.merge(part),
+            Err(e) => tracing::error!("Import task failed: {}", e),
+        }
+    }
+    report
+}
+
+async fn resolve_season(
+    manager: Arc<DownloadManager>,
+    limit: Arc<Semaphore>,
+    url: String,
+    episodes: Option<String>,
+) -> ImportReport {
+    let mut report = ImportReport::default();
+    let outcome = |title: &str, episode: Option<u32>, detail: String| ImportOutcome {
+        source: url.clone(),
+        title: title.to_string(),
+        episode,
+        detail,
+    };
+
+    let info = {
+        let _permit = limit.acquire().await;
+        manager.get_cached_scraper().get_season(&url, false).await
+    };
+    let info = match info {
+        Ok(info) => info,
+        Err(e) => {
+            report.failed.push(outcome(&url, None, e.to_string()));
+            return report;
+        }
+    };
+
+    let selection = episodes.as_deref().map(parse_episode_range);
+    let selected: Vec<Episode> = info
+        .episodes
+        .iter()
+        .filter(|ep| selection.as_ref().is_none_or(|s| s.contains(&ep.number)))
+        .cloned()
+        .collect();
+    if selected.is_empty() {
+        let detail = format!("No released episode matches '{}'", episodes.unwrap_or_default());
+        report.failed.push(outcome(&info.title, None, detail));
+        return report;
+    }
+
+    let settings = manager.get_settings();
+    let folder = settings.download_dir.join(safe_file_name(&info.title));
+    let mut links = JoinSet::new();
+    for ep in selected {
+        if manager.is_episode_downloaded(&folder, ep.number) {
+            report.skipped.push(outcome(&info.title, Some(ep.number), "already downloaded".to_string()));
+            continue;
+        }
+        match manager.is_episode_in_queue(&info.url, ep.number) {
+            Some(TaskStatus::Error(_)) | None => {}
+            Some(_) => {
+                report.skipped.push(outcome(&info.title, Some(ep.number), "already queued".to_string()));
+                continue;
+            }
+        }
+        if ep.gate_id.is_empty() {
+            report.failed.push(outcome(&info.title, Some(ep.number), "Gate ID is empty".to_string()));
+            continue;
+        }
+
+        let scraper = manager.get_scraper();
+        let limit = limit.clone();
+        links.spawn(async move {
+            let _permit = limit.acquire().await;
+            let link = scraper.get_download_link(&ep).await;
+            (ep, link)
+        });
+    }
+
+    let mut tasks = Vec::new();
+    while let Some(result) = links.join_next().await {
+        let Ok((ep, link)) = result else {
+            continue;
+        };
+        match link {
+            Ok(link) => {
+                let path = folder.join(episode_file_name(&info, ep.number, settings.metadata.jellyfin_naming));
+                report.queued.push(outcome(&info.title, Some(ep.number), path.to_string_lossy().to_string()));
+                tasks.push(DownloadTask {
+                    id: format!("{}-ep{}", info.url, ep.number),
+                    url: link,
+                    filename: path.to_string_lossy().to_string(),
+                    status: TaskStatus::Pending,
+                    progress_bytes: 0,
+                    total_bytes: 0,
+                    episode_url: Some(ep.url.clone()),
+                    gate_id: Some(ep.gate_id.clone()),
+                    episode_number: Some(ep.number),
+                    segments: vec![],
+                });
+            }
+            Err(e) => report.failed.push(outcome(&info.title, Some(ep.number), e.to_string())),
+        }
+    }
+
+    if !tasks.is_empty() {
+        tasks.sort_by_key(|t| t.episode_number);
+        report.jobs.push(DownloadJob {
+            id: info.url.clone(),
+            name: info.title.clone(),
+            tasks,
+            anime_url: Some(info.url.clone()),
+            cover_url: None,
+        });
+    }
+    report
+}
+
+fn resolve_video(manager: &DownloadManager, url: String, filename: String) -> ImportReport {
+    let mut report = ImportReport::default();
+    let requested = PathBuf::from(&filename);
+    let path = if requested.is_absolute() {
+        requested
+    } else {
+        manager.get_settings().download_dir.join(requested)
+    };
+    let title = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| filename.clone());
+    let outcome = ImportOutcome {
+        source: url.clone(),
+        title: title.clone(),
+        episode: None,
+        detail: path.to_string_lossy().to_string(),
+    };
+
+    if path.exists() {
+        report.skipped.push(ImportOutcome {
+            detail: "already downloaded".to_string(),
+            ..outcome
+        });
+        return report;
+    }
+
+    report.queued.push(outcome);
+    report.jobs.push(DownloadJob {
+        id: url.clone(),
+        name: title,
+        tasks: vec![DownloadTask {
+            id: format!("{}-video", url),
+            url,
+            filename: path.to_string_lossy().to_string(),
+            status: TaskStatus::Pending,
+            progress_bytes: 0,
+            total_bytes: 0,
+            episode_url: None,
+            gate_id: None,
+            episode_number: None,
+            segments: vec![],
+        }],
+        anime_url: None,
+        cover_url: None,
+    });
+    report
+}
+
+fn episode_file_name(info: &AnimeInfo, number: u32, jellyfin_naming: bool) -> String {
+    if jellyfin_naming {
+        jellyfin_episode_filename(&info.title, number)
+    } else {
+        format!("Ep{:02}.mp4", number)
+    }
+}
@@ -2,25 +2,89 @@
 //
 // Modules:
 // - scraper: Website parsing and link extraction
+// - provider: Site selectors, patterns and URLs, with a config-dir override
+// - search: Search filters and fuzzy relevance ranking
+// - cache: TTL cache in front of the scraper
+// - images: Local cover image and thumbnail cache
 // - downloader: Async file download primitives
+// - network: HTTP clients built from the network settings (proxy, timeouts, TLS)
 // - manager: Download queue management
+// - bundle: Portable queue export for moving to another machine
 // - models: Shared data structures
-// - config: User settings
+// - config: User settings, layered from files, environment and command line
+// - migrations: Schema versions of settings.toml and jobs.json
+// - store: JSON list files in the config directory
+// - subscriptions: Followed shows and new episode polling
+// - notifications: Notification feed built from manager events
+// - watch_state: Watched episodes and playback positions
+// - watchlist: Personal anime list with statuses, scores and notes
+// - metadata: NFO and poster sidecars for media servers
+// - playlist: M3U8 and XSPF playlists for downloaded shows
+// - import: Batch download lists (TOML, JSON or text)
+// - streaming: LAN HTTP server for the library (feature "streaming")
+// - daemon: Headless control API and its client (feature "daemon")
 
 pub mod scraper;
+pub mod provider;
+pub mod search;
+pub mod cache;
+pub mod images;
 pub mod downloader;
+pub mod network;
 pub mod manager;
+pub mod bundle;
 pub mod models;
 pub mod config;
+pub mod migrations;
 pub mod logging;
+mod store;
+pub mod subscriptions;
+pub mod notifications;
+pub mod watch_state;
+pub mod watchlist;
+pub mod metadata;
+pub mod playlist;
+pub mod import;
+#[cfg(feature = "streaming")]
+pub mod streaming;
+#[cfg(feature = "daemon")]
+pub mod daemon;
 
 // Re-export main types for convenience
-pub use config::Settings;
+pub use bundle::QueueBundle;
+pub use cache::CachedScraper;
+pub use config::{
+    CacheSettings, ConfigLayers, ConfigSource, DaemonSettings, IpVersion, MetadataSettings, NetworkSettings,
+    Settings, SettingsError, SettingsOrigins, StreamingSettings,
+};
+pub use images::{CachedImage, ImageCache};
 pub use manager::DownloadManager;
 pub use models::{
     // Scraper models
-    AnimeInfo, Episode, SearchResult, ListEntry, ScrapeError,
+    AnimeInfo, AiringStatus, Episode, SearchResult, ListEntry, Page, ScrapeError,
     // Download models
     DownloadJob, DownloadTask, TaskStatus, PauseReason, Segment, SegmentStatus,
+    // Subscription & event models
+    Subscription, ManagerEvent, Notification, NotificationKind,
+    // Watch state models
+    WatchEntry, ShowProgress,
+    // Watchlist models
+    ListStatus, WatchlistEntry,
 };
+pub use provider::Provider;
 pub use scraper::AnimeScraper;
+pub use search::{AnimeKind, SearchQuery};
+pub use subscriptions::SubscriptionManager;
+pub use notifications::{NotificationCenter, NotificationSink};
+pub use watch_state::WatchStateStore;
+pub use watchlist::WatchlistStore;
+pub use metadata::{LibraryReport, MetadataWriter};
+pub use playlist::PlaylistFormat;
+
+/// Current time as a unix timestamp in seconds
+pub(crate) fn unix_timestamp() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
@@ -1,47 +1,155 @@
-use crate::config::Settings;
+use crate::bundle::QueueBundle;
+use crate::migrations;
+use crate::network::{next_chunk, HttpClients};
+use crate::cache::CachedScraper;
+use crate::config::{ConfigLayers, LayeredSettings, Settings, SettingsOrigins};
 use crate::downloader::get_content_length;
 use crate::models::{
-    DownloadJob, Episode, PauseReason, Segment, SegmentStatus, TaskStatus,
+    DownloadJob, DownloadTask, Episode, ManagerEvent, PauseReason, Segment, SegmentStatus, TaskStatus,
 };
+use crate::provider::Provider;
 use crate::scraper::AnimeScraper;
 use anyhow::{anyhow, Result};
+use std::collections::HashSet;
 use std::fs;
-use std::sync::{atomic::{AtomicU64, Ordering}, Arc, Mutex, RwLock};
+use std::sync::{atomic::{AtomicU64, AtomicUsize, Ordering}, Arc, Mutex, RwLock};
 use std::time::Duration;
-use tokio::sync::Semaphore;
+use tokio::sync::{broadcast, Notify, OwnedSemaphorePermit, Semaphore};
 
 const MAX_LINK_REFRESH_ATTEMPTS: u32 = 3;
+const EVENT_CHANNEL_CAPACITY: usize = 64;
+/// How often `watch_settings` looks at `settings.toml`
+const SETTINGS_WATCH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Hands out download slots in queue order. A task only keeps a permit when no waiting task of
+/// an earlier job (or earlier in the same job) is waiting too, so `move_job` decides which
+/// episodes start next.
+struct SlotQueue {
+    semaphore: Arc<Semaphore>,
+    /// Number of slots, including those in use
+    limit: AtomicUsize,
+    waiting: Mutex<HashSet<String>>,
+    changed: Notify,
+}
+
+impl SlotQueue {
+    fn new(permits: usize) -> Self {
+        Self {
+            semaphore: Arc::new(Semaphore::new(permits)),
+            limit: AtomicUsize::new(permits),
+            waiting: Mutex::new(HashSet::new()),
+            changed: Notify::new(),
+        }
+    }
+
+    /// Change the number of slots. Running downloads keep their slot; when shrinking,
+    /// slots are retired as they are released.
+    fn resize(&self, permits: usize) {
+        let previous = self.limit.swap(permits, Ordering::SeqCst);
+        if permits > previous {
+            self.semaphore.add_permits(permits - previous);
+            return;
+        }
+        let excess = (previous - permits) - self.semaphore.forget_permits(previous - permits);
+        if excess == 0 {
+            return;
+        }
+        let semaphore = self.semaphore.clone();
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) => {
+                handle.spawn(async move {
+                    if let Ok(permits) = semaphore.acquire_many_owned(excess as u32).await {
+                        permits.forget();
+                    }
+                });
+            }
+            Err(_) => tracing::warn!("Concurrency limit lowered outside a runtime; applies after restart"),
+        }
+    }
+
+    async fn acquire(&self, jobs: &Mutex<Vec<DownloadJob>>, task_id: &str) -> Result<OwnedSemaphorePermit> {
+        self.waiting.lock().unwrap().insert(task_id.to_string());
+        let permit = loop {
+            let permit = match self.semaphore.clone().acquire_owned().await {
+                Ok(permit) => permit,
+                Err(e) => {
+                    self.waiting.lock().unwrap().remove(task_id);
+                    return Err(anyhow!("Semaphore closed: {}", e));
+                }
+            };
+            let first = {
+                let waiting = self.waiting.lock().unwrap();
+                let jobs = jobs.lock().unwrap();
+                jobs.iter()
+                    .flat_map(|j| &j.tasks)
+                    .find(|t| waiting.contains(&t.id))
+                    .map(|t| t.id.clone())
+            };
+            if first.is_none_or(|id| id == task_id) {
+                break permit;
+            }
+            // Let the task ahead of us take the slot
+            drop(permit);
+            self.changed.notify_waiters();
+            let _ = tokio::time::timeout(Duration::from_millis(500), self.changed.notified()).await;
+        };
+        self.waiting.lock().unwrap().remove(task_id);
+        self.changed.notify_waiters();
+        Ok(permit)
+    }
+}
 
 pub struct DownloadManager {
     pub settings: Arc<RwLock<Settings>>,
+    /// Layers the settings were merged from, used to save changes to the user file only
+    config: Mutex<LayeredSettings>,
     jobs: Arc<Mutex<Vec<DownloadJob>>>,
-    semaphore: Arc<Semaphore>,
+    slots: Arc<SlotQueue>,
     jobs_path: String,
     scraper: Arc<AnimeScraper>,
+    cached_scraper: Arc<CachedScraper>,
+    clients: Arc<HttpClients>,
+    events: broadcast::Sender<ManagerEvent>,
+    /// Tasks someone is watching while they download; their segments are fetched in order
+    streaming_tasks: Arc<Mutex<HashSet<String>>>,
 }
 
 impl DownloadManager {
     pub fn new(custom_config_path: Option<String>) -> Result<Self> {
-        let config_dir = if let Some(path) = custom_config_path {
-            std::path::PathBuf::from(path)
-        } else {
-            dirs::config_dir()
-                .ok_or_else(|| anyhow!("Could not find config directory"))?
-                .join("aura")
-        };
+        Self::with_layers(custom_config_path, ConfigLayers::discover())
+    }
+
+    /// Like `new`, with explicit system/project files, environment and command line overrides
+    pub fn with_layers(custom_config_path: Option<String>, layers: ConfigLayers) -> Result<Self> {
+        let config_dir = resolve_config_dir(custom_config_path)?;
 
         fs::create_dir_all(&config_dir)?;
 
-        let settings = Settings::load(Some(&config_dir))?;
+        let config = LayeredSettings::load_or_defaults(&config_dir, &layers)?;
+        let settings = config.settings.clone();
         let max_concurrent = settings.max_concurrent_downloads;
-        let semaphore = Arc::new(Semaphore::new(max_concurrent));
-        let scraper = Arc::new(AnimeScraper::new());
+        let slots = Arc::new(SlotQueue::new(max_concurrent));
+        let settings = Arc::new(RwLock::new(settings));
+        let clients = Arc::new(HttpClients::new(settings.clone()));
+        let provider = Provider::load_or_builtin(&config_dir);
+        let scraper = Arc::new(AnimeScraper::with_clients(clients.clone()).with_provider(Arc::new(provider)));
 
         let jobs_path = config_dir.join("jobs.json").to_string_lossy().to_string();
 
         let mut jobs: Vec<DownloadJob> = if fs::metadata(&jobs_path).is_ok() {
             let data = fs::read_to_string(&jobs_path)?;
-            serde_json::from_str(&data).unwrap_or_default()
+            match migrations::migrate_jobs(&data) {
+                Ok(jobs) => jobs,
+                // Refuse to overwrite a queue written by a newer version
+                Err(e) if e.is::<migrations::NewerJobsVersion>() => return Err(e),
+                Err(e) => {
+                    // Keep the unreadable file around instead of silently replacing it
+                    let backup = format!("{}.bak", jobs_path);
+                    tracing::warn!("Could not read {}: {}. Moved it to {}", jobs_path, e, backup);
+                    fs::rename(&jobs_path, &backup)?;
+                    vec![]
+                }
+            }
         } else {
             vec![]
         };
@@ -55,18 +163,30 @@ impl DownloadManager {
                     for seg in &mut task.segments {
                         if seg.status == SegmentStatus::Downloading {
                             seg.status = SegmentStatus::Pending;
+                            seg.downloaded = 0;
                         }
                     }
                 }
             }
         }
 
+        let cached_scraper = Arc::new(CachedScraper::new(
+            scraper.clone(),
+            settings.clone(),
+            config_dir.join("cache").join("scrape"),
+        ));
+
         Ok(Self {
-            settings: Arc::new(RwLock::new(settings)),
+            settings,
+            config: Mutex::new(config),
             jobs: Arc::new(Mutex::new(jobs)),
-            semaphore,
+            slots,
             jobs_path,
             scraper,
+            cached_scraper,
+            clients,
+            events: broadcast::channel(EVENT_CHANNEL_CAPACITY).0,
+            streaming_tasks: Arc::new(Mutex::new(HashSet::new())),
         })
     }
 
@@ -74,6 +194,72 @@ impl DownloadManager {
         self.scraper.clone()
     }
 
+    /// HTTP clients built from the network settings, shared by the scraper and downloads
+    pub fn http_clients(&self) -> Arc<HttpClients> {
+        self.clients.clone()
+    }
+
+    /// Scraper with the TTL cache in front of search, season and listing requests
+    pub fn get_cached_scraper(&self) -> Arc<CachedScraper> {
+        self.cached_scraper.clone()
+    }
+
+    /// Directory holding `settings.toml`, `jobs.json` and the other persisted stores
+    pub fn config_dir(&self) -> std::path::PathBuf {
+        std::path::Path::new(&self.jobs_path)
+            .parent()
+            .map(|p| p.to_path_buf())
+            .unwrap_or_default()
+    }
+
+    /// Subscribe to manager events (queued episodes, finished jobs, failures)
+    pub fn subscribe_events(&self) -> broadcast::Receiver<ManagerEvent> {
+        self.events.subscribe()
+    }
+
+    /// Broadcast an event to all subscribers. Having no subscribers is not an error.
+    pub fn emit(&self, event: ManagerEvent) {
+        let _ = self.events.send(event);
+    }
+
+    /// Folder a job's episodes are written to
+    pub fn job_folder(&self, job: &DownloadJob) -> std::path::PathBuf {
+        job.tasks
+            .iter()
+            .map(|t| std::path::Path::new(&t.filename))
+            .find(|p| p.is_absolute())
+            .and_then(|p| p.parent())
+            .map(|p| p.to_path_buf())
+            .unwrap_or_else(|| self.get_settings().download_dir.join(sanitize_file_name(&job.name)))
+    }
+
+    /// Folder holding the `partN.mp4` segment files of a task that is still downloading
+    pub fn task_parts_folder(&self, job: &DownloadJob, task: &DownloadTask) -> std::path::PathBuf {
+        let final_path = self.task_output_path(job, task);
+        let stem = final_path.file_stem().unwrap_or_default().to_string_lossy().to_string();
+        final_path.with_file_name(format!("{}.downloading", stem))
+    }
+
+    /// Ask the worker of a task to fetch its segments strictly in order, so the
+    /// completed prefix can be played while the rest downloads
+    pub fn request_streaming(&self, task_id: &str) {
+        self.streaming_tasks.lock().unwrap().insert(task_id.to_string());
+    }
+
+    pub fn is_streaming(&self, task_id: &str) -> bool {
+        self.streaming_tasks.lock().unwrap().contains(task_id)
+    }
+
+    /// Final location of a task's file once it has been downloaded
+    pub fn task_output_path(&self, job: &DownloadJob, task: &DownloadTask) -> std::path::PathBuf {
+        let path = std::path::Path::new(&task.filename);
+        if path.is_absolute() {
+            path.to_path_buf()
+        } else {
+            self.job_folder(job).join(sanitize_file_name(&task.filename))
+        }
+    }
+
     pub fn get_jobs(&self) -> Vec<DownloadJob> {
         self.jobs.lock().unwrap().clone()
     }
@@ -83,27 +269,69 @@ impl DownloadManager {
         self.settings.read().unwrap().clone()
     }
 
+    /// Where each effective setting came from (default, files, environment, command line)
+    pub fn settings_origins(&self) -> SettingsOrigins {
+        self.config.lock().unwrap().origins.clone()
+    }
+
     /// Update settings and persist to disk
+    /// Fails with a `SettingsError` when `new_settings` doesn't pass `Settings::validate`.
     pub fn update_settings(&self, new_settings: Settings) -> Result<()> {
-        let config_dir = std::path::Path::new(&self.jobs_path).parent();
-        new_settings.save(config_dir)?;
-        
-        let mut settings_guard = self.settings.write().unwrap();
-        *settings_guard = new_settings.clone();
-        
-        // Update semaphore with new concurrency limit? 
-        // Note: Replacing the semaphore in Arc is tricky if other threads hold refs to old semaphore.
-        // However, since we store Arc<Semaphore> in struct, and workers clone it, 
-        // only NEW tasks will use the new semaphore if we replace it here?
-        // Actually, DownloadManager struct is immutable (via Arc). 
-        // We cannot update `self.semaphore` if `self` is `&self`.
-        // We would need to wrap `semaphore` in RwLock or Mutex too if we want to change it dynamically.
-        // For now, let's accept that concurrency limit changes require restart, OR wrap semaphore.
-        // Given complexity, let's just update settings on disk/memory. App restart might be needed for concurrency change.
-        
+        let keys = self.config.lock().unwrap().save(new_settings.clone())?;
+        self.apply_settings(new_settings, keys, false);
         Ok(())
     }
 
+    /// Invalid settings that were replaced by their defaults at startup, for the settings view.
+    /// Empty once valid settings are saved or reloaded.
+    pub fn settings_errors(&self) -> Vec<String> {
+        self.config.lock().unwrap().rejected.clone()
+    }
+
+    /// Pick up hand edits of `settings.toml`. Returns whether the settings changed; an invalid
+    /// file is rejected (and a `SettingsRejected` event sent) only once per edit.
+    pub fn reload_settings(&self) -> Result<bool> {
+        let reloaded = {
+            let mut config = self.config.lock().unwrap();
+            config
+                .reload_if_changed()
+                .map(|keys| keys.map(|keys| (keys, config.settings.clone())))
+        };
+        match reloaded {
+            Ok(Some((keys, settings))) => {
+                tracing::info!("Reloaded settings.toml ({} changed)", keys.join(", "));
+                self.apply_settings(settings, keys, true);
+                Ok(true)
+            }
+            Ok(None) => Ok(false),
+            Err(e) => {
+                tracing::warn!("Ignoring invalid settings.toml: {:#}", e);
+                self.emit(ManagerEvent::SettingsRejected { error: format!("{:#}", e) });
+                Err(e)
+            }
+        }
+    }
+
+    /// Reload `settings.toml` whenever it changes on disk, until the runtime shuts down.
+    /// Concurrency applies immediately; user agent, cache TTLs and the download directory
+    /// apply to the next request or queued episode. Server addresses need a restart.
+    pub async fn watch_settings(self: Arc<Self>) {
+        let mut ticker = tokio::time::interval(SETTINGS_WATCH_INTERVAL);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            let _ = self.reload_settings();
+        }
+    }
+
+    fn apply_settings(&self, settings: Settings, keys: Vec<String>, from_disk: bool) {
+        self.slots.resize(settings.max_concurrent_downloads);
+        *self.settings.write().unwrap() = settings;
+        if !keys.is_empty() {
+            self.emit(ManagerEvent::SettingsChanged { keys, from_disk });
+        }
+    }
+
     /// Smart add_job: merges with existing job if same ID, updates URLs for incomplete tasks
     pub fn add_job(&self, job: DownloadJob) {
         let mut jobs = self.jobs.lock().unwrap();
@@ -156,13 +384,10 @@ impl DownloadManager {
     }
 
     /// Check if an episode file already exists on disk (completed download)
-    /// Completed files are named `Ep01.mp4`, in-progress files are `Ep01.downloading.mp4`
+    /// Completed files are named `Ep01.mp4` (or `<Title> S01E01.mp4` with Jellyfin naming),
+    /// in-progress files are `Ep01.downloading.mp4`
     pub fn is_episode_downloaded(&self, anime_folder: &std::path::Path, episode_number: u32) -> bool {
-        let filename = format!("Ep{:02}.mp4", episode_number);
-        let filepath = anime_folder.join(&filename);
-        
-        // File exists and is NOT a .downloading file means it's complete
-        filepath.exists()
+        is_episode_downloaded(anime_folder, episode_number)
     }
 
     /// Check if an episode is already in the download queue (pending/downloading)
@@ -205,15 +430,27 @@ impl DownloadManager {
                 .ok_or(anyhow!("Job not found"))?;
             job.tasks.iter().map(|t| t.id.clone()).collect()
         };
+        self.start_tasks(job_id, task_ids).await
+    }
+
+    /// Spawn workers for some tasks of a job only, e.g. episodes just added to a job whose
+    /// other tasks may already have workers waiting for a slot
+    pub async fn start_tasks(&self, job_id: String, task_ids: Vec<String>) -> Result<()> {
+        if !self.jobs.lock().unwrap().iter().any(|j| j.id == job_id) {
+            return Err(anyhow!("Job not found"));
+        }
 
         // Spawn a worker for each task
         for task_id in task_ids {
             let jobs_ref = self.jobs.clone();
-            let semaphore_ref = self.semaphore.clone();
+            let slots_ref = self.slots.clone();
             let settings_ref = self.settings.clone();
             let scraper_ref = self.scraper.clone();
+            let clients_ref = self.clients.clone();
             let job_id_clone = job_id.clone();
             let jobs_path = self.jobs_path.clone();
+            let events_ref = self.events.clone();
+            let streaming_ref = self.streaming_tasks.clone();
 
             tokio::spawn(async move {
                 if let Err(e) = download_task_worker(
@@ -221,13 +458,15 @@ impl DownloadManager {
                     job_id_clone,
                     task_id,
                     settings_ref,
-                    semaphore_ref,
+                    slots_ref,
                     scraper_ref,
+                    clients_ref,
                     jobs_path,
+                    events_ref,
+                    streaming_ref,
                 )
                 .await
                 {
-                    println!("[Aura] Download failed: {}", e);
                     tracing::error!("Download failed: {}", e);
                 }
             });
@@ -279,28 +518,95 @@ impl DownloadManager {
         self.save_jobs();
     }
 
+    /// Move a job `offset` places towards the front (negative) or back of the queue.
+    /// Waiting episodes of earlier jobs get download slots first.
+    pub fn move_job(&self, job_id: &str, offset: isize) -> bool {
+        let mut jobs = self.jobs.lock().unwrap();
+        let Some(from) = jobs.iter().position(|j| j.id == job_id) else {
+            return false;
+        };
+        let to = (from as isize + offset).clamp(0, jobs.len() as isize - 1) as usize;
+        if from == to {
+            return false;
+        }
+        let job = jobs.remove(from);
+        jobs.insert(to, job);
+        drop(jobs);
+        self.save_jobs();
+        self.slots.changed.notify_waiters();
+        true
+    }
+
+    /// Remove tasks from the queue (the whole job when `task_id` is None) and delete their
+    /// downloaded segments. Completed episode files are kept.
+    pub fn cancel(&self, job_id: &str, task_id: Option<&str>) {
+        let mut jobs = self.jobs.lock().unwrap();
+        let Some(index) = jobs.iter().position(|j| j.id == job_id) else {
+            return;
+        };
+        let job = jobs[index].clone();
+        let (cancelled, kept): (Vec<DownloadTask>, Vec<DownloadTask>) = job
+            .tasks
+            .iter()
+            .cloned()
+            .partition(|t| task_id.is_none_or(|id| id == t.id));
+        if kept.is_empty() {
+            jobs.remove(index);
+        } else {
+            jobs[index].tasks = kept;
+        }
+        drop(jobs);
+        self.save_jobs();
+
+        for task in cancelled.iter().filter(|t| t.status != TaskStatus::Completed) {
+            self.streaming_tasks.lock().unwrap().remove(&task.id);
+            let _ = fs::remove_dir_all(self.task_parts_folder(&job, task));
+        }
+    }
+
+    /// Portable copy of the unfinished queue (see `QueueBundle`)
+    pub fn export_queue(&self) -> QueueBundle {
+        QueueBundle::from_jobs(&self.get_jobs(), &self.get_settings().download_dir)
+    }
+
+    /// Add the jobs of a bundle exported on another machine, with paths under this machine's
+    /// `download_dir`. Links are resolved when each task starts. Returns the imported job ids;
+    /// nothing is started.
+    pub fn import_queue(&self, bundle: QueueBundle) -> Result<Vec<String>> {
+        let jobs = bundle.into_jobs(&self.get_settings().download_dir)?;
+        let ids = jobs.iter().map(|j| j.id.clone()).collect();
+        for job in jobs {
+            self.add_job(job);
+        }
+        Ok(ids)
+    }
+
     fn save_jobs(&self) {
         let jobs = self.jobs.lock().unwrap();
-        if let Ok(data) = serde_json::to_string_pretty(&*jobs) {
+        if let Ok(data) = migrations::serialize_jobs(&jobs) {
             let _ = fs::write(&self.jobs_path, data);
         }
     }
 }
 
 /// Worker function that downloads a single task using folder-based parts
+#[allow(clippy::too_many_arguments)]
 async fn download_task_worker(
     jobs: Arc<Mutex<Vec<DownloadJob>>>,
     job_id: String,
     task_id: String,
     settings_store: Arc<RwLock<Settings>>,
-    semaphore: Arc<Semaphore>,
+    slots: Arc<SlotQueue>,
     scraper: Arc<AnimeScraper>,
+    clients: Arc<HttpClients>,
     jobs_path: String,
+    events: broadcast::Sender<ManagerEvent>,
+    streaming_tasks: Arc<Mutex<HashSet<String>>>,
 ) -> Result<()> {
     // Helper to save jobs
     let save_jobs = |jobs: &Arc<Mutex<Vec<DownloadJob>>>| {
         let jobs_lock = jobs.lock().unwrap();
-        if let Ok(data) = serde_json::to_string_pretty(&*jobs_lock) {
+        if let Ok(data) = migrations::serialize_jobs(&jobs_lock) {
             let _ = fs::write(&jobs_path, data);
         }
     };
@@ -321,19 +627,19 @@ async fn download_task_worker(
         // Skip if already completed or not pending
         match task.status {
             TaskStatus::Completed => {
-                println!("[Aura] Task {} already completed.", task_id);
+                tracing::debug!("Task {} already completed.", task_id);
                 return Ok(());
             },
             TaskStatus::Downloading => {
-                println!("[Aura] Task {} already downloading.", task_id);
+                tracing::debug!("Task {} already downloading.", task_id);
                 return Ok(());
             }, 
             TaskStatus::Paused(_) | TaskStatus::Error(_) => {
-                println!("[Aura] Task {} is paused or error.", task_id);
+                tracing::debug!("Task {} is paused or error.", task_id);
                 return Ok(());
             },
             TaskStatus::Pending => {
-                println!("[Aura] Task {} is pending. Starting...", task_id);
+                tracing::debug!("Task {} is pending. Starting...", task_id);
             } 
         }
 
@@ -349,7 +655,7 @@ async fn download_task_worker(
 
     // Resolve URL if pending
     if url == "pending" {
-        println!("[Aura] URL is pending for task {}. Resolving...", task_id);
+        tracing::info!("URL is pending for task {}. Resolving...", task_id);
         if let (Some(ep_url), Some(gid), Some(eno)) = (&episode_url, &gate_id, episode_number) {
              let temp_ep = Episode {
                  name: "".to_string(),
@@ -360,7 +666,7 @@ async fn download_task_worker(
              
              match scraper.get_download_link(&temp_ep).await {
                  Ok(resolved_url) => {
-                     println!("[Aura] Link resolved successfully: {}", resolved_url);
+                     tracing::info!("Link resolved successfully: {}", resolved_url);
                      url = resolved_url.clone();
                      
                      // Persist resolved URL
@@ -376,7 +682,23 @@ async fn download_task_worker(
                  },
                  Err(e) => {
                      let msg = format!("Failed to resolve link: {}", e);
-                     println!("[Aura] {}", msg);
+                     tracing::error!("{}", msg);
+                     {
+                        let mut jobs_lock = jobs.lock().unwrap();
+                        if let Some(job) = jobs_lock.iter_mut().find(|j| j.id == job_id) {
+                            if let Some(task) = job.tasks.iter_mut().find(|t| t.id == task_id) {
+                                task.status = TaskStatus::Error(msg.clone());
+                            }
+                        }
+                     }
+                     save_jobs(&jobs);
+                     let _ = events.send(ManagerEvent::TaskFailed {
+                         job_id: job_id.clone(),
+                         job_name: job_name.clone(),
+                         task_id: task_id.clone(),
+                         episode_number,
+                         error: msg.clone(),
+                     });
                      return Err(anyhow::anyhow!(msg));
                  }
              }
@@ -385,11 +707,11 @@ async fn download_task_worker(
         }
     }
 
-    println!("[Aura] Acquiring semaphore for task {}...", task_id);
+    tracing::debug!("Acquiring semaphore for task {}...", task_id);
     // Acquire semaphore permit to limit concurrency
-    // This will wait here until a slot is available
-    let _permit = semaphore.acquire().await.map_err(|e| anyhow::anyhow!("Semaphore closed: {}", e))?;
-    println!("[Aura] Semaphore acquired for task {}.", task_id);
+    // This will wait here until a slot is available and no earlier task is waiting
+    let _permit = slots.acquire(&jobs, &task_id).await?;
+    tracing::debug!("Semaphore acquired for task {}.", task_id);
 
     // Setup paths
     // FIXED: Use configured download_dir instead of saving to CWD
@@ -398,20 +720,29 @@ async fn download_task_worker(
         (settings.download_dir.clone(), filename.clone())
     };
 
-    // Sanitize filename for Windows (remove invalid chars)
-    let sanitized_filename = raw_filename
-        .replace(':', " -")
-        .replace(['<', '>', '"', '/', '\\', '|', '?', '*'], "");
+    // Tasks queued with an absolute path (e.g. by the CLI) already name their target folder
+    let requested_path = std::path::PathBuf::from(&raw_filename);
+    let (anime_folder, raw_filename) = if requested_path.is_absolute() {
+        let folder = requested_path
+            .parent()
+            .map(|p| p.to_path_buf())
+            .unwrap_or_else(|| download_dir.clone());
+        let name = requested_path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or(raw_filename);
+        (Some(folder), name)
+    } else {
+        (None, raw_filename)
+    };
 
-    // Sanitize anime name folder
-    let sanitized_job_name = job_name
-        .replace(':', " -")
-        .replace(['<', '>', '"', '/', '\\', '|', '?', '*'], "");
+    let sanitized_filename = sanitize_file_name(&raw_filename);
+    let sanitized_job_name = sanitize_file_name(&job_name);
 
-    let anime_folder = download_dir.join(&sanitized_job_name);
+    let anime_folder = anime_folder.unwrap_or_else(|| download_dir.join(&sanitized_job_name));
     // Ensure anime folder exists
     if let Err(e) = tokio::fs::create_dir_all(&anime_folder).await {
-         println!("[Aura] Failed to create anime folder: {}", e);
+         tracing::warn!("Failed to create anime folder: {}", e);
     }
 
     let final_path = anime_folder.join(&sanitized_filename);
@@ -419,7 +750,7 @@ async fn download_task_worker(
     let parts_folder_name = format!("{}.downloading", file_stem);
     let parts_folder = anime_folder.join(&parts_folder_name);
     
-    println!("[Aura] Download target: {:?}", final_path);
+    tracing::debug!("Download target: {:?}", final_path);
 
     // Mark as downloading
     {
@@ -428,7 +759,11 @@ async fn download_task_worker(
             if let Some(task) = job.tasks.iter_mut().find(|t| t.id == task_id) {
                 task.status = TaskStatus::Downloading;
                 // Update the task filename to the sanitized one so we can find it later
-                task.filename = sanitized_filename.clone();
+                task.filename = if requested_path.is_absolute() {
+                    final_path.to_string_lossy().to_string()
+                } else {
+                    sanitized_filename.clone()
+                };
             }
         }
     }
@@ -441,24 +776,28 @@ async fn download_task_worker(
     tokio::fs::create_dir_all(&parts_folder).await?;
 
     // Get total size and initialize segments
-    let client = reqwest::Client::new();
+    let client = clients.download()?;
+    let read_timeout = clients.read_timeout();
     let current_settings = settings_store.read().unwrap().clone();
     
-    println!("[Aura] Fetching content length for: {}", url);
-    let total_size = match get_content_length(&client, &url, &current_settings.user_agent).await {
+    tracing::debug!("Fetching content length for: {}", url);
+    // A resumed task keeps the split it was started with, even if `segments_per_file` changed since
+    let segments = match get_content_length(&client, &url, &current_settings.user_agent).await {
         Ok(size) => {
             let mut jobs_guard = jobs.lock().unwrap();
+            let mut segments = Vec::new();
             if let Some(job) = jobs_guard.iter_mut().find(|j| j.id == job_id) {
                 if let Some(task) = job.tasks.iter_mut().find(|t| t.id == task_id) {
                     task.total_bytes = size;
                     if task.segments.is_empty() {
                         task.segments = create_segments(size, current_settings.segments_per_file);
                     }
+                    segments = task.segments.clone();
                 }
             }
             drop(jobs_guard);
             save_jobs(&jobs);
-            size
+            segments
         }
         Err(e) => {
             let mut jobs_guard = jobs.lock().unwrap();
@@ -469,25 +808,28 @@ async fn download_task_worker(
             }
             drop(jobs_guard);
             save_jobs(&jobs);
+            let _ = events.send(ManagerEvent::TaskFailed {
+                job_id: job_id.clone(),
+                job_name: job_name.clone(),
+                task_id: task_id.clone(),
+                episode_number,
+                error: e.to_string(),
+            });
             return Err(e);
         }
     };
 
-    let num_parts = current_settings.segments_per_file;
+    let num_parts = segments.len();
     let mut link_refresh_attempts = 0u32;
 
     // Check which parts already exist (for resume)
-    for i in 0..num_parts {
+    for segment in &segments {
+        let i = segment.index;
         let part_path = parts_folder.join(format!("part{}.mp4", i));
         if part_path.exists() {
             // Check if part file is complete (expected size)
-            let segment_size = total_size / num_parts as u64;
-            let expected_size = if i == num_parts - 1 {
-                total_size - (i as u64 * segment_size)
-            } else {
-                segment_size
-            };
-            
+            let expected_size = segment.end - segment.start + 1;
+
             if let Ok(meta) = tokio::fs::metadata(&part_path).await {
                 if meta.len() >= expected_size {
                     // Mark segment as completed
@@ -518,10 +860,10 @@ async fn download_task_worker(
     let ticker_handle = tokio::spawn(async move {
         loop {
             tokio::time::sleep(Duration::from_millis(2000)).await;
-            
-            let current_inc = progress_ticker.load(Ordering::Relaxed);
-            
+
+            // Read the counter under the lock so it can't belong to the previous segment
             let mut jobs_guard = jobs_ref_ticker.lock().unwrap();
+            let current_inc = progress_ticker.load(Ordering::Relaxed);
             if let Some(job) = jobs_guard.iter_mut().find(|j| j.id == job_id_ticker) {
                 if let Some(task) = job.tasks.iter_mut().find(|t| t.id == task_id_ticker) {
                     // Check if task is still active
@@ -533,6 +875,14 @@ async fn download_task_worker(
                                 .map(|s| s.downloaded)
                                 .sum();
                             task.progress_bytes = completed_sum + current_inc;
+                            // The streaming server serves the written part of the current segment
+                            if let Some(seg) = task
+                                .segments
+                                .iter_mut()
+                                .find(|s| s.status == SegmentStatus::Downloading)
+                            {
+                                seg.downloaded = current_inc;
+                            }
                         }
                         _ => break, // Exit if paused/error/completed
                     }
@@ -545,24 +895,24 @@ async fn download_task_worker(
     // Download loop
     loop {
         // Check if paused or cancelled
-        {
+        let status = {
             let jobs_guard = jobs.lock().unwrap();
-            if let Some(job) = jobs_guard.iter().find(|j| j.id == job_id) {
-                if let Some(task) = job.tasks.iter().find(|t| t.id == task_id) {
-                    match task.status {
-                        TaskStatus::Paused(_) | TaskStatus::Error(_) => {
-                             ticker_handle.abort();
-                             return Ok(());
-                        }
-                        TaskStatus::Completed => {
-                            ticker_handle.abort();
-                            return Ok(());
-                        }
-                        _ => {}
-                    }
-                }
-            } else {
+            jobs_guard
+                .iter()
+                .find(|j| j.id == job_id)
+                .and_then(|j| j.tasks.iter().find(|t| t.id == task_id))
+                .map(|t| t.status.clone())
+        };
+        match status {
+            Some(TaskStatus::Paused(_)) | Some(TaskStatus::Error(_)) | Some(TaskStatus::Completed) => {
+                ticker_handle.abort();
+                return Ok(());
+            }
+            Some(_) => {}
+            None => {
+                // Cancelled: drop the segments written so far
                 ticker_handle.abort();
+                let _ = tokio::fs::remove_dir_all(&parts_folder).await;
                 return Ok(());
             }
         }
@@ -585,20 +935,29 @@ async fn download_task_worker(
                 }
             };
 
-            task.segments
+            // Segments restored from disk or reset after an error can be out of order;
+            // a task that is being streamed always continues the contiguous prefix
+            let streaming = streaming_tasks.lock().unwrap().contains(&task_id);
+            let mut pending = task
+                .segments
                 .iter_mut()
-                .find(|s| s.status == SegmentStatus::Pending)
-                .map(|s| {
-                    s.status = SegmentStatus::Downloading;
-                    s.clone()
-                })
+                .filter(|s| s.status == SegmentStatus::Pending);
+            let segment = if streaming {
+                pending.min_by_key(|s| s.index)
+            } else {
+                pending.next()
+            };
+            segment.map(|s| {
+                // Reset progress for this segment
+                current_segment_progress.store(0, Ordering::Relaxed);
+                s.status = SegmentStatus::Downloading;
+                s.downloaded = 0;
+                s.clone()
+            })
         };
 
         if let Some(segment) = next_segment {
             let part_path = parts_folder.join(format!("part{}.mp4", segment.index));
-            
-            // Reset progress for this segment
-            current_segment_progress.store(0, Ordering::Relaxed);
 
             // Download this part to its own file
             let result = download_part_to_file(
@@ -609,6 +968,7 @@ async fn download_task_worker(
                 segment.end,
                 &part_path,
                 current_segment_progress.clone(),
+                read_timeout,
             )
             .await;
 
@@ -654,6 +1014,7 @@ async fn download_task_worker(
                                         .find(|s| s.index == segment.index)
                                     {
                                         seg.status = SegmentStatus::Pending;
+                                        seg.downloaded = 0;
                                     }
                                 }
                             }
@@ -710,6 +1071,12 @@ async fn download_task_worker(
                         }
                         drop(jobs_guard);
                         save_jobs(&jobs);
+                        let _ = events.send(ManagerEvent::LinkExpired {
+                            job_id: job_id.clone(),
+                            job_name: job_name.clone(),
+                            task_id: task_id.clone(),
+                            episode_number,
+                        });
                         return Ok(());
                     } else {
                         // Other error - reset segment for retry
@@ -720,6 +1087,7 @@ async fn download_task_worker(
                                     task.segments.iter_mut().find(|s| s.index == segment.index)
                                 {
                                     seg.status = SegmentStatus::Pending;
+                                    seg.downloaded = 0;
                                 }
                             }
                         }
@@ -747,15 +1115,31 @@ async fn download_task_worker(
                 
                 // Remove parts folder
                 let _ = tokio::fs::remove_dir_all(&parts_folder).await;
+                streaming_tasks.lock().unwrap().remove(&task_id);
                 
                 let mut jobs_guard = jobs.lock().unwrap();
+                let mut job_finished = false;
                 if let Some(job) = jobs_guard.iter_mut().find(|j| j.id == job_id) {
                     if let Some(task) = job.tasks.iter_mut().find(|t| t.id == task_id) {
                         task.status = TaskStatus::Completed;
                     }
+                    job_finished = job.tasks.iter().all(|t| t.status == TaskStatus::Completed);
                 }
                 drop(jobs_guard);
                 save_jobs(&jobs);
+
+                let _ = events.send(ManagerEvent::TaskCompleted {
+                    job_id: job_id.clone(),
+                    job_name: job_name.clone(),
+                    task_id: task_id.clone(),
+                    episode_number,
+                });
+                if job_finished {
+                    let _ = events.send(ManagerEvent::JobFinished {
+                        job_id: job_id.clone(),
+                        job_name: job_name.clone(),
+                    });
+                }
                 break;
             }
 
@@ -766,7 +1150,51 @@ async fn download_task_worker(
     Ok(())
 }
 
+/// `custom_config_path`, or the `aura` folder in the platform's config directory
+pub fn resolve_config_dir(custom_config_path: Option<String>) -> Result<std::path::PathBuf> {
+    match custom_config_path {
+        Some(path) => Ok(std::path::PathBuf::from(path)),
+        None => Ok(dirs::config_dir()
+            .ok_or_else(|| anyhow!("Could not find config directory"))?
+            .join("aura")),
+    }
+}
+
+/// See `DownloadManager::is_episode_downloaded`; usable without a manager (daemon clients)
+pub fn is_episode_downloaded(anime_folder: &std::path::Path, episode_number: u32) -> bool {
+    let filename = format!("Ep{:02}.mp4", episode_number);
+    let filepath = anime_folder.join(&filename);
+
+    // File exists and is NOT a .downloading file means it's complete
+    if filepath.exists() {
+        return true;
+    }
+
+    let jellyfin_suffix = format!("S01E{:02}.mp4", episode_number);
+    fs::read_dir(anime_folder)
+        .map(|entries| {
+            entries
+                .flatten()
+                .any(|e| e.file_name().to_string_lossy().ends_with(&jellyfin_suffix))
+        })
+        .unwrap_or(false)
+}
+
+/// Folder or file name with everything but letters, digits, spaces and dashes replaced by `_`.
+/// Shows queued by `aura-cli get`/`season` and by subscriptions are saved in
+/// `<download_dir>/<safe_file_name(title)>`.
+pub fn safe_file_name(name: &str) -> String {
+    name.replace(|c: char| !c.is_alphanumeric() && c != ' ' && c != '-', "_")
+}
+
+/// Make a show or episode name safe to use as a file name on Windows (remove invalid chars)
+pub(crate) fn sanitize_file_name(name: &str) -> String {
+    name.replace(':', " -")
+        .replace(['<', '>', '"', '/', '\\', '|', '?', '*'], "")
+}
+
 /// Download a range to a separate file (not seeking within existing file)
+#[allow(clippy::too_many_arguments)]
 async fn download_part_to_file(
     client: &reqwest::Client,
     url: &str,
@@ -775,6 +1203,7 @@ async fn download_part_to_file(
     end: u64,
     filepath: &std::path::Path,
     current_progress: Arc<AtomicU64>,
+    read_timeout: Duration,
 ) -> Result<()> {
     use tokio::io::AsyncWriteExt;
     
@@ -802,7 +1231,7 @@ async fn download_part_to_file(
     // Batch updates to reduce atomic contention (update every ~512KB)
     const PROGRESS_UPDATE_THRESHOLD: u64 = 512 * 1024; 
 
-    while let Some(chunk) = resp.chunk().await? {
+    while let Some(chunk) = next_chunk(&mut resp, read_timeout).await? {
         file.write_all(&chunk).await?;
         accumulated_progress += chunk.len() as u64;
         
@@ -0,0 +1,354 @@
+use crate::images::ImageCache;
+use crate::manager::{sanitize_file_name, DownloadManager};
+use crate::models::{AiringStatus, AnimeInfo, DownloadJob, ManagerEvent};
+use crate::playlist::{self, PlaylistFormat};
+use crate::scraper::{absolute_url, season_path};
+use anyhow::{anyhow, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::broadcast;
+
+lazy_static::lazy_static! {
+    static ref EPISODE_NUMBER_RE: regex::Regex =
+        regex::Regex::new(r"(?i)(?:S\d+E|\bEp(?:isode)?\s*)(\d+)").unwrap();
+}
+
+const SHOW_NFO: &str = "tvshow.nfo";
+const VIDEO_EXTENSIONS: &[&str] = &["mp4", "mkv", "webm", "avi"];
+/// `<uniqueid type="...">` used to remember which season page a folder came from
+const UNIQUE_ID_TYPE: &str = "animeheaven";
+
+/// Episode file name Jellyfin and Kodi can match without a custom parser
+pub fn jellyfin_episode_filename(show_title: &str, episode_number: u32) -> String {
+    format!("{} S01E{:02}.mp4", sanitize_file_name(show_title), episode_number)
+}
+
+/// Outcome of regenerating metadata for the whole download directory
+#[derive(Debug, Default, Clone)]
+pub struct LibraryReport {
+    pub shows: usize,
+    pub episodes: usize,
+    pub failed: Vec<(PathBuf, String)>,
+}
+
+/// Writes `tvshow.nfo`, per-episode `.nfo` files and `poster.jpg` next to downloads
+/// so media servers (Jellyfin, Kodi, Plex) can identify them, and playlists once a job finishes.
+pub struct MetadataWriter {
+    images: Arc<ImageCache>,
+}
+
+impl MetadataWriter {
+    pub fn new(images: Arc<ImageCache>) -> Self {
+        Self { images }
+    }
+
+    /// Write `tvshow.nfo` for a show folder, and the poster if a cover is known
+    pub async fn write_show(
+        &self,
+        folder: &Path,
+        info: &AnimeInfo,
+        cover_url: Option<&str>,
+        save_poster: bool,
+    ) -> Result<()> {
+        fs::create_dir_all(folder)?;
+        fs::write(folder.join(SHOW_NFO), show_nfo(info))?;
+
+        match cover_url {
+            Some(cover) if save_poster => self.save_poster(folder, cover).await,
+            _ => Ok(()),
+        }
+    }
+
+    /// Write `<video stem>.nfo` next to an episode file
+    pub fn write_episode(&self, video: &Path, info: &AnimeInfo, episode_number: u32) -> Result<()> {
+        fs::write(video.with_extension("nfo"), episode_nfo(info, episode_number))?;
+        Ok(())
+    }
+
+    /// Write sidecars for a completed task, or playlists for a finished job,
+    /// as configured in `Settings.metadata`
+    pub async fn handle_event(&self, manager: &DownloadManager, event: &ManagerEvent) -> Result<()> {
+        let settings = manager.get_settings().metadata;
+        let (job_id, task_id) = match event {
+            ManagerEvent::TaskCompleted { job_id, task_id, .. } => (job_id, task_id),
+            ManagerEvent::JobFinished { job_id, .. } if settings.write_playlists => {
+                if let Some(job) = manager.get_jobs().into_iter().find(|j| &j.id == job_id) {
+                    playlist::export_folder(manager, &manager.job_folder(&job), &PlaylistFormat::ALL).await?;
+                }
+                return Ok(());
+            }
+            _ => return Ok(()),
+        };
+        if !settings.write_nfo && !settings.save_poster {
+            return Ok(());
+        }
+
+        let Some(job) = manager.get_jobs().into_iter().find(|j| &j.id == job_id) else {
+            return Ok(());
+        };
+        let Some(task) = job.tasks.iter().find(|t| &t.id == task_id) else {
+            return Ok(());
+        };
+
+        let (info, cover) = resolve_show(manager, &job.name, job.anime_url.as_deref(), job.cover_url.clone()).await?;
+        let folder = manager.job_folder(&job);
+
+        if settings.write_nfo {
+            self.write_show(&folder, &info, cover.as_deref(), settings.save_poster).await?;
+            if let Some(number) = task.episode_number {
+                self.write_episode(&manager.task_output_path(&job, task), &info, number)?;
+            }
+        } else if let Some(cover) = cover {
+            fs::create_dir_all(&folder)?;
+            self.save_poster(&folder, &cover).await?;
+        }
+        Ok(())
+    }
+
+    /// Consume manager events until the channel closes.
+    /// Spawn this on the runtime of the hosting application.
+    pub async fn run(self: Arc<Self>, manager: Arc<DownloadManager>) {
+        let mut events = manager.subscribe_events();
+        loop {
+            match events.recv().await {
+                Ok(event) => {
+                    if let Err(e) = self.handle_event(&manager, &event).await {
+                        tracing::warn!("Failed to write metadata: {}", e);
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    tracing::warn!("Metadata writer skipped {} events", skipped);
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    }
+
+    /// Rewrite metadata for every show folder in the download directory.
+    /// The show is identified from an existing `tvshow.nfo`, the job that downloaded it,
+    /// or a search for the folder name, in that order.
+    pub async fn regenerate_library(&self, manager: &DownloadManager) -> Result<LibraryReport> {
+        let settings = manager.get_settings();
+        let jobs = manager.get_jobs();
+        let mut report = LibraryReport::default();
+
+        let mut folders: Vec<PathBuf> = fs::read_dir(&settings.download_dir)?
+            .flatten()
+            .map(|e| e.path())
+            .filter(|p| p.is_dir())
+            .collect();
+        folders.sort();
+
+        for folder in folders {
+            let videos = episode_files(&folder);
+            if videos.is_empty() {
+                continue;
+            }
+
+            match self.regenerate_show(manager, &jobs, &folder, &videos, settings.metadata.save_poster).await {
+                Ok(episodes) => {
+                    report.shows += 1;
+                    report.episodes += episodes;
+                }
+                Err(e) => report.failed.push((folder, e.to_string())),
+            }
+        }
+        Ok(report)
+    }
+
+    async fn regenerate_show(
+        &self,
+        manager: &DownloadManager,
+        jobs: &[DownloadJob],
+        folder: &Path,
+        videos: &[(PathBuf, u32)],
+        save_poster: bool,
+    ) -> Result<usize> {
+        let name = folder
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let job = jobs.iter().find(|j| manager.job_folder(j) == folder);
+        let anime_url = read_unique_id(folder).or_else(|| job.and_then(|j| j.anime_url.clone()));
+        let cover = job.and_then(|j| j.cover_url.clone());
+
+        let (info, cover) = resolve_show(manager, &name, anime_url.as_deref(), cover).await?;
+        self.write_show(folder, &info, cover.as_deref(), save_poster).await?;
+        for (video, number) in videos {
+            self.write_episode(video, &info, *number)?;
+        }
+        Ok(videos.len())
+    }
+
+    /// Save the cover as `poster.<ext>` unless the folder already has a poster
+    pub async fn save_poster(&self, folder: &Path, cover: &str) -> Result<()> {
+        if find_poster(folder).is_some() {
+            return Ok(());
+        }
+        let image = self.images.get(cover).await?;
+        let ext = image
+            .path
+            .extension()
+            .map(|e| e.to_string_lossy().to_string())
+            .unwrap_or_else(|| "jpg".to_string());
+        fs::write(folder.join(format!("poster.{}", ext)), &image.bytes)?;
+        Ok(())
+    }
+}
+
+/// Season info and cover for a show. Without a known season URL, or without a cover,
+/// the title is searched and the closest match is used.
+async fn resolve_show(
+    manager: &DownloadManager,
+    title: &str,
+    anime_url: Option<&str>,
+    cover_url: Option<String>,
+) -> Result<(AnimeInfo, Option<String>)> {
+    let scraper = manager.get_cached_scraper();
+    let provider = scraper.inner().provider();
+
+    let search_hit = if anime_url.is_none() || cover_url.is_none() {
+        // A failed search only matters when it is the sole way to find the show
+        let results = match scraper.search(title, false).await {
+            Ok(results) => results,
+            Err(e) if anime_url.is_some() => {
+                tracing::debug!("Cover lookup for {} failed: {}", title, e);
+                vec![]
+            }
+            Err(e) => return Err(e.into()),
+        };
+        let wanted = anime_url.map(|url| season_path(&provider, url));
+        results
+            .iter()
+            .find(|r| Some(season_path(&provider, &r.url)) == wanted)
+            .or_else(|| results.iter().find(|r| sanitize_file_name(&r.title) == sanitize_file_name(title)))
+            .or_else(|| if wanted.is_none() { results.first() } else { None })
+            .cloned()
+    } else {
+        None
+    };
+
+    let url = anime_url
+        .map(str::to_string)
+        .or_else(|| search_hit.as_ref().map(|r| r.url.clone()))
+        .ok_or_else(|| anyhow!("Could not identify show \"{}\"", title))?;
+    let info = scraper.get_season(&url, false).await?;
+    let cover = cover_url
+        .or_else(|| search_hit.map(|r| r.image))
+        .or_else(|| info.cover_image.clone())
+        .filter(|c| !c.is_empty())
+        .map(|c| absolute_url(&provider, &c));
+    Ok((info, cover))
+}
+
+fn show_nfo(info: &AnimeInfo) -> String {
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n<tvshow>\n");
+    push_element(&mut xml, "title", &info.title);
+    if let Some(jp) = &info.japanese_title {
+        push_element(&mut xml, "originaltitle", jp);
+    }
+    if let Some(year) = info.year.as_deref().and_then(parse_year) {
+        push_element(&mut xml, "year", &year.to_string());
+    }
+    if let Some(description) = &info.description {
+        push_element(&mut xml, "plot", description);
+    }
+    for tag in info.tags.iter().flatten() {
+        push_element(&mut xml, "genre", tag);
+    }
+    if let Some(score) = info.score {
+        push_element(&mut xml, "rating", &score.to_string());
+    }
+    // Kodi's names for the airing states
+    match info.status {
+        Some(AiringStatus::Airing) => push_element(&mut xml, "status", "Continuing"),
+        Some(AiringStatus::Finished) => push_element(&mut xml, "status", "Ended"),
+        None => {}
+    }
+    xml.push_str(&format!(
+        "  <uniqueid type=\"{}\" default=\"true\">{}</uniqueid>\n",
+        UNIQUE_ID_TYPE,
+        xml_escape(&info.url)
+    ));
+    xml.push_str("</tvshow>\n");
+    xml
+}
+
+fn episode_nfo(info: &AnimeInfo, episode_number: u32) -> String {
+    let title = info
+        .episodes
+        .iter()
+        .find(|e| e.number == episode_number)
+        .map(|e| e.name.clone())
+        .filter(|n| !n.trim().is_empty())
+        .unwrap_or_else(|| format!("Episode {}", episode_number));
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n<episodedetails>\n");
+    push_element(&mut xml, "title", &title);
+    push_element(&mut xml, "showtitle", &info.title);
+    push_element(&mut xml, "season", "1");
+    push_element(&mut xml, "episode", &episode_number.to_string());
+    xml.push_str("</episodedetails>\n");
+    xml
+}
+
+fn push_element(xml: &mut String, name: &str, value: &str) {
+    xml.push_str(&format!("  <{0}>{1}</{0}>\n", name, xml_escape(value.trim())));
+}
+
+pub(crate) fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// `AnimeInfo.year` holds text such as "2023" or "Fall 2023"
+fn parse_year(text: &str) -> Option<u32> {
+    text.split(|c: char| !c.is_ascii_digit())
+        .find(|s| s.len() == 4)
+        .and_then(|s| s.parse().ok())
+}
+
+fn find_poster(folder: &Path) -> Option<PathBuf> {
+    ["jpg", "png", "webp", "gif"]
+        .iter()
+        .map(|ext| folder.join(format!("poster.{}", ext)))
+        .find(|p| p.exists())
+}
+
+/// Season URL stored by a previous run in `tvshow.nfo`
+pub(crate) fn read_unique_id(folder: &Path) -> Option<String> {
+    let data = fs::read_to_string(folder.join(SHOW_NFO)).ok()?;
+    let doc = roxmltree::Document::parse(&data).ok()?;
+    doc.descendants()
+        .find(|n| n.has_tag_name("uniqueid") && n.attribute("type") == Some(UNIQUE_ID_TYPE))
+        .and_then(|n| n.text())
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty())
+}
+
+/// Completed episode files in a show folder with their episode numbers
+pub(crate) fn episode_files(folder: &Path) -> Vec<(PathBuf, u32)> {
+    let Ok(entries) = fs::read_dir(folder) else {
+        return vec![];
+    };
+    let mut files: Vec<(PathBuf, u32)> = entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.is_file())
+        .filter(|p| {
+            p.extension()
+                .map(|e| VIDEO_EXTENSIONS.contains(&e.to_string_lossy().to_lowercase().as_str()))
+                .unwrap_or(false)
+        })
+        .filter_map(|p| {
+            let stem = p.file_stem()?.to_string_lossy().to_string();
+            let number = EPISODE_NUMBER_RE.captures(&stem)?.get(1)?.as_str().parse().ok()?;
+            Some((p, number))
+        })
+        .collect();
+    files.sort_by_key(|(_, n)| *n);
+    files
+}
@@ -0,0 +1,108 @@
+use crate::config::SettingsError;
+use crate::models::DownloadJob;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+/// Current `config_version` of `settings.toml`. Files without one are version 0.
+pub const SETTINGS_VERSION: u32 = 1;
+
+/// Current `version` of `jobs.json`. A bare array of jobs is version 0.
+pub const JOBS_VERSION: u32 = 1;
+
+/// Step `n` upgrades a settings table from version `n` to `n + 1`
+const SETTINGS_MIGRATIONS: [fn(&mut toml::Table); SETTINGS_VERSION as usize] = [settings_v0_to_v1];
+
+/// Step `n` upgrades a jobs document from version `n` to `n + 1`
+const JOBS_MIGRATIONS: [fn(serde_json::Value) -> serde_json::Value; JOBS_VERSION as usize] = [jobs_v0_to_v1];
+
+/// Bring a parsed `settings.toml` up to `SETTINGS_VERSION`. Returns whether anything changed,
+/// so the caller knows to write the file back.
+pub fn migrate_settings(table: &mut toml::Table) -> Result<bool> {
+    let version = match table.get("config_version") {
+        None => 0,
+        Some(toml::Value::Integer(v)) if *v >= 0 => *v as u32,
+        Some(other) => return Err(anyhow!("config_version must be a whole number, got {}", other)),
+    };
+    if version > SETTINGS_VERSION {
+        return Err(SettingsError::UnsupportedVersion {
+            found: version,
+            supported: SETTINGS_VERSION,
+        }
+        .into());
+    }
+
+    for step in &SETTINGS_MIGRATIONS[version as usize..] {
+        step(table);
+    }
+    table.insert("config_version".to_string(), toml::Value::Integer(SETTINGS_VERSION as i64));
+    Ok(version < SETTINGS_VERSION)
+}
+
+/// Before validation existed, zero was accepted for these and hung or crashed the manager.
+/// Fall back to the defaults.
+fn settings_v0_to_v1(table: &mut toml::Table) {
+    let defaults = crate::config::Settings::default();
+    let repairs = [
+        ("max_concurrent_downloads", defaults.max_concurrent_downloads as i64),
+        ("segments_per_file", defaults.segments_per_file as i64),
+        ("subscription_check_interval_secs", defaults.subscription_check_interval_secs as i64),
+    ];
+    for (key, default) in repairs {
+        if matches!(table.get(key), Some(toml::Value::Integer(v)) if *v <= 0) {
+            table.insert(key.to_string(), toml::Value::Integer(default));
+        }
+    }
+}
+
+/// `jobs.json` was written by a newer Aura; it must not be overwritten
+#[derive(Debug, thiserror::Error)]
+#[error("jobs.json version {found} is newer than supported ({JOBS_VERSION}); update Aura")]
+pub struct NewerJobsVersion {
+    pub found: u32,
+}
+
+#[derive(Deserialize)]
+struct JobsFile {
+    jobs: Vec<DownloadJob>,
+}
+
+#[derive(Serialize)]
+struct JobsFileRef<'a> {
+    version: u32,
+    jobs: &'a [DownloadJob],
+}
+
+/// `jobs.json` contents for `jobs` at the current version
+pub fn serialize_jobs(jobs: &[DownloadJob]) -> serde_json::Result<String> {
+    serde_json::to_string_pretty(&JobsFileRef {
+        version: JOBS_VERSION,
+        jobs,
+    })
+}
+
+/// Parse `jobs.json` written by any version up to `JOBS_VERSION`
+pub fn migrate_jobs(data: &str) -> Result<Vec<DownloadJob>> {
+    let mut value: serde_json::Value = serde_json::from_str(data)?;
+    let version = match &value {
+        serde_json::Value::Array(_) => 0,
+        serde_json::Value::Object(map) => map
+            .get("version")
+            .and_then(|v| v.as_u64())
+            .ok_or_else(|| anyhow!("jobs.json has no version"))? as u32,
+        _ => return Err(anyhow!("jobs.json is neither a list nor a versioned document")),
+    };
+    if version > JOBS_VERSION {
+        return Err(NewerJobsVersion { found: version }.into());
+    }
+
+    for step in &JOBS_MIGRATIONS[version as usize..] {
+        value = step(value);
+    }
+    let file: JobsFile = serde_json::from_value(value)?;
+    Ok(file.jobs)
+}
+
+/// Version 0 was the bare list of jobs
+fn jobs_v0_to_v1(value: serde_json::Value) -> serde_json::Value {
+    serde_json::json!({ "version": 1, "jobs": value })
+}
@@ -7,6 +7,12 @@ pub struct SearchResult {
     pub title: String,
     pub url: String,
     pub image: String,
+    /// Known once the season page has been fetched
+    #[serde(default)]
+    pub japanese_title: Option<String>,
+    /// Fuzzy match of the query on the titles, 0 to 1 (see `search::rank`)
+    #[serde(default)]
+    pub relevance: f64,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -28,6 +34,36 @@ pub struct AnimeInfo {
     pub tags: Option<Vec<String>>,
     pub description: Option<String>,
     pub japanese_title: Option<String>,
+    /// Planned episode count; `None` while the site shows "?"
+    #[serde(default)]
+    pub total_episodes: Option<u32>,
+    /// Site rating out of 10
+    #[serde(default)]
+    pub score: Option<f32>,
+    #[serde(default)]
+    pub status: Option<AiringStatus>,
+    /// Poster URL as found on the page (may be site-relative)
+    #[serde(default)]
+    pub cover_image: Option<String>,
+}
+
+/// Whether a show is still getting new episodes
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AiringStatus {
+    Airing,
+    Finished,
+}
+
+impl AnimeInfo {
+    /// "N of M episodes released", or "N episodes released" when the total isn't known
+    pub fn release_summary(&self) -> String {
+        let released = self.episodes.len();
+        match self.total_episodes {
+            Some(total) => format!("{} of {} episodes released", released, total),
+            None => format!("{} episodes released", released),
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -40,6 +76,14 @@ pub struct ListEntry {
     pub rank: Option<u32>,
 }
 
+/// One page of a listing. `page` counts from 1; `has_more` is set when a next page has entries.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub page: u32,
+    pub has_more: bool,
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum ScrapeError {
     #[error("Network request failed: {0}")]
@@ -48,8 +92,16 @@ pub enum ScrapeError {
     #[error("Parsing failed: {0}")]
     Parse(String),
 
-    #[error("Element not found")]
-    NotFound,
+    /// The page layout no longer matches: run `aura-cli doctor scraper`
+    #[error("Selector '{selector}' matched nothing on the {page}")]
+    NotFound { selector: String, page: String },
+
+    #[error("HTTP client setup failed: {0}")]
+    Client(String),
+
+    /// The site answered with an error page (blocked, missing or down); nothing was parsed
+    #[error("{url} answered with HTTP {status}")]
+    Status { status: u16, url: String },
 }
 
 pub type ScrapeResult<T> = std::result::Result<T, ScrapeError>;
@@ -61,6 +113,12 @@ pub struct DownloadJob {
     pub id: String,
     pub name: String,
     pub tasks: Vec<DownloadTask>,
+    /// Season page the job was queued from, used to look up metadata after download
+    #[serde(default)]
+    pub anime_url: Option<String>,
+    /// Cover image of the show, saved as `poster.jpg` next to the episodes
+    #[serde(default)]
+    pub cover_url: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -110,3 +168,142 @@ pub enum PauseReason {
     NetworkError,
     Unknown,
 }
+
+// ============ SUBSCRIPTION MODELS ============
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Subscription {
+    /// Season page URL, also used as the download job id
+    pub url: String,
+    pub title: String,
+    /// Highest episode number already seen (and queued) for this show
+    pub last_seen_episode: u32,
+    /// Unix timestamp (seconds) when the show was followed
+    pub added_at: u64,
+    /// Unix timestamp (seconds) of the last successful check
+    pub last_checked: Option<u64>,
+}
+
+// ============ EVENT MODELS ============
+
+/// Events broadcast by the `DownloadManager` to front-ends
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum ManagerEvent {
+    /// A new episode of a followed show was found and queued
+    EpisodeQueued {
+        anime_title: String,
+        anime_url: String,
+        episode_number: u32,
+    },
+    /// A single task finished and its file is in place
+    TaskCompleted {
+        job_id: String,
+        job_name: String,
+        task_id: String,
+        episode_number: Option<u32>,
+    },
+    /// Every task of a job is completed
+    JobFinished { job_id: String, job_name: String },
+    /// A task stopped with an error
+    TaskFailed {
+        job_id: String,
+        job_name: String,
+        task_id: String,
+        episode_number: Option<u32>,
+        error: String,
+    },
+    /// A task was paused because its link expired and could not be refreshed
+    LinkExpired {
+        job_id: String,
+        job_name: String,
+        task_id: String,
+        episode_number: Option<u32>,
+    },
+    /// The effective settings changed. `keys` are the dotted keys that changed (`cache.capacity`);
+    /// `from_disk` is set when `settings.toml` was edited, rather than `update_settings` called.
+    SettingsChanged { keys: Vec<String>, from_disk: bool },
+    /// `settings.toml` was edited but is invalid; the previous settings stay in effect
+    SettingsRejected { error: String },
+}
+
+// ============ NOTIFICATION MODELS ============
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum NotificationKind {
+    JobFinished,
+    TaskFailed,
+    LinkExpired,
+    NewEpisode,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Notification {
+    pub id: u64,
+    pub kind: NotificationKind,
+    pub title: String,
+    pub body: String,
+    /// Unix timestamp (seconds)
+    pub created_at: u64,
+    pub read: bool,
+    /// Download job (or season URL) the notification refers to
+    pub job_id: Option<String>,
+}
+
+// ============ WATCH STATE MODELS ============
+
+/// Watch state of a single episode, keyed by anime URL + episode number
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchEntry {
+    pub anime_url: String,
+    pub anime_title: String,
+    pub episode_number: u32,
+    pub watched: bool,
+    /// Last playback position in seconds
+    pub position_secs: u64,
+    pub duration_secs: Option<u64>,
+    /// Unix timestamp (seconds) of the last update
+    pub updated_at: u64,
+    /// Unix timestamp (seconds) when the episode was marked watched
+    pub watched_at: Option<u64>,
+}
+
+/// Summary of a show with watch activity, for "continue watching" lists
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShowProgress {
+    pub anime_url: String,
+    pub anime_title: String,
+    pub watched_episodes: usize,
+    /// Highest episode number marked watched
+    pub last_watched_episode: Option<u32>,
+    /// Episode with a saved position that isn't finished yet
+    pub resume_episode: Option<u32>,
+    pub last_activity: u64,
+}
+
+// ============ WATCHLIST MODELS ============
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ListStatus {
+    PlanToWatch,
+    Watching,
+    Completed,
+    OnHold,
+    Dropped,
+}
+
+/// An entry of the personal anime list, keyed by `AnimeInfo.url`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchlistEntry {
+    pub url: String,
+    pub title: String,
+    pub status: ListStatus,
+    /// Personal score from 1 to 10
+    pub score: Option<u8>,
+    pub notes: String,
+    pub episodes_watched: u32,
+    /// Unix timestamp (seconds)
+    pub added_at: u64,
+    /// Unix timestamp (seconds)
+    pub updated_at: u64,
+}
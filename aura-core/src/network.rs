@@ -0,0 +1,182 @@
+use crate::config::{IpVersion, NetworkSettings, Settings};
+use anyhow::{anyhow, Result};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::{Client, ClientBuilder};
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio::time::Instant;
+
+/// Builds the HTTP clients of the scraper and the downloader from `Settings.network` and
+/// `Settings.user_agent`. Clients are cached and rebuilt when those settings change, so a
+/// reloaded proxy applies to the next request.
+pub struct HttpClients {
+    settings: Arc<RwLock<Settings>>,
+    cached: Mutex<Option<Cached>>,
+    hosts: Mutex<HashMap<String, Arc<HostLimit>>>,
+}
+
+struct Cached {
+    /// Network settings and user agent the clients were built from
+    key: String,
+    scraper: Client,
+    download: Client,
+}
+
+/// Request slots and spacing for one host
+struct HostLimit {
+    /// `max_requests_per_host` the semaphore was sized for; a changed setting replaces the entry
+    limit: usize,
+    slots: Arc<Semaphore>,
+    /// Earliest start of the next request
+    next_start: Mutex<Instant>,
+}
+
+impl HostLimit {
+    fn new(limit: usize) -> Self {
+        Self {
+            limit,
+            slots: Arc::new(Semaphore::new(limit)),
+            next_start: Mutex::new(Instant::now()),
+        }
+    }
+}
+
+/// A request slot for a host, released on drop
+pub struct RequestPermit {
+    _slot: OwnedSemaphorePermit,
+}
+
+impl HttpClients {
+    pub fn new(settings: Arc<RwLock<Settings>>) -> Self {
+        Self {
+            settings,
+            cached: Mutex::new(None),
+            hosts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Wait for a free slot on the host of `url` (`max_requests_per_host`) and for
+    /// `min_request_interval_ms` after the previous request to it started. Hold the permit until
+    /// the response body has been read.
+    pub async fn throttle(&self, url: &str) -> RequestPermit {
+        let (limit, interval) = {
+            let network = &self.settings.read().unwrap().network;
+            (network.max_requests_per_host.max(1), Duration::from_millis(network.min_request_interval_ms))
+        };
+        let host = reqwest::Url::parse(url)
+            .ok()
+            .and_then(|u| u.host_str().map(|h| format!("{}:{}", h, u.port_or_known_default().unwrap_or(0))))
+            .unwrap_or_default();
+
+        let state = {
+            let mut hosts = self.hosts.lock().unwrap();
+            let entry = hosts.entry(host).or_insert_with(|| Arc::new(HostLimit::new(limit)));
+            if entry.limit != limit {
+                *entry = Arc::new(HostLimit::new(limit));
+            }
+            entry.clone()
+        };
+
+        let slot = state.slots.clone().acquire_owned().await.expect("host semaphore is never closed");
+        let wait = {
+            let mut next_start = state.next_start.lock().unwrap();
+            let now = Instant::now();
+            let start = (*next_start).max(now);
+            *next_start = start + interval;
+            start - now
+        };
+        tokio::time::sleep(wait).await;
+        RequestPermit { _slot: slot }
+    }
+
+    /// Client for site pages: keeps cookies, and a whole request may take up to `read_timeout_secs`
+    pub fn scraper(&self) -> Result<Client> {
+        self.current().map(|c| c.0)
+    }
+
+    /// Client for video files: no overall timeout, callers bound each chunk with `read_timeout`
+    pub fn download(&self) -> Result<Client> {
+        self.current().map(|c| c.1)
+    }
+
+    /// How long to wait for the next chunk of a download
+    pub fn read_timeout(&self) -> Duration {
+        Duration::from_secs(self.settings.read().unwrap().network.read_timeout_secs)
+    }
+
+    fn current(&self) -> Result<(Client, Client)> {
+        let (network, user_agent) = {
+            let settings = self.settings.read().unwrap();
+            (settings.network.clone(), settings.user_agent.clone())
+        };
+        let key = format!("{:?}|{}", network, user_agent);
+
+        let mut cached = self.cached.lock().unwrap();
+        if let Some(c) = cached.as_ref().filter(|c| c.key == key) {
+            return Ok((c.scraper.clone(), c.download.clone()));
+        }
+        let scraper = client_builder(&network, &user_agent)?
+            .cookie_store(true)
+            .timeout(Duration::from_secs(network.read_timeout_secs))
+            .build()?;
+        let download = client_builder(&network, &user_agent)?.build()?;
+        *cached = Some(Cached {
+            key,
+            scraper: scraper.clone(),
+            download: download.clone(),
+        });
+        Ok((scraper, download))
+    }
+}
+
+/// Builder with the proxy, timeouts, headers, CA bundle and address family of `network`
+pub fn client_builder(network: &NetworkSettings, user_agent: &str) -> Result<ClientBuilder> {
+    let mut headers = HeaderMap::new();
+    for (name, value) in &network.headers {
+        headers.insert(
+            HeaderName::from_bytes(name.as_bytes()).map_err(|_| anyhow!("Invalid header name '{}'", name))?,
+            HeaderValue::from_str(value).map_err(|_| anyhow!("Invalid value for header '{}'", name))?,
+        );
+    }
+
+    let mut builder = ClientBuilder::new()
+        .user_agent(user_agent)
+        .default_headers(headers)
+        .connect_timeout(Duration::from_secs(network.connect_timeout_secs));
+
+    let proxy = network.proxy.trim();
+    if !proxy.is_empty() {
+        let mut proxy = reqwest::Proxy::all(proxy)?;
+        if !network.proxy_username.is_empty() {
+            proxy = proxy.basic_auth(&network.proxy_username, &network.proxy_password);
+        }
+        builder = builder.proxy(proxy);
+    }
+
+    if !network.ca_bundle.as_os_str().is_empty() {
+        let pem = std::fs::read(&network.ca_bundle)
+            .map_err(|e| anyhow!("CA bundle {}: {}", network.ca_bundle.display(), e))?;
+        for cert in reqwest::Certificate::from_pem_bundle(&pem)? {
+            builder = builder.add_root_certificate(cert);
+        }
+    }
+
+    // Binding to the unspecified address of a family limits connections to that family
+    builder = match network.ip_version {
+        IpVersion::Auto => builder,
+        IpVersion::Ipv4 => builder.local_address(IpAddr::V4(Ipv4Addr::UNSPECIFIED)),
+        IpVersion::Ipv6 => builder.local_address(IpAddr::V6(Ipv6Addr::UNSPECIFIED)),
+    };
+    Ok(builder)
+}
+
+/// Next chunk of a response body, failing when none arrives within `timeout`
+pub async fn next_chunk(response: &mut reqwest::Response, timeout: Duration) -> Result<Option<bytes::Bytes>> {
+    match tokio::time::timeout(timeout, response.chunk()).await {
+        Ok(chunk) => Ok(chunk?),
+        Err(_) => Err(anyhow!("No data received for {} seconds", timeout.as_secs())),
+    }
+}
@@ -0,0 +1,215 @@
+use crate::models::{ManagerEvent, Notification, NotificationKind};
+use crate::store;
+use crate::unix_timestamp;
+use anyhow::Result;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+use tokio::sync::{broadcast, mpsc};
+
+/// Oldest notifications are dropped past this many records
+const MAX_NOTIFICATIONS: usize = 200;
+
+/// Destination for new notifications (OS notifications, terminal bell, ...)
+pub trait NotificationSink: Send + Sync {
+    fn deliver(&self, notification: &Notification);
+}
+
+/// Turns manager events into persisted notification records and forwards them to sinks
+pub struct NotificationCenter {
+    notifications: Mutex<Vec<Notification>>,
+    sinks: RwLock<Vec<Box<dyn NotificationSink>>>,
+    path: PathBuf,
+}
+
+impl NotificationCenter {
+    /// Load the notification feed from `notifications.json` in the given config directory
+    pub fn new(config_dir: &Path) -> Result<Self> {
+        fs::create_dir_all(config_dir)?;
+        let path = config_dir.join("notifications.json");
+
+        let notifications = store::load_list(&path)?;
+
+        Ok(Self {
+            notifications: Mutex::new(notifications),
+            sinks: RwLock::new(Vec::new()),
+            path,
+        })
+    }
+
+    pub fn add_sink(&self, sink: Box<dyn NotificationSink>) {
+        self.sinks.write().unwrap().push(sink);
+    }
+
+    /// Record a notification and deliver it to every sink
+    pub fn notify(
+        &self,
+        kind: NotificationKind,
+        title: String,
+        body: String,
+        job_id: Option<String>,
+    ) -> Result<Notification> {
+        let notification = {
+            let mut notifications = self.notifications.lock().unwrap();
+            let id = notifications.iter().map(|n| n.id).max().unwrap_or(0) + 1;
+            let notification = Notification {
+                id,
+                kind,
+                title,
+                body,
+                created_at: unix_timestamp(),
+                read: false,
+                job_id,
+            };
+            notifications.push(notification.clone());
+            if notifications.len() > MAX_NOTIFICATIONS {
+                let excess = notifications.len() - MAX_NOTIFICATIONS;
+                notifications.drain(..excess);
+            }
+            notification
+        };
+        self.save()?;
+
+        for sink in self.sinks.read().unwrap().iter() {
+            sink.deliver(&notification);
+        }
+        Ok(notification)
+    }
+
+    /// Record a notification for a manager event, if the event warrants one
+    pub fn handle_event(&self, event: &ManagerEvent) -> Result<Option<Notification>> {
+        let (kind, title, body, job_id) = match event {
+            ManagerEvent::EpisodeQueued {
+                anime_title,
+                anime_url,
+                episode_number,
+            } => (
+                NotificationKind::NewEpisode,
+                "New episode queued".to_string(),
+                format!("{} - Episode {}", anime_title, episode_number),
+                anime_url.clone(),
+            ),
+            ManagerEvent::JobFinished { job_id, job_name } => (
+                NotificationKind::JobFinished,
+                "Download finished".to_string(),
+                format!("{} is ready to watch", job_name),
+                job_id.clone(),
+            ),
+            ManagerEvent::TaskFailed {
+                job_id,
+                job_name,
+                episode_number,
+                error,
+                ..
+            } => (
+                NotificationKind::TaskFailed,
+                "Download failed".to_string(),
+                format!("{}{}: {}", job_name, episode_suffix(*episode_number), error),
+                job_id.clone(),
+            ),
+            ManagerEvent::LinkExpired {
+                job_id,
+                job_name,
+                episode_number,
+                ..
+            } => (
+                NotificationKind::LinkExpired,
+                "Download paused".to_string(),
+                format!(
+                    "{}{}: link expired and could not be refreshed",
+                    job_name,
+                    episode_suffix(*episode_number)
+                ),
+                job_id.clone(),
+            ),
+            ManagerEvent::TaskCompleted { .. }
+            | ManagerEvent::SettingsChanged { .. }
+            | ManagerEvent::SettingsRejected { .. } => return Ok(None),
+        };
+        self.notify(kind, title, body, Some(job_id)).map(Some)
+    }
+
+    /// Consume manager events until the channel closes.
+    /// Spawn this on the runtime of the hosting application.
+    pub async fn run(self: Arc<Self>, mut events: broadcast::Receiver<ManagerEvent>) {
+        loop {
+            match events.recv().await {
+                Ok(event) => self.record(&event),
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    tracing::warn!("Notification listener skipped {} events", skipped);
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    }
+
+    /// Consume events forwarded from a daemon (see `DaemonClient::events`) until the
+    /// connection closes
+    pub async fn run_remote(self: Arc<Self>, mut events: mpsc::Receiver<ManagerEvent>) {
+        while let Some(event) = events.recv().await {
+            self.record(&event);
+        }
+    }
+
+    fn record(&self, event: &ManagerEvent) {
+        if let Err(e) = self.handle_event(event) {
+            tracing::warn!("Could not save notification: {:#}", e);
+        }
+    }
+
+    /// All notifications, newest first
+    pub fn list(&self) -> Vec<Notification> {
+        let mut notifications = self.notifications.lock().unwrap().clone();
+        notifications.reverse();
+        notifications
+    }
+
+    pub fn unread_count(&self) -> usize {
+        self.notifications
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|n| !n.read)
+            .count()
+    }
+
+    /// Mark one notification as read. Returns false if the id is unknown.
+    pub fn mark_read(&self, id: u64) -> Result<bool> {
+        let found = {
+            let mut notifications = self.notifications.lock().unwrap();
+            match notifications.iter_mut().find(|n| n.id == id) {
+                Some(n) => {
+                    n.read = true;
+                    true
+                }
+                None => false,
+            }
+        };
+        if found {
+            self.save()?;
+        }
+        Ok(found)
+    }
+
+    pub fn mark_all_read(&self) -> Result<()> {
+        for n in self.notifications.lock().unwrap().iter_mut() {
+            n.read = true;
+        }
+        self.save()
+    }
+
+    pub fn clear(&self) -> Result<()> {
+        self.notifications.lock().unwrap().clear();
+        self.save()
+    }
+
+    fn save(&self) -> Result<()> {
+        store::save_list(&self.path, &self.notifications.lock().unwrap())
+    }
+}
+
+fn episode_suffix(episode_number: Option<u32>) -> String {
+    episode_number
+        .map(|n| format!(" (Episode {})", n))
+        .unwrap_or_default()
+}
@@ -0,0 +1,183 @@
+use crate::manager::{sanitize_file_name, DownloadManager};
+use crate::metadata::{episode_files, read_unique_id, xml_escape};
+use crate::models::AnimeInfo;
+use anyhow::{anyhow, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Playlist file formats understood by common players (VLC, mpv, Kodi)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlaylistFormat {
+    M3u8,
+    Xspf,
+}
+
+impl PlaylistFormat {
+    pub const ALL: [PlaylistFormat; 2] = [PlaylistFormat::M3u8, PlaylistFormat::Xspf];
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            PlaylistFormat::M3u8 => "m3u8",
+            PlaylistFormat::Xspf => "xspf",
+        }
+    }
+}
+
+impl FromStr for PlaylistFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "m3u8" | "m3u" => Ok(PlaylistFormat::M3u8),
+            "xspf" => Ok(PlaylistFormat::Xspf),
+            other => Err(anyhow!("Unknown playlist format '{}' (expected m3u8 or xspf)", other)),
+        }
+    }
+}
+
+/// One completed episode in a playlist. `path` is relative to the show folder.
+#[derive(Debug, Clone)]
+pub struct PlaylistEntry {
+    pub episode_number: u32,
+    pub title: String,
+    pub path: String,
+}
+
+/// Completed episode files in a show folder, ordered by episode number.
+/// Titles are `<Show> - Episode N` when the season info is known, otherwise the file stem.
+pub fn folder_entries(folder: &Path, info: Option<&AnimeInfo>) -> Vec<PlaylistEntry> {
+    episode_files(folder)
+        .into_iter()
+        .filter_map(|(path, number)| {
+            let file_name = path.file_name()?.to_string_lossy().to_string();
+            let title = match info {
+                Some(info) => format!("{} - Episode {}", info.title, number),
+                None => path.file_stem()?.to_string_lossy().to_string(),
+            };
+            Some(PlaylistEntry {
+                episode_number: number,
+                title,
+                path: file_name,
+            })
+        })
+        .collect()
+}
+
+pub fn render_m3u8(show_title: &str, entries: &[PlaylistEntry]) -> String {
+    let mut out = format!("#EXTM3U\n#PLAYLIST:{}\n", show_title);
+    for entry in entries {
+        out.push_str(&format!("#EXTINF:-1,{}\n{}\n", entry.title, entry.path));
+    }
+    out
+}
+
+pub fn render_xspf(show_title: &str, entries: &[PlaylistEntry]) -> String {
+    let mut out = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<playlist version=\"1\" xmlns=\"http://xspf.org/ns/0/\">\n",
+    );
+    out.push_str(&format!("  <title>{}</title>\n  <trackList>\n", xml_escape(show_title)));
+    for entry in entries {
+        out.push_str("    <track>\n");
+        out.push_str(&format!("      <location>{}</location>\n", xml_escape(&encode_uri_path(&entry.path))));
+        out.push_str(&format!("      <title>{}</title>\n", xml_escape(&entry.title)));
+        out.push_str(&format!("      <creator>{}</creator>\n", xml_escape(show_title)));
+        out.push_str(&format!("      <trackNum>{}</trackNum>\n", entry.episode_number));
+        out.push_str("    </track>\n");
+    }
+    out.push_str("  </trackList>\n</playlist>\n");
+    out
+}
+
+/// Write `<Show>.m3u8` / `<Show>.xspf` into the show folder. Returns the written files.
+pub fn write_playlists(
+    folder: &Path,
+    show_title: &str,
+    entries: &[PlaylistEntry],
+    formats: &[PlaylistFormat],
+) -> Result<Vec<PathBuf>> {
+    if entries.is_empty() {
+        return Err(anyhow!("No completed episodes in {}", folder.display()));
+    }
+
+    let stem = sanitize_file_name(show_title);
+    let mut written = Vec::new();
+    for format in formats {
+        let body = match format {
+            PlaylistFormat::M3u8 => render_m3u8(show_title, entries),
+            PlaylistFormat::Xspf => render_xspf(show_title, entries),
+        };
+        let path = folder.join(format!("{}.{}", stem, format.extension()));
+        fs::write(&path, body)?;
+        written.push(path);
+    }
+    Ok(written)
+}
+
+/// Show folder named by `target`: an existing folder, or the folder of the job whose id,
+/// season URL or title (case-insensitive) it is
+pub fn resolve_target(manager: &DownloadManager, target: &str) -> Result<PathBuf> {
+    let folder = PathBuf::from(target);
+    if folder.is_dir() {
+        return Ok(folder);
+    }
+    manager
+        .get_jobs()
+        .into_iter()
+        .find(|j| j.id == target || j.anime_url.as_deref() == Some(target) || j.name.eq_ignore_ascii_case(target))
+        .map(|job| manager.job_folder(&job))
+        .ok_or_else(|| anyhow!("No downloaded show matches '{}'", target))
+}
+
+/// Write playlists for a show folder in the library. The season info (for episode titles)
+/// is looked up from the job that downloaded the folder or its `tvshow.nfo`; without it,
+/// episodes are titled by file name and the folder name is used as the show title.
+pub async fn export_folder(
+    manager: &DownloadManager,
+    folder: &Path,
+    formats: &[PlaylistFormat],
+) -> Result<Vec<PathBuf>> {
+    let job = manager
+        .get_jobs()
+        .into_iter()
+        .find(|j| manager.job_folder(j) == folder);
+    let anime_url = job
+        .as_ref()
+        .and_then(|j| j.anime_url.clone())
+        .or_else(|| read_unique_id(folder));
+
+    let info = match anime_url {
+        Some(url) => match manager.get_cached_scraper().get_season(&url, false).await {
+            Ok(info) => Some(info),
+            Err(e) => {
+                tracing::debug!("Episode titles for {} unavailable: {}", folder.display(), e);
+                None
+            }
+        },
+        None => None,
+    };
+
+    let show_title = info
+        .as_ref()
+        .map(|i| i.title.clone())
+        .or_else(|| job.map(|j| j.name))
+        .or_else(|| folder.file_name().map(|n| n.to_string_lossy().to_string()))
+        .unwrap_or_else(|| "Playlist".to_string());
+
+    let entries = folder_entries(folder, info.as_ref());
+    write_playlists(folder, &show_title, &entries, formats)
+}
+
+/// Percent-encode a relative path for use as a URI reference (XSPF `<location>`)
+fn encode_uri_path(path: &str) -> String {
+    let mut out = String::with_capacity(path.len());
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                out.push(byte as char)
+            }
+            _ => out.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    out
+}
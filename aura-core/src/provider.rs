@@ -0,0 +1,238 @@
+use anyhow::{anyhow, Context, Result};
+use regex::Regex;
+use scraper::Selector;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::path::{Path, PathBuf};
+
+/// Current `version` of provider definitions. Older files are read as-is, newer ones rejected.
+pub const PROVIDER_VERSION: u32 = 1;
+
+/// Definition of animeheaven.me, compiled in
+const BUILTIN: &str = include_str!("../providers/animeheaven.toml");
+
+/// Selectors, patterns and URLs the scraper uses for one site. The built-in definition can be
+/// patched by `<config dir>/providers/<name>.toml` or `.json` (see `Provider::load`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Provider {
+    pub version: u32,
+    pub name: String,
+    pub base_url: String,
+    pub urls: ProviderUrls,
+    pub selectors: ProviderSelectors,
+    pub patterns: ProviderPatterns,
+    /// Override file merged over the built-in definition, if any
+    #[serde(skip)]
+    pub source: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProviderUrls {
+    pub search: String,
+    /// Query parameter carrying the search text
+    pub search_param: String,
+    pub new: String,
+    pub popular: String,
+    /// Query parameter carrying the page number, from 2 on
+    pub page_param: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProviderSelectors {
+    pub search_item: Sel,
+    pub search_link: Sel,
+    pub search_image: Sel,
+    pub search_name: Sel,
+    pub chart_item: Sel,
+    pub chart_link: Sel,
+    pub chart_image: Sel,
+    pub chart_title: Sel,
+    pub chart_time: Sel,
+    pub chart_episode: Sel,
+    pub title: Sel,
+    pub japanese_title: Sel,
+    pub info: Sel,
+    pub tags: Sel,
+    pub description: Sel,
+    pub cover: Sel,
+    pub episode_link: Sel,
+    pub episode_number: Sel,
+    pub episode_time: Sel,
+    pub download_link: Sel,
+}
+
+/// Regexes over page text; all but `download_text` capture the value in group 1
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProviderPatterns {
+    pub gate_id: Pattern,
+    pub year: Pattern,
+    pub episodes: Pattern,
+    pub score: Pattern,
+    pub download_text: Pattern,
+}
+
+/// A compiled CSS selector that keeps its source text for errors and `doctor` reports
+#[derive(Clone)]
+pub struct Sel {
+    pub css: String,
+    pub(crate) selector: Selector,
+}
+
+impl Sel {
+    pub fn new(css: &str) -> Result<Self> {
+        let selector = Selector::parse(css).map_err(|e| anyhow!("Invalid selector '{}': {:?}", css, e))?;
+        Ok(Self {
+            css: css.to_string(),
+            selector,
+        })
+    }
+}
+
+impl std::fmt::Debug for Sel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Sel({:?})", self.css)
+    }
+}
+
+impl Serialize for Sel {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.css)
+    }
+}
+
+impl<'de> Deserialize<'de> for Sel {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let css = String::deserialize(deserializer)?;
+        Sel::new(&css).map_err(serde::de::Error::custom)
+    }
+}
+
+/// A compiled regex that serializes as its source
+#[derive(Debug, Clone)]
+pub struct Pattern(pub Regex);
+
+impl Serialize for Pattern {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.0.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for Pattern {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let source = String::deserialize(deserializer)?;
+        Regex::new(&source).map(Pattern).map_err(serde::de::Error::custom)
+    }
+}
+
+impl Provider {
+    /// The definition shipped with Aura
+    pub fn builtin() -> Self {
+        let provider: Provider = toml::from_str(BUILTIN).expect("built-in provider definition is valid");
+        provider.validate().expect("built-in provider definition is valid");
+        provider
+    }
+
+    /// `load`, falling back to the built-in definition when the override is broken.
+    /// A broken override shouldn't keep the app from starting; `doctor scraper` shows the error.
+    pub fn load_or_builtin(config_dir: &Path) -> Self {
+        Self::load(config_dir).unwrap_or_else(|e| {
+            tracing::warn!("{:#}; using the built-in definition", e);
+            Self::builtin()
+        })
+    }
+
+    /// The built-in definition, patched by `<config_dir>/providers/<name>.toml` or `.json`
+    /// when one exists. An override must state its `version`; keys it leaves out keep their
+    /// built-in values. Errors name the file and the offending key.
+    pub fn load(config_dir: &Path) -> Result<Self> {
+        let builtin: toml::Table = toml::from_str(BUILTIN)?;
+        let name = builtin.get("name").and_then(|n| n.as_str()).unwrap_or_default().to_string();
+        let dir = config_dir.join("providers");
+        let Some(path) = ["toml", "json"]
+            .iter()
+            .map(|ext| dir.join(format!("{}.{}", name, ext)))
+            .find(|p| p.exists())
+        else {
+            return Ok(Self::builtin());
+        };
+
+        let load = || -> Result<Self> {
+            let data = std::fs::read_to_string(&path)?;
+            let patch: toml::Table = if path.extension().is_some_and(|e| e == "json") {
+                let json: serde_json::Value = serde_json::from_str(&data)?;
+                match toml::Value::try_from(json)? {
+                    toml::Value::Table(table) => table,
+                    _ => return Err(anyhow!("Expected an object")),
+                }
+            } else {
+                toml::from_str(&data)?
+            };
+            match patch.get("version") {
+                Some(toml::Value::Integer(v)) if *v >= 1 && *v <= PROVIDER_VERSION as i64 => {}
+                Some(toml::Value::Integer(v)) => {
+                    return Err(anyhow!("version {} is not supported (up to {}); update Aura", v, PROVIDER_VERSION))
+                }
+                _ => return Err(anyhow!("version is required")),
+            }
+
+            let mut table = builtin.clone();
+            patch_table(&mut table, patch);
+            // The message ends with the key path ("... in `selectors.title`") on its own line
+            let mut provider: Provider = toml::Value::Table(table)
+                .try_into()
+                .map_err(|e: toml::de::Error| anyhow!("{}", e.to_string().trim().replace('\n', " ")))?;
+            provider.validate()?;
+            provider.source = Some(path.clone());
+            Ok(provider)
+        };
+        load().with_context(|| format!("Provider definition {}", path.display()))
+    }
+
+    /// Checks beyond syntax: capture groups the parsers rely on, and URLs that can be joined
+    pub fn validate(&self) -> Result<()> {
+        let patterns = &self.patterns;
+        for (key, pattern) in [
+            ("gate_id", &patterns.gate_id),
+            ("year", &patterns.year),
+            ("episodes", &patterns.episodes),
+            ("score", &patterns.score),
+        ] {
+            if pattern.0.captures_len() < 2 {
+                return Err(anyhow!("patterns.{} must have a capture group", key));
+            }
+        }
+        reqwest::Url::parse(&self.base_url).map_err(|e| anyhow!("base_url: {}", e))?;
+        for (key, path) in [("search", &self.urls.search), ("new", &self.urls.new), ("popular", &self.urls.popular)] {
+            if path.is_empty() || path.contains("://") {
+                return Err(anyhow!("urls.{} must be a path on the site", key));
+            }
+        }
+        for (key, param) in [("search_param", &self.urls.search_param), ("page_param", &self.urls.page_param)] {
+            if param.is_empty() {
+                return Err(anyhow!("urls.{} is empty", key));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for Provider {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+/// Replace the keys of `base` present in `patch`, recursing into tables
+fn patch_table(base: &mut toml::Table, patch: toml::Table) {
+    for (key, value) in patch {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(existing)), toml::Value::Table(table)) => patch_table(existing, table),
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
@@ -1,129 +1,221 @@
-use crate::models::{AnimeInfo, Episode, ListEntry, SearchResult, ScrapeError, ScrapeResult};
-use reqwest::{Client, ClientBuilder};
-use scraper::{Html, Selector};
-use std::sync::Arc;
-use std::time::Duration;
-use tokio::sync::Mutex;
-
-lazy_static::lazy_static! {
-    static ref SEL_SEARCH_ITEM: Selector = Selector::parse(".similarimg").unwrap();
-    static ref SEL_SEARCH_LINK: Selector = Selector::parse("a[href*='anime.php']").unwrap();
-    static ref SEL_SEARCH_IMG: Selector = Selector::parse("img.coverimg").unwrap();
-    static ref SEL_SEARCH_NAME: Selector = Selector::parse(".similarname a").unwrap();
-
-    static ref SEL_CHART_ITEM: Selector = Selector::parse(".chart").unwrap();
-    static ref SEL_CHART_LINK: Selector = Selector::parse(".chartimg a").unwrap();
-    static ref SEL_CHART_IMG: Selector = Selector::parse("img.coverimg").unwrap();
-    static ref SEL_CHART_TITLE: Selector = Selector::parse(".charttitle a").unwrap();
-    static ref SEL_CHART_TIME: Selector = Selector::parse(".charttimer").unwrap();
-    static ref SEL_CHART_EP: Selector = Selector::parse(".chartepm").unwrap();
-
-    static ref SEL_INFO_TITLE: Selector = Selector::parse(".infotitle").unwrap();
-    static ref SEL_EPISODE_LINK: Selector = Selector::parse(".linetitle2 a").unwrap();
-
-    static ref SEL_DOWNLOAD_LINK: Selector = Selector::parse("a").unwrap();
-
-    static ref SEL_INFO_YEAR: Selector = Selector::parse(".infoyear").unwrap();
-    static ref SEL_INFO_TAGS: Selector = Selector::parse(".infotags .boxitem").unwrap();
-    static ref SEL_INFO_DES: Selector = Selector::parse(".infodes").unwrap();
-    static ref SEL_INFO_TITLE_JP: Selector = Selector::parse(".infotitlejp").unwrap();
-}
+use crate::config::Settings;
+use crate::models::{AiringStatus, AnimeInfo, Episode, ListEntry, SearchResult, ScrapeError, ScrapeResult};
+use crate::network::HttpClients;
+use crate::provider::{Provider, Sel};
+use reqwest::Client;
+use scraper::{ElementRef, Html};
+use serde::Serialize;
+use std::sync::{Arc, RwLock};
 
 struct AnimeClient {
-    client: Client,
+    clients: Arc<HttpClients>,
     base_url: String,
 }
 
 impl AnimeClient {
-    fn new() -> ScrapeResult<Self> {
-        let client = ClientBuilder::new()
-            .cookie_store(true)
-            .timeout(Duration::from_secs(30))
-            .user_agent("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36")
-            .build()?;
-
-        Ok(Self {
-            client,
-            base_url: "https://animeheaven.me/".to_string(),
-        })
+    fn new(clients: Arc<HttpClients>, base_url: &str) -> Self {
+        Self {
+            clients,
+            base_url: base_url.to_string(),
+        }
     }
 
-    async fn get_text(&self, path: &str) -> ScrapeResult<String> {
-        let url = format!("{}{}", self.base_url, path);
-        let response = self
-            .client
-            .get(&url)
-            .header("Referer", "https://animeheaven.me/")
-            .send()
-            .await?
-            .text()
-            .await?;
-        Ok(response)
+    /// Client built from the current network settings
+    fn http(&self) -> ScrapeResult<Client> {
+        self.clients.scraper().map_err(|e| ScrapeError::Client(e.to_string()))
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}/{}", self.base_url.trim_end_matches('/'), path.trim_start_matches('/'))
+    }
+
+    /// GET a site page within the per-host request limits. `cookie` is sent as-is.
+    async fn get_text(&self, path: &str, cookie: Option<String>) -> ScrapeResult<String> {
+        self.fetch(&self.url(path), cookie).await
+    }
+
+    async fn fetch(&self, url: &str, cookie: Option<String>) -> ScrapeResult<String> {
+        let _permit = self.clients.throttle(url).await;
+        let mut request = self.http()?.get(url).header("Referer", &self.base_url);
+        if let Some(cookie) = cookie {
+            request = request.header("Cookie", cookie);
+        }
+        let response = request.send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(ScrapeError::Status {
+                status: status.as_u16(),
+                url: url.to_string(),
+            });
+        }
+        Ok(response.text().await?)
     }
 }
 
+/// Site scraper. Methods take `&self` and can run concurrently; requests are limited per host
+/// by `network.max_requests_per_host` and spaced by `network.min_request_interval_ms`.
+/// Selectors, patterns and URLs come from a `Provider` definition.
 pub struct AnimeScraper {
-    client: Arc<Mutex<AnimeClient>>,
+    client: AnimeClient,
+    provider: Arc<Provider>,
 }
 
 impl AnimeScraper {
+    /// Scraper with the default network settings
     pub fn new() -> Self {
+        Self::with_clients(Arc::new(HttpClients::new(Arc::new(RwLock::new(Settings::default())))))
+    }
+
+    /// Scraper sharing `clients` (proxy, timeouts, user agent) with the downloader
+    pub fn with_clients(clients: Arc<HttpClients>) -> Self {
+        let provider = Arc::new(Provider::builtin());
         Self {
-            client: Arc::new(Mutex::new(
-                AnimeClient::new().expect("Failed to create HTTP client"),
-            )),
+            client: AnimeClient::new(clients, &provider.base_url),
+            provider,
         }
     }
 
+    /// Read pages with `provider` (e.g. from `Provider::load`), fetching from its `base_url`
+    pub fn with_provider(mut self, provider: Arc<Provider>) -> Self {
+        self.client.base_url = provider.base_url.clone();
+        self.provider = provider;
+        self
+    }
+
+    pub fn provider(&self) -> Arc<Provider> {
+        self.provider.clone()
+    }
+
+    /// Fetch pages from `base_url` instead of the live site, e.g. a mirror or a test server
+    pub fn with_base_url(self, base_url: &str) -> Self {
+        let mut provider = (*self.provider).clone();
+        provider.base_url = base_url.to_string();
+        self.with_provider(Arc::new(provider))
+    }
+
+    /// Results in the site's order; `CachedScraper::search_with` filters and ranks them
     pub async fn search(&self, query: &str) -> ScrapeResult<Vec<SearchResult>> {
-        let client = self.client.lock().await;
-        let html = client.get_text(&format!("/search.php?s={}", query)).await?;
-        parse_search(&html)
+        self.search_page(query, 1).await
     }
 
-    pub async fn get_season(&self, url: &str) -> ScrapeResult<AnimeInfo> {
-        let client = self.client.lock().await;
-        let path = if url.starts_with("http") {
-            url.replacen("https://animeheaven.me", "", 1)
-        } else {
-            url.to_string()
-        };
+    /// Page `page` (from 1) of the results for `query`; empty past the last page
+    pub async fn search_page(&self, query: &str, page: u32) -> ScrapeResult<Vec<SearchResult>> {
+        let html = self.client.fetch(&self.search_url(query, page)?, None).await?;
+        parse_search(&self.provider, &html)
+    }
 
-        let html = client.get_text(&path).await?;
-        parse_season(&html, &path)
+    fn search_url(&self, query: &str, page: u32) -> ScrapeResult<String> {
+        let urls = &self.provider.urls;
+        self.page_url(&urls.search, Some((urls.search_param.as_str(), query)), page)
+    }
+
+    /// Absolute URL of `path`, with `param` and, from page 2 on, `urls.page_param`
+    fn page_url(&self, path: &str, param: Option<(&str, &str)>, page: u32) -> ScrapeResult<String> {
+        let page_number = page.to_string();
+        let page_param = (page > 1).then_some((self.provider.urls.page_param.as_str(), page_number.as_str()));
+        let params: Vec<(&str, &str)> = param.into_iter().chain(page_param).collect();
+        if params.is_empty() {
+            return Ok(self.client.url(path));
+        }
+        reqwest::Url::parse_with_params(&self.client.url(path), params)
+            .map(String::from)
+            .map_err(|e| ScrapeError::Client(e.to_string()))
+    }
+
+    pub async fn get_season(&self, url: &str) -> ScrapeResult<AnimeInfo> {
+        let path = season_path(&self.provider, url);
+        let html = self.client.get_text(&path, None).await?;
+        parse_season(&self.provider, &html, &path)
     }
 
     pub async fn get_download_link(&self, episode: &Episode) -> ScrapeResult<String> {
-        let client_lock = self.client.lock().await;
-        let http_client = &client_lock.client;
-
-        let url = format!("https://animeheaven.me/{}", episode.url);
-
-        let response = http_client
-            .get(&url)
-            .header("Referer", "https://animeheaven.me/")
-            .header("Cookie", format!("key={}", episode.gate_id))
-            .send()
-            .await?
-            .text()
+        let response = self
+            .client
+            .get_text(&episode.url, Some(format!("key={}", episode.gate_id)))
             .await?;
 
-        match parse_download_link(&response) {
-            Some(link) => Ok(link),
-            None => Err(ScrapeError::Parse("Download link not found".into())),
-        }
+        parse_download_link(&self.provider, &response)
     }
 
     pub async fn get_new(&self) -> ScrapeResult<Vec<ListEntry>> {
-        let client = self.client.lock().await;
-        let html = client.get_text("/new.php").await?;
-        parse_list(&html, false)
+        self.get_new_page(1).await
+    }
+
+    /// Page `page` (from 1) of `/new.php`; empty past the last page
+    pub async fn get_new_page(&self, page: u32) -> ScrapeResult<Vec<ListEntry>> {
+        let html = self.client.fetch(&self.page_url(&self.provider.urls.new, None, page)?, None).await?;
+        parse_list(&self.provider, &html, false)
     }
 
     pub async fn get_popular(&self) -> ScrapeResult<Vec<ListEntry>> {
-        let client = self.client.lock().await;
-        let html = client.get_text("/popular.php").await?;
-        parse_list(&html, true)
+        self.get_popular_page(1).await
+    }
+
+    /// Page `page` (from 1) of `/popular.php`. `rank` counts from 1 on every page;
+    /// `CachedScraper::get_popular_page` numbers entries across pages.
+    pub async fn get_popular_page(&self, page: u32) -> ScrapeResult<Vec<ListEntry>> {
+        let html = self.client.fetch(&self.page_url(&self.provider.urls.popular, None, page)?, None).await?;
+        parse_list(&self.provider, &html, true)
+    }
+
+    /// Fetch every kind of page the scraper reads and count the matches of each selector:
+    /// a search for `query`, the season page of its first result, the gate page of that
+    /// season's first episode, and the new and popular listings.
+    pub async fn diagnose(&self, query: &str) -> Vec<PageCheck> {
+        let mut checks = Vec::new();
+
+        let (search, html) = match self.search_url(query, 1) {
+            Ok(url) => self.check_page(PageKind::Search, &url, None).await,
+            Err(e) => (PageCheck::failed(PageKind::Search, "", e.to_string()), None),
+        };
+        checks.push(search);
+
+        let season = html
+            .and_then(|html| parse_search(&self.provider, &html).ok())
+            .and_then(|results| results.into_iter().next())
+            .map(|result| season_path(&self.provider, &result.url));
+        match season {
+            Some(path) => {
+                let (check, html) = self.check_page(PageKind::Season, &self.client.url(&path), None).await;
+                checks.push(check);
+                let episode = html
+                    .and_then(|html| parse_season(&self.provider, &html, &path).ok())
+                    .and_then(|info| info.episodes.into_iter().next());
+                match episode {
+                    Some(ep) => {
+                        let cookie = Some(format!("key={}", ep.gate_id));
+                        checks.push(self.check_page(PageKind::Gate, &self.client.url(&ep.url), cookie).await.0);
+                    }
+                    None => checks.push(PageCheck::failed(PageKind::Gate, "", "Skipped: no episode found".into())),
+                }
+            }
+            None => {
+                checks.push(PageCheck::failed(PageKind::Season, "", "Skipped: no search result".into()));
+                checks.push(PageCheck::failed(PageKind::Gate, "", "Skipped: no season page".into()));
+            }
+        }
+
+        let urls = &self.provider.urls;
+        for (page, path) in [(PageKind::New, &urls.new), (PageKind::Popular, &urls.popular)] {
+            checks.push(self.check_page(page, &self.client.url(path), None).await.0);
+        }
+        checks
+    }
+
+    /// The page's report, and its HTML when it could be fetched
+    async fn check_page(&self, page: PageKind, url: &str, cookie: Option<String>) -> (PageCheck, Option<String>) {
+        match self.client.fetch(url, cookie).await {
+            Ok(html) => {
+                let check = PageCheck {
+                    page,
+                    url: url.to_string(),
+                    error: None,
+                    selectors: check_selectors(&self.provider, page, &html),
+                };
+                (check, Some(html))
+            }
+            Err(e) => (PageCheck::failed(page, url, e.to_string()), None),
+        }
     }
 }
 
@@ -133,27 +225,69 @@ impl Default for AnimeScraper {
     }
 }
 
+/// Turn a site-relative path (cover images, links) into an absolute URL on `provider.base_url`
+pub fn absolute_url(provider: &Provider, path: &str) -> String {
+    if path.starts_with("http") {
+        path.to_string()
+    } else {
+        format!("{}/{}", provider.base_url.trim_end_matches('/'), path.trim_start_matches('/'))
+    }
+}
+
+/// Normalize a season URL to the site-relative path used as `AnimeInfo.url`, always with a
+/// leading `/`, so `anime.php?x`, `/anime.php?x` and the absolute URL give the same key.
+/// Only `provider.base_url` is stripped; links to other hosts are kept whole.
+pub fn season_path(provider: &Provider, url: &str) -> String {
+    let base = provider.base_url.trim_end_matches('/');
+    let path = match url.strip_prefix(base) {
+        Some(path) if path.is_empty() || path.starts_with('/') => path,
+        _ if url.contains("://") => return url.to_string(),
+        _ => url,
+    };
+    format!("/{}", path.trim_start_matches('/'))
+}
+
 // ============ PARSING FUNCTIONS ============
 
-fn parse_search(html: &str) -> ScrapeResult<Vec<SearchResult>> {
-    let document = Html::parse_document(html);
+/// First element under `el` matching `sel`, or `NotFound` naming the selector and page
+fn first<'a>(el: ElementRef<'a>, sel: &Sel, page: &str) -> ScrapeResult<ElementRef<'a>> {
+    el.select(&sel.selector).next().ok_or_else(|| ScrapeError::NotFound {
+        selector: sel.css.clone(),
+        page: page.to_string(),
+    })
+}
+
+/// Attribute `name` of an element found by `sel`, or `NotFound` for `css[name]`
+fn attr<'a>(el: ElementRef<'a>, name: &str, sel: &Sel, page: &str) -> ScrapeResult<&'a str> {
+    el.value().attr(name).ok_or_else(|| ScrapeError::NotFound {
+        selector: format!("{}[{}]", sel.css, name),
+        page: page.to_string(),
+    })
+}
+
+fn text_of(el: ElementRef) -> String {
+    el.text().collect::<String>().trim().to_string()
+}
+
+/// Results of a `/search.php` page
+pub fn parse_search(provider: &Provider, html: &str) -> ScrapeResult<Vec<SearchResult>> {
+    parse_similar(provider, &Html::parse_document(html), "search page")
+}
+
+/// Show cards (`.similarimg`): search results, and related shows on a season page
+fn parse_similar(provider: &Provider, document: &Html, page: &str) -> ScrapeResult<Vec<SearchResult>> {
+    let sel = &provider.selectors;
     let mut results = Vec::new();
 
-    for item in document.select(&SEL_SEARCH_ITEM) {
-        let link = item
-            .select(&SEL_SEARCH_LINK)
-            .next()
-            .ok_or(ScrapeError::NotFound)?;
-        let href = link.value().attr("href").ok_or(ScrapeError::NotFound)?;
+    for item in document.select(&sel.search_item.selector) {
+        let link = first(item, &sel.search_link, page)?;
+        let href = attr(link, "href", &sel.search_link, page)?;
 
-        let img = item
-            .select(&SEL_SEARCH_IMG)
-            .next()
-            .ok_or(ScrapeError::NotFound)?;
-        let src = img.value().attr("src").ok_or(ScrapeError::NotFound)?;
+        let img = first(item, &sel.search_image, page)?;
+        let src = attr(img, "src", &sel.search_image, page)?;
         let alt = img.value().attr("alt").unwrap_or("Unknown");
 
-        let name_el = item.select(&SEL_SEARCH_NAME).next();
+        let name_el = item.select(&sel.search_name.selector).next();
         let title = name_el
             .map(|n| n.inner_html().trim().to_string())
             .unwrap_or_else(|| alt.to_string());
@@ -162,41 +296,38 @@ fn parse_search(html: &str) -> ScrapeResult<Vec<SearchResult>> {
             title,
             url: href.to_string(),
             image: src.to_string(),
+            japanese_title: None,
+            relevance: 0.0,
         });
     }
     Ok(results)
 }
 
-fn parse_list(html: &str, is_popular: bool) -> ScrapeResult<Vec<ListEntry>> {
+/// Entries of `/new.php`, or of `/popular.php` (ranked) when `is_popular` is set
+pub fn parse_list(provider: &Provider, html: &str, is_popular: bool) -> ScrapeResult<Vec<ListEntry>> {
+    let sel = &provider.selectors;
     let document = Html::parse_document(html);
+    let page = if is_popular { "popular page" } else { "new page" };
     let mut results = Vec::new();
 
-    for (idx, item) in document.select(&SEL_CHART_ITEM).enumerate() {
-        let link = item
-            .select(&SEL_CHART_LINK)
-            .next()
-            .ok_or(ScrapeError::NotFound)?;
-        let href = link.value().attr("href").ok_or(ScrapeError::NotFound)?;
+    for (idx, item) in document.select(&sel.chart_item.selector).enumerate() {
+        let link = first(item, &sel.chart_link, page)?;
+        let href = attr(link, "href", &sel.chart_link, page)?;
 
-        let img = item
-            .select(&SEL_CHART_IMG)
-            .next()
-            .ok_or(ScrapeError::NotFound)?;
-        let src = img.value().attr("src").ok_or(ScrapeError::NotFound)?;
+        let img = first(item, &sel.chart_image, page)?;
+        let src = attr(img, "src", &sel.chart_image, page)?;
 
-        let title_el = item
-            .select(&SEL_CHART_TITLE)
-            .next()
-            .ok_or(ScrapeError::NotFound)?;
-        let title = title_el.inner_html().trim().to_string();
+        let title = first(item, &sel.chart_title, page)?.inner_html().trim().to_string();
 
-        let time_el = item.select(&SEL_CHART_TIME).next();
-        let time_ago = time_el
-            .map(|e| e.text().collect::<String>().trim().to_string())
+        let time_ago = item
+            .select(&sel.chart_time.selector)
+            .next()
+            .map(text_of)
             .unwrap_or_default();
 
-        let ep_el = item.select(&SEL_CHART_EP).next();
-        let latest_ep = ep_el
+        let latest_ep = item
+            .select(&sel.chart_episode.selector)
+            .next()
             .map(|e| e.inner_html().trim().to_string())
             .unwrap_or_default();
 
@@ -216,34 +347,34 @@ fn parse_list(html: &str, is_popular: bool) -> ScrapeResult<Vec<ListEntry>> {
     Ok(results)
 }
 
-fn parse_season(html: &str, url: &str) -> ScrapeResult<AnimeInfo> {
+/// Details and episodes of a season page; `url` becomes `AnimeInfo.url`
+pub fn parse_season(provider: &Provider, html: &str, url: &str) -> ScrapeResult<AnimeInfo> {
+    let sel = &provider.selectors;
+    let patterns = &provider.patterns;
     let document = Html::parse_document(html);
+    let page = format!("season page {}", url);
 
-    let title_el = document
-        .select(&SEL_INFO_TITLE)
-        .next()
-        .ok_or(ScrapeError::NotFound)?;
-    let title = title_el.inner_html().trim().to_string();
+    let title = first(document.root_element(), &sel.title, &page)?
+        .inner_html()
+        .trim()
+        .to_string();
 
     let mut episodes: Vec<Episode> = Vec::new();
 
-    let gate_re = regex::Regex::new(r#"gate\("([^"]+)"\)"#).unwrap();
-
-    let sel_watch2 = Selector::parse(".watch2").unwrap();
-    let sel_watch1 = Selector::parse(".watch1").unwrap();
-
-    for ep in document.select(&SEL_EPISODE_LINK) {
+    for ep in document.select(&sel.episode_link.selector) {
         let onclick = ep.value().attr("onclick").unwrap_or("");
         let href = ep.value().attr("href").unwrap_or("").to_string();
 
-        let gate_id = gate_re
+        let gate_id = patterns
+            .gate_id
+            .0
             .captures(onclick)
             .and_then(|c| c.get(1))
             .map(|m| m.as_str().to_string())
             .unwrap_or_default();
 
         let number_str = ep
-            .select(&sel_watch2)
+            .select(&sel.episode_number.selector)
             .next()
             .map(|d| d.inner_html().trim().to_string())
             .unwrap_or_else(|| "0".to_string());
@@ -251,7 +382,7 @@ fn parse_season(html: &str, url: &str) -> ScrapeResult<AnimeInfo> {
         let number: u32 = number_str.parse().unwrap_or(0);
 
         if number > 0 {
-            let watch1_items: Vec<_> = ep.select(&sel_watch1).collect();
+            let watch1_items: Vec<_> = ep.select(&sel.episode_time.selector).collect();
             let time_str = watch1_items
                 .last()
                 .map(|d| d.inner_html().trim().to_string())
@@ -270,60 +401,193 @@ fn parse_season(html: &str, url: &str) -> ScrapeResult<AnimeInfo> {
 
     episodes.sort_by_key(|e| e.number);
 
-    // Extract new fields
-    let year_el = document.select(&SEL_INFO_YEAR).next();
-    let year = if let Some(el) = year_el {
-        let text = el.text().collect::<String>();
-        // Text format: "Episodes: 24 Year: 2018-2019 Score: 8.7"
-        // Simple regex to capture text between "Year:" and "Score" or end
-        let re = regex::Regex::new(r"Year:\s*(.*?)(?:Score|$)").unwrap();
-        re.captures(&text)
-            .and_then(|c| c.get(1))
-            .map(|m| m.as_str().trim().to_string())
-    } else {
-        None
-    };
+    // Text format: "Episodes: 24 Year: 2018-2019 Score: 8.7", with "Episodes: ?" while airing
+    let info_text = document
+        .select(&sel.info.selector)
+        .next()
+        .map(|el| el.text().collect::<String>().split_whitespace().collect::<Vec<_>>().join(" "));
+    let year = info_text
+        .as_deref()
+        .and_then(|text| patterns.year.0.captures(text))
+        .and_then(|c| c.get(1))
+        .map(|m| m.as_str().trim().to_string());
+    let episodes_field = info_text
+        .as_deref()
+        .and_then(|text| patterns.episodes.0.captures(text))
+        .map(|c| c[1].to_string());
+    let total_episodes = episodes_field.as_deref().and_then(|n| n.parse::<u32>().ok());
+    let score = info_text
+        .as_deref()
+        .and_then(|text| patterns.score.0.captures(text))
+        .and_then(|c| c[1].parse::<f32>().ok());
+    let status = episodes_field.map(|_| match total_episodes {
+        Some(total) if episodes.len() as u32 >= total => AiringStatus::Finished,
+        _ => AiringStatus::Airing,
+    });
+
+    let cover_image = document
+        .select(&sel.cover.selector)
+        .next()
+        .and_then(|img| img.value().attr("src"))
+        .map(|src| src.to_string());
+
+    let related = parse_similar(provider, &document, &page).unwrap_or_else(|e| {
+        tracing::debug!("Ignoring related shows: {}", e);
+        Vec::new()
+    });
 
     let tags = document
-        .select(&SEL_INFO_TAGS)
-        .map(|el| el.text().collect::<String>().trim().to_string())
+        .select(&sel.tags.selector)
+        .map(text_of)
         .filter(|s| !s.is_empty())
         .collect::<Vec<String>>();
-    
+
     let tags = if tags.is_empty() { None } else { Some(tags) };
 
-    let description = document
-        .select(&SEL_INFO_DES)
-        .next()
-        .map(|el| el.text().collect::<String>().trim().to_string());
+    let description = document.select(&sel.description.selector).next().map(text_of);
 
-    let japanese_title = document
-        .select(&SEL_INFO_TITLE_JP)
-        .next()
-        .map(|el| el.text().collect::<String>().trim().to_string());
+    let japanese_title = document.select(&sel.japanese_title.selector).next().map(text_of);
 
     Ok(AnimeInfo {
         title,
         url: url.to_string(),
         episodes,
-        related: vec![],
+        related,
         year,
         tags,
         description,
         japanese_title,
+        total_episodes,
+        score,
+        status,
+        cover_image,
     })
 }
 
-fn parse_download_link(html: &str) -> Option<String> {
+/// Video URL on an episode's gate page
+pub fn parse_download_link(provider: &Provider, html: &str) -> ScrapeResult<String> {
     let document = Html::parse_document(html);
+    let link = &provider.selectors.download_link;
+
+    for a in document.select(&link.selector) {
+        if provider.patterns.download_text.0.is_match(&a.inner_html()) {
+            return Ok(a.value().attr("href").unwrap_or_default().to_string());
+        }
+    }
+    Err(ScrapeError::NotFound {
+        selector: link.css.clone(),
+        page: "gate page".to_string(),
+    })
+}
+
+// ============ SELECTOR DRIFT ============
+
+/// Site pages the scraper reads
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PageKind {
+    Search,
+    Season,
+    Gate,
+    New,
+    Popular,
+}
+
+impl std::fmt::Display for PageKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.pad(match self {
+            PageKind::Search => "search",
+            PageKind::Season => "season",
+            PageKind::Gate => "gate",
+            PageKind::New => "new",
+            PageKind::Popular => "popular",
+        })
+    }
+}
+
+/// How many elements one selector matched on a page
+#[derive(Debug, Clone, Serialize)]
+pub struct SelectorCheck {
+    pub name: &'static str,
+    pub css: String,
+    pub matches: usize,
+    /// Parsing fails (or finds nothing) without it
+    pub required: bool,
+}
 
-    for a in document.select(&SEL_DOWNLOAD_LINK) {
-        let text = a.inner_html().to_lowercase();
-        let href = a.value().attr("href").unwrap_or("");
+impl SelectorCheck {
+    /// A required selector that no longer matches: the markup has likely changed
+    pub fn drifted(&self) -> bool {
+        self.required && self.matches == 0
+    }
+}
 
-        if text.contains("download") && href.contains("video.mp4") {
-            return Some(href.to_string());
+/// Selector report for one fetched page
+#[derive(Debug, Clone, Serialize)]
+pub struct PageCheck {
+    pub page: PageKind,
+    pub url: String,
+    /// Fetch error, or why the page was skipped
+    pub error: Option<String>,
+    pub selectors: Vec<SelectorCheck>,
+}
+
+impl PageCheck {
+    fn failed(page: PageKind, url: &str, error: String) -> Self {
+        Self {
+            page,
+            url: url.to_string(),
+            error: Some(error),
+            selectors: Vec::new(),
         }
     }
-    None
+
+    pub fn healthy(&self) -> bool {
+        self.error.is_none() && !self.selectors.iter().any(SelectorCheck::drifted)
+    }
+}
+
+/// Match counts of every selector the scraper uses on a `page`
+pub fn check_selectors(provider: &Provider, page: PageKind, html: &str) -> Vec<SelectorCheck> {
+    let sel = &provider.selectors;
+    let selectors: Vec<(&'static str, &Sel, bool)> = match page {
+        PageKind::Search => vec![
+            ("result", &sel.search_item, true),
+            ("result link", &sel.search_link, true),
+            ("result cover", &sel.search_image, true),
+            ("result name", &sel.search_name, false),
+        ],
+        PageKind::New | PageKind::Popular => vec![
+            ("entry", &sel.chart_item, true),
+            ("entry link", &sel.chart_link, true),
+            ("entry cover", &sel.chart_image, true),
+            ("entry title", &sel.chart_title, true),
+            ("entry time", &sel.chart_time, false),
+            ("entry episode", &sel.chart_episode, false),
+        ],
+        PageKind::Season => vec![
+            ("title", &sel.title, true),
+            ("episode link", &sel.episode_link, true),
+            ("episode number", &sel.episode_number, true),
+            ("episode time", &sel.episode_time, false),
+            ("info line", &sel.info, false),
+            ("tags", &sel.tags, false),
+            ("description", &sel.description, false),
+            ("japanese title", &sel.japanese_title, false),
+            ("cover", &sel.cover, false),
+            ("related", &sel.search_item, false),
+        ],
+        PageKind::Gate => vec![("download link", &sel.download_link, true)],
+    };
+
+    let document = Html::parse_document(html);
+    selectors
+        .into_iter()
+        .map(|(name, sel, required)| SelectorCheck {
+            name,
+            css: sel.css.clone(),
+            matches: document.select(&sel.selector).count(),
+            required,
+        })
+        .collect()
 }
@@ -0,0 +1,172 @@
+use crate::models::{AnimeInfo, SearchResult};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+lazy_static::lazy_static! {
+    static ref YEAR_RE: regex::Regex = regex::Regex::new(r"\d{4}").unwrap();
+}
+
+/// A search with optional filters. The site only searches by text, so `text` is sent with
+/// the request and the filters are checked against the season pages of the results
+/// (see `CachedScraper::search_with`).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchQuery {
+    pub text: String,
+    /// First year the show aired, inclusive
+    pub year_from: Option<u32>,
+    pub year_to: Option<u32>,
+    /// Every tag must be present (case-insensitive)
+    pub tags: Vec<String>,
+    pub kind: Option<AnimeKind>,
+    pub min_episodes: Option<u32>,
+}
+
+/// Broad type of a show. The site doesn't list it, so it is guessed from the title.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AnimeKind {
+    Tv,
+    Movie,
+    Ova,
+    Special,
+}
+
+impl AnimeKind {
+    pub fn of(info: &AnimeInfo) -> Self {
+        let title = normalize(&info.title);
+        let has = |word: &str| title.split(' ').any(|t| t == word);
+        if has("movie") || has("film") || has("gekijouban") {
+            AnimeKind::Movie
+        } else if has("ova") || has("oad") || has("ona") {
+            AnimeKind::Ova
+        } else if has("special") || has("specials") || has("recap") {
+            AnimeKind::Special
+        } else {
+            AnimeKind::Tv
+        }
+    }
+}
+
+impl std::fmt::Display for AnimeKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            AnimeKind::Tv => "tv",
+            AnimeKind::Movie => "movie",
+            AnimeKind::Ova => "ova",
+            AnimeKind::Special => "special",
+        })
+    }
+}
+
+impl FromStr for AnimeKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "tv" => Ok(AnimeKind::Tv),
+            "movie" => Ok(AnimeKind::Movie),
+            "ova" => Ok(AnimeKind::Ova),
+            "special" => Ok(AnimeKind::Special),
+            other => Err(anyhow::anyhow!("Unknown type '{}' (expected tv, movie, ova or special)", other)),
+        }
+    }
+}
+
+impl SearchQuery {
+    pub fn new(text: &str) -> Self {
+        Self {
+            text: text.to_string(),
+            ..Self::default()
+        }
+    }
+
+    /// Whether results need their season pages to be checked
+    pub fn has_filters(&self) -> bool {
+        self.year_from.is_some()
+            || self.year_to.is_some()
+            || !self.tags.is_empty()
+            || self.kind.is_some()
+            || self.min_episodes.is_some()
+    }
+
+    pub fn matches(&self, info: &AnimeInfo) -> bool {
+        if self.year_from.is_some() || self.year_to.is_some() {
+            let Some(year) = start_year(info) else {
+                return false;
+            };
+            if self.year_from.is_some_and(|from| year < from) || self.year_to.is_some_and(|to| year > to) {
+                return false;
+            }
+        }
+        if !self.tags.is_empty() {
+            let tags = info.tags.as_deref().unwrap_or_default();
+            let has_tag = |wanted: &String| tags.iter().any(|t| t.eq_ignore_ascii_case(wanted.trim()));
+            if !self.tags.iter().all(has_tag) {
+                return false;
+            }
+        }
+        if self.kind.is_some_and(|kind| kind != AnimeKind::of(info)) {
+            return false;
+        }
+        if self.min_episodes.is_some_and(|min| (info.episodes.len() as u32) < min) {
+            return false;
+        }
+        true
+    }
+}
+
+/// First year in `AnimeInfo.year`, which reads like "2018" or "2018-2019"
+fn start_year(info: &AnimeInfo) -> Option<u32> {
+    YEAR_RE.find(info.year.as_deref()?)?.as_str().parse().ok()
+}
+
+/// Lowercase words of `s`; punctuation becomes a word break. Letters of any script are kept.
+fn normalize(s: &str) -> String {
+    s.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// How well `query` matches `title`, from 0 (nothing in common) to 1 (the same title).
+/// A title containing the whole query scores from 0.8 up, higher the shorter it is. Otherwise
+/// the score comes from the query words found in the title (typos allowed) and overall similarity.
+pub fn title_relevance(query: &str, title: &str) -> f64 {
+    let query = normalize(query);
+    let title = normalize(title);
+    if query.is_empty() || title.is_empty() {
+        return 0.0;
+    }
+    if query == title {
+        return 1.0;
+    }
+    if title.contains(&query) {
+        return 0.8 + 0.2 * query.chars().count() as f64 / title.chars().count() as f64;
+    }
+
+    let title_words: Vec<&str> = title.split(' ').collect();
+    let query_words: Vec<&str> = query.split(' ').collect();
+    let found = query_words
+        .iter()
+        .filter(|q| {
+            title_words
+                .iter()
+                .any(|t| t.starts_with(**q) || strsim::jaro_winkler(q, t) >= 0.9)
+        })
+        .count();
+    let words = found as f64 / query_words.len() as f64;
+    (0.6 * words + 0.4 * strsim::jaro_winkler(&query, &title)).min(0.79)
+}
+
+/// Set `relevance` of every result to its best match on the title or the Japanese title, and
+/// sort the best first. Equal scores keep the site's order. The site's results have no
+/// Japanese title; it is only known when the caller filled it in from a season page.
+pub fn rank(query: &str, results: &mut [SearchResult]) {
+    for result in results.iter_mut() {
+        let japanese = result.japanese_title.as_deref().map_or(0.0, |jp| title_relevance(query, jp));
+        result.relevance = title_relevance(query, &result.title).max(japanese);
+    }
+    results.sort_by(|a, b| b.relevance.total_cmp(&a.relevance));
+}
@@ -0,0 +1,52 @@
+use anyhow::{Context, Result};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Load a list stored as JSON. A missing file is an empty list; a file that can't be
+/// parsed is moved to `<file>.bak` (like an unreadable jobs.json) so the next save
+/// doesn't overwrite the user's data. Earlier backups are kept: if `<file>.bak` exists,
+/// the file goes to `<file>.bak.1`, `<file>.bak.2`, …
+pub(crate) fn load_list<T: DeserializeOwned>(path: &Path) -> Result<Vec<T>> {
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+    let data = fs::read_to_string(path)?;
+    match serde_json::from_str(&data) {
+        Ok(items) => Ok(items),
+        Err(e) => {
+            let backup = backup_path(path);
+            tracing::warn!(
+                "Could not read {}: {}. Moved it to {}",
+                path.display(),
+                e,
+                backup.display()
+            );
+            fs::rename(path, &backup)?;
+            Ok(vec![])
+        }
+    }
+}
+
+/// Write a list as pretty-printed JSON
+pub(crate) fn save_list<T: Serialize>(path: &Path, items: &[T]) -> Result<()> {
+    let data = serde_json::to_string_pretty(items)?;
+    fs::write(path, data).with_context(|| format!("Could not write {}", path.display()))
+}
+
+/// First of `<file>.bak`, `<file>.bak.1`, … that doesn't exist yet
+fn backup_path(path: &Path) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(".bak");
+    let mut backup = PathBuf::from(&name);
+    let mut n = 1;
+    while backup.exists() {
+        let mut numbered = name.clone();
+        numbered.push(format!(".{}", n));
+        backup = PathBuf::from(numbered);
+        n += 1;
+    }
+    backup
+}
@@ -0,0 +1,393 @@
+use crate::manager::DownloadManager;
+use crate::metadata::episode_files;
+use crate::models::{DownloadTask, SegmentStatus, TaskStatus};
+use anyhow::Result;
+use axum::body::Body;
+use axum::extract::{Path as UrlPath, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::io::SeekFrom;
+use std::path::Path;
+use std::sync::Arc;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeekExt};
+use tokio::net::TcpListener;
+use tokio_util::io::ReaderStream;
+
+/// JSON index served at `/api/library`
+#[derive(Debug, Clone, Serialize)]
+pub struct LibraryIndex {
+    pub shows: Vec<LibraryShow>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LibraryShow {
+    pub name: String,
+    pub episodes: Vec<LibraryEpisode>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LibraryEpisode {
+    pub number: u32,
+    pub file: String,
+    /// Full size of the episode in bytes
+    pub size: u64,
+    /// Bytes that can be played right now (the contiguous prefix for in-progress downloads)
+    pub available_bytes: u64,
+    pub complete: bool,
+    /// Server-relative URL to play the episode from
+    pub url: String,
+}
+
+/// Routes of the streaming server:
+/// - `GET /api/library`: shows and episodes as JSON
+/// - `GET /files/{show}/{file}`: an episode video of a show in the download directory
+/// - `GET /stream/{job_id}/{task_id}`: a queued episode, completed or still downloading
+pub fn router(manager: Arc<DownloadManager>) -> Router {
+    Router::new()
+        .route("/api/library", get(library_handler))
+        .route("/files/:show/:file", get(file_handler))
+        .route("/stream/:job_id/:task_id", get(stream_handler))
+        .with_state(manager)
+}
+
+/// Listen on the address from `Settings.streaming` until the process exits.
+/// Spawn this on the runtime of the hosting application.
+pub async fn serve(manager: Arc<DownloadManager>) -> Result<()> {
+    let settings = manager.get_settings().streaming;
+    let listener = TcpListener::bind((settings.bind_address.as_str(), settings.port)).await?;
+    serve_listener(manager, listener).await
+}
+
+/// Serve on an already bound listener
+pub async fn serve_listener(manager: Arc<DownloadManager>, listener: TcpListener) -> Result<()> {
+    tracing::info!("Streaming server listening on {}", listener.local_addr()?);
+    axum::serve(listener, router(manager)).await?;
+    Ok(())
+}
+
+/// Completed files in the download directory plus episodes that are still downloading
+pub fn library_index(manager: &DownloadManager) -> LibraryIndex {
+    let download_dir = manager.get_settings().download_dir;
+    let mut shows: BTreeMap<String, Vec<LibraryEpisode>> = BTreeMap::new();
+
+    if let Ok(entries) = std::fs::read_dir(&download_dir) {
+        for folder in entries.flatten().map(|e| e.path()).filter(|p| p.is_dir()) {
+            let name = folder.file_name().unwrap_or_default().to_string_lossy().to_string();
+            let episodes: Vec<LibraryEpisode> = episode_files(&folder)
+                .into_iter()
+                .map(|(path, number)| {
+                    let file = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+                    let size = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+                    LibraryEpisode {
+                        number,
+                        url: format!("/files/{}/{}", encode_segment(&name), encode_segment(&file)),
+                        file,
+                        size,
+                        available_bytes: size,
+                        complete: true,
+                    }
+                })
+                .collect();
+            if !episodes.is_empty() {
+                shows.insert(name, episodes);
+            }
+        }
+    }
+
+    for job in manager.get_jobs() {
+        let folder = manager.job_folder(&job);
+        let name = folder.file_name().unwrap_or_default().to_string_lossy().to_string();
+        for task in &job.tasks {
+            if task.status == TaskStatus::Completed || task.segments.is_empty() {
+                continue;
+            }
+            let file = manager
+                .task_output_path(&job, task)
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string();
+            let episodes = shows.entry(name.clone()).or_default();
+            if episodes.iter().any(|e| e.file == file) {
+                continue;
+            }
+            episodes.push(LibraryEpisode {
+                number: task.episode_number.unwrap_or(0),
+                file,
+                size: task.total_bytes,
+                available_bytes: contiguous_prefix(task),
+                complete: false,
+                url: format!("/stream/{}/{}", encode_segment(&job.id), encode_segment(&task.id)),
+            });
+        }
+    }
+
+    LibraryIndex {
+        shows: shows
+            .into_iter()
+            .map(|(name, mut episodes)| {
+                episodes.sort_by_key(|e| e.number);
+                LibraryShow { name, episodes }
+            })
+            .collect(),
+    }
+}
+
+/// Bytes from the start of the file covered by completed segments, plus what has been
+/// written of the segment downloading right after them
+pub fn contiguous_prefix(task: &DownloadTask) -> u64 {
+    let mut segments: Vec<_> = task.segments.iter().collect();
+    segments.sort_by_key(|s| s.index);
+    let mut prefix = 0;
+    for segment in segments {
+        let len = segment.end - segment.start + 1;
+        match segment.status {
+            SegmentStatus::Completed => prefix += len,
+            SegmentStatus::Downloading => return prefix + segment.downloaded.min(len),
+            _ => break,
+        }
+    }
+    prefix
+}
+
+/// `contiguous_prefix`, limited to what the in-progress part file actually holds on disk
+async fn playable_prefix(task: &DownloadTask, parts_folder: &Path) -> u64 {
+    let prefix = contiguous_prefix(task);
+    let in_progress = task
+        .segments
+        .iter()
+        .find(|s| s.status == SegmentStatus::Downloading && s.start < prefix && prefix <= s.end + 1);
+    match in_progress {
+        Some(segment) => {
+            let part = parts_folder.join(format!("part{}.mp4", segment.index));
+            let written = tokio::fs::metadata(&part).await.map(|m| m.len()).unwrap_or(0);
+            prefix.min(segment.start + written)
+        }
+        None => prefix,
+    }
+}
+
+async fn library_handler(State(manager): State<Arc<DownloadManager>>) -> Json<LibraryIndex> {
+    Json(library_index(&manager))
+}
+
+async fn file_handler(
+    State(manager): State<Arc<DownloadManager>>,
+    UrlPath((show, file)): UrlPath<(String, String)>,
+    headers: HeaderMap,
+) -> Response {
+    if !is_plain_name(&show) || !is_plain_name(&file) {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+    // Only the episodes listed in the library, never other files next to them
+    let folder = manager.get_settings().download_dir.join(&show);
+    let Some((path, _)) = episode_files(&folder)
+        .into_iter()
+        .find(|(path, _)| path.file_name().is_some_and(|name| name == file.as_str()))
+    else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    serve_file(&path, &headers).await
+}
+
+async fn stream_handler(
+    State(manager): State<Arc<DownloadManager>>,
+    UrlPath((job_id, task_id)): UrlPath<(String, String)>,
+    headers: HeaderMap,
+) -> Response {
+    let Some(job) = manager.get_jobs().into_iter().find(|j| j.id == job_id) else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    let Some(task) = job.tasks.iter().find(|t| t.id == task_id) else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+
+    let final_path = manager.task_output_path(&job, task);
+    if task.status == TaskStatus::Completed || (task.segments.is_empty() && final_path.exists()) {
+        return serve_file(&final_path, &headers).await;
+    }
+
+    manager.request_streaming(&task.id);
+    let parts_folder = manager.task_parts_folder(&job, task);
+    serve_partial(task, &parts_folder, &final_path, &headers).await
+}
+
+/// Serve a complete file, honouring a single `Range: bytes=` request
+async fn serve_file(path: &Path, headers: &HeaderMap) -> Response {
+    let Ok(mut file) = tokio::fs::File::open(path).await else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    let len = match file.metadata().await {
+        Ok(meta) if meta.is_file() => meta.len(),
+        _ => return StatusCode::NOT_FOUND.into_response(),
+    };
+
+    let range = match parse_range(headers, len, len) {
+        Ok(range) => range,
+        Err(()) => return unsatisfiable(len),
+    };
+    let (start, end, partial) = match range {
+        Some((start, end)) => (start, end, true),
+        None => (0, len.saturating_sub(1), false),
+    };
+
+    if file.seek(SeekFrom::Start(start)).await.is_err() {
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    }
+    let body_len = if len == 0 { 0 } else { end - start + 1 };
+    respond(path, Box::new(file.take(body_len)), start, end, body_len, len, partial)
+}
+
+/// Serve the playable prefix of a task that is still downloading from its part files.
+/// Responses are always partial so players know the full size of the episode.
+async fn serve_partial(
+    task: &DownloadTask,
+    parts_folder: &Path,
+    final_path: &Path,
+    headers: &HeaderMap,
+) -> Response {
+    let total = task.total_bytes;
+    let available = playable_prefix(task, parts_folder).await;
+    if available == 0 {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            [(header::RETRY_AFTER, "5")],
+            "Nothing downloaded yet",
+        )
+            .into_response();
+    }
+
+    let (start, end) = match parse_range(headers, total, available) {
+        Ok(Some(range)) => range,
+        Ok(None) => (0, available - 1),
+        Err(()) => return unsatisfiable(total),
+    };
+
+    let mut segments: Vec<_> = task.segments.iter().collect();
+    segments.sort_by_key(|s| s.index);
+
+    let mut reader: Box<dyn AsyncRead + Send + Unpin> = Box::new(tokio::io::empty());
+    for segment in segments {
+        if segment.end < start || segment.start > end {
+            continue;
+        }
+        let from = start.max(segment.start);
+        let to = end.min(segment.end);
+        let part = parts_folder.join(format!("part{}.mp4", segment.index));
+        let Ok(mut file) = tokio::fs::File::open(&part).await else {
+            return StatusCode::NOT_FOUND.into_response();
+        };
+        if file.seek(SeekFrom::Start(from - segment.start)).await.is_err() {
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+        reader = Box::new(reader.chain(file.take(to - from + 1)));
+    }
+
+    respond(final_path, reader, start, end, end - start + 1, total, true)
+}
+
+fn respond(
+    path: &Path,
+    reader: Box<dyn AsyncRead + Send + Unpin>,
+    start: u64,
+    end: u64,
+    body_len: u64,
+    total: u64,
+    partial: bool,
+) -> Response {
+    let mut builder = Response::builder()
+        .status(if partial { StatusCode::PARTIAL_CONTENT } else { StatusCode::OK })
+        .header(header::CONTENT_TYPE, content_type(path))
+        .header(header::CONTENT_LENGTH, body_len)
+        .header(header::ACCEPT_RANGES, "bytes");
+    if partial {
+        builder = builder.header(header::CONTENT_RANGE, format!("bytes {}-{}/{}", start, end, total));
+    }
+    builder
+        .body(Body::from_stream(ReaderStream::new(reader)))
+        .unwrap_or_else(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())
+}
+
+fn unsatisfiable(total: u64) -> Response {
+    (
+        StatusCode::RANGE_NOT_SATISFIABLE,
+        [(header::CONTENT_RANGE, format!("bytes */{}", total))],
+    )
+        .into_response()
+}
+
+/// Parse a single `bytes=` range against a resource of `total` bytes of which the first
+/// `available` can be served. `Ok(None)` means no (or an unsupported) Range header.
+fn parse_range(headers: &HeaderMap, total: u64, available: u64) -> Result<Option<(u64, u64)>, ()> {
+    let Some(value) = headers.get(header::RANGE).and_then(|v| v.to_str().ok()) else {
+        return Ok(None);
+    };
+    let Some(spec) = value.trim().strip_prefix("bytes=") else {
+        return Ok(None);
+    };
+    if spec.contains(',') {
+        // Multiple ranges aren't supported; fall back to the whole resource
+        return Ok(None);
+    }
+    let Some((first, last)) = spec.split_once('-') else {
+        return Err(());
+    };
+
+    let (start, end) = match (first.trim(), last.trim()) {
+        ("", suffix) => {
+            let suffix: u64 = suffix.parse().map_err(|_| ())?;
+            (total.saturating_sub(suffix), total.saturating_sub(1))
+        }
+        (first, "") => (first.parse().map_err(|_| ())?, total.saturating_sub(1)),
+        (first, last) => (first.parse().map_err(|_| ())?, last.parse().map_err(|_| ())?),
+    };
+
+    if start > end || start >= available {
+        return Err(());
+    }
+    Ok(Some((start, end.min(available - 1))))
+}
+
+fn content_type(path: &Path) -> &'static str {
+    let ext = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    match ext.as_str() {
+        "mp4" | "m4v" => "video/mp4",
+        "mkv" => "video/x-matroska",
+        "webm" => "video/webm",
+        "avi" => "video/x-msvideo",
+        "m3u8" => "application/vnd.apple.mpegurl",
+        "xspf" => "application/xspf+xml",
+        "nfo" => "text/xml; charset=utf-8",
+        "jpg" | "jpeg" => "image/jpeg",
+        "png" => "image/png",
+        "webp" => "image/webp",
+        _ => "application/octet-stream",
+    }
+}
+
+/// A single path component: no separators, parent references or drive and UNC prefixes (`C:`, `\\?\`)
+fn is_plain_name(name: &str) -> bool {
+    let mut components = Path::new(name).components();
+    !name.contains(['/', '\\', ':'])
+        && matches!(components.next(), Some(std::path::Component::Normal(_)))
+        && components.next().is_none()
+}
+
+/// Percent-encode one URL path segment
+fn encode_segment(segment: &str) -> String {
+    let mut out = String::with_capacity(segment.len());
+    for byte in segment.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => out.push(byte as char),
+            _ => out.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    out
+}
@@ -0,0 +1,241 @@
+use crate::manager::{safe_file_name, DownloadManager};
+use crate::metadata::jellyfin_episode_filename;
+use crate::models::{DownloadJob, DownloadTask, ManagerEvent, Subscription, TaskStatus};
+use crate::provider::Provider;
+use crate::scraper::{season_path, AnimeScraper};
+use crate::store;
+use crate::unix_timestamp;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Lower bound for the poll interval so a bad setting can't hammer the site
+const MIN_POLL_INTERVAL_SECS: u64 = 60;
+
+/// An episode queued by a subscription check
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueuedEpisode {
+    pub anime_title: String,
+    pub anime_url: String,
+    pub episode_number: u32,
+}
+
+/// Keeps track of followed shows and queues their new episodes
+pub struct SubscriptionManager {
+    subscriptions: Mutex<Vec<Subscription>>,
+    path: PathBuf,
+    /// Strips the site's origin from URLs used as keys
+    provider: Provider,
+}
+
+impl SubscriptionManager {
+    /// Load subscriptions from `subscriptions.json` in the given config directory
+    pub fn new(config_dir: &Path) -> Result<Self> {
+        fs::create_dir_all(config_dir)?;
+        let path = config_dir.join("subscriptions.json");
+
+        let provider = Provider::load_or_builtin(config_dir);
+        let mut subscriptions: Vec<Subscription> = store::load_list(&path)?;
+        // Files written before keys always had a leading `/`
+        for sub in &mut subscriptions {
+            sub.url = season_path(&provider, &sub.url);
+        }
+
+        Ok(Self {
+            subscriptions: Mutex::new(subscriptions),
+            path,
+            provider,
+        })
+    }
+
+    pub fn list(&self) -> Vec<Subscription> {
+        self.subscriptions.lock().unwrap().clone()
+    }
+
+    pub fn is_subscribed(&self, url: &str) -> bool {
+        let key = season_path(&self.provider, url);
+        self.subscriptions.lock().unwrap().iter().any(|s| s.url == key)
+    }
+
+    /// Follow a show by its season URL.
+    /// Episodes up to `last_seen_episode` are not queued; defaults to the latest released one.
+    pub async fn subscribe(
+        &self,
+        scraper: &AnimeScraper,
+        url: &str,
+        last_seen_episode: Option<u32>,
+    ) -> Result<Subscription> {
+        let info = scraper.get_season(url).await?;
+        let latest = info.episodes.iter().map(|e| e.number).max().unwrap_or(0);
+        let now = unix_timestamp();
+
+        let subscription = Subscription {
+            url: info.url,
+            title: info.title,
+            last_seen_episode: last_seen_episode.unwrap_or(latest),
+            added_at: now,
+            last_checked: Some(now),
+        };
+        self.add(subscription.clone())?;
+        Ok(subscription)
+    }
+
+    /// Insert or replace a subscription without contacting the site
+    pub fn add(&self, subscription: Subscription) -> Result<()> {
+        let mut subscriptions = self.subscriptions.lock().unwrap();
+        if let Some(existing) = subscriptions.iter_mut().find(|s| s.url == subscription.url) {
+            *existing = subscription;
+        } else {
+            subscriptions.push(subscription);
+        }
+        drop(subscriptions);
+        self.save()
+    }
+
+    /// Stop following a show. Returns false if it wasn't followed.
+    pub fn unsubscribe(&self, url: &str) -> Result<bool> {
+        let key = season_path(&self.provider, url);
+        let mut subscriptions = self.subscriptions.lock().unwrap();
+        let before = subscriptions.len();
+        subscriptions.retain(|s| s.url != key);
+        let removed = subscriptions.len() != before;
+        drop(subscriptions);
+        if removed {
+            self.save()?;
+        }
+        Ok(removed)
+    }
+
+    /// Check every followed show once and queue episodes newer than the last seen one.
+    /// Failures for a single show are logged and don't stop the others.
+    pub async fn check_all(&self, manager: &DownloadManager) -> Vec<QueuedEpisode> {
+        let mut queued = Vec::new();
+        for subscription in self.list() {
+            match self.check_one(manager, &subscription).await {
+                Ok(mut episodes) => queued.append(&mut episodes),
+                Err(e) => {
+                    tracing::warn!("Subscription check failed for {}: {}", subscription.url, e)
+                }
+            }
+        }
+        queued
+    }
+
+    async fn check_one(
+        &self,
+        manager: &DownloadManager,
+        subscription: &Subscription,
+    ) -> Result<Vec<QueuedEpisode>> {
+        let info = manager.get_scraper().get_season(&subscription.url).await?;
+        let settings = manager.get_settings();
+        // Same folder as episodes queued by hand with `aura-cli get`
+        let folder = settings.download_dir.join(safe_file_name(&info.title));
+        let queued_episodes: Vec<u32> = manager
+            .get_jobs()
+            .into_iter()
+            .find(|j| j.id == subscription.url)
+            .map(|j| j.tasks.iter().filter_map(|t| t.episode_number).collect())
+            .unwrap_or_default();
+
+        let new_episodes: Vec<_> = info
+            .episodes
+            .iter()
+            .filter(|ep| ep.number > subscription.last_seen_episode && !ep.gate_id.is_empty())
+            .collect();
+        let tasks: Vec<DownloadTask> = new_episodes
+            .iter()
+            .filter(|ep| !queued_episodes.contains(&ep.number) && !manager.is_episode_downloaded(&folder, ep.number))
+            .map(|ep| DownloadTask {
+                id: format!("{}-ep{}", subscription.url, ep.number),
+                url: "pending".to_string(),
+                filename: folder
+                    .join(if settings.metadata.jellyfin_naming {
+                        jellyfin_episode_filename(&info.title, ep.number)
+                    } else {
+                        format!("Ep{:02}.mp4", ep.number)
+                    })
+                    .to_string_lossy()
+                    .to_string(),
+                status: TaskStatus::Pending,
+                progress_bytes: 0,
+                total_bytes: 0,
+                episode_url: Some(ep.url.clone()),
+                gate_id: Some(ep.gate_id.clone()),
+                episode_number: Some(ep.number),
+                segments: vec![],
+            })
+            .collect();
+
+        let queued: Vec<QueuedEpisode> = tasks
+            .iter()
+            .filter_map(|t| t.episode_number)
+            .map(|episode_number| QueuedEpisode {
+                anime_title: info.title.clone(),
+                anime_url: subscription.url.clone(),
+                episode_number,
+            })
+            .collect();
+
+        if !tasks.is_empty() {
+            tracing::info!("Queueing {} new episode(s) of {}", tasks.len(), info.title);
+            let task_ids = tasks.iter().map(|t| t.id.clone()).collect();
+            manager.add_job(DownloadJob {
+                id: subscription.url.clone(),
+                name: info.title.clone(),
+                tasks,
+                anime_url: Some(subscription.url.clone()),
+                cover_url: None,
+            });
+            // Tasks queued by earlier checks may still be waiting for a slot; only the new
+            // ones get workers
+            manager.start_tasks(subscription.url.clone(), task_ids).await?;
+
+            for ep in &queued {
+                manager.emit(ManagerEvent::EpisodeQueued {
+                    anime_title: ep.anime_title.clone(),
+                    anime_url: ep.anime_url.clone(),
+                    episode_number: ep.episode_number,
+                });
+            }
+        }
+
+        {
+            let mut subscriptions = self.subscriptions.lock().unwrap();
+            if let Some(existing) = subscriptions.iter_mut().find(|s| s.url == subscription.url) {
+                existing.title = info.title;
+                existing.last_checked = Some(unix_timestamp());
+                // Episodes already queued or on disk count as seen too
+                if let Some(max) = new_episodes.iter().map(|ep| ep.number).max() {
+                    existing.last_seen_episode = existing.last_seen_episode.max(max);
+                }
+            }
+        }
+        self.save()?;
+
+        Ok(queued)
+    }
+
+    /// Poll followed shows forever, sleeping `subscription_check_interval_secs` between rounds.
+    /// Spawn this on the runtime of the hosting application.
+    pub async fn run_poller(self: Arc<Self>, manager: Arc<DownloadManager>) {
+        loop {
+            let queued = self.check_all(&manager).await;
+            if !queued.is_empty() {
+                tracing::info!("Subscription poll queued {} episode(s)", queued.len());
+            }
+
+            let interval = manager
+                .get_settings()
+                .subscription_check_interval_secs
+                .max(MIN_POLL_INTERVAL_SECS);
+            tokio::time::sleep(Duration::from_secs(interval)).await;
+        }
+    }
+
+    fn save(&self) -> Result<()> {
+        store::save_list(&self.path, &self.subscriptions.lock().unwrap())
+    }
+}
@@ -0,0 +1,232 @@
+use crate::models::{Episode, ShowProgress, WatchEntry};
+use crate::provider::Provider;
+use crate::scraper::season_path;
+use crate::store;
+use crate::unix_timestamp;
+use anyhow::Result;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Playback past this share of the duration counts as watched
+const WATCHED_THRESHOLD: f64 = 0.9;
+
+/// Tracks which episodes have been watched and where playback stopped
+pub struct WatchStateStore {
+    entries: Mutex<Vec<WatchEntry>>,
+    path: PathBuf,
+    /// Strips the site's origin from URLs used as keys
+    provider: Provider,
+}
+
+impl WatchStateStore {
+    /// Load watch state from `watch_state.json` in the given config directory
+    pub fn new(config_dir: &Path) -> Result<Self> {
+        fs::create_dir_all(config_dir)?;
+        let path = config_dir.join("watch_state.json");
+
+        let provider = Provider::load_or_builtin(config_dir);
+        let mut entries: Vec<WatchEntry> = store::load_list(&path)?;
+        // Files written before keys always had a leading `/`
+        for entry in &mut entries {
+            entry.anime_url = season_path(&provider, &entry.anime_url);
+        }
+
+        Ok(Self {
+            entries: Mutex::new(entries),
+            path,
+            provider,
+        })
+    }
+
+    pub fn get(&self, anime_url: &str, episode_number: u32) -> Option<WatchEntry> {
+        let key = season_path(&self.provider, anime_url);
+        self.entries
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|e| e.anime_url == key && e.episode_number == episode_number)
+            .cloned()
+    }
+
+    /// Watch state of every tracked episode of a show, ordered by episode number
+    pub fn episodes(&self, anime_url: &str) -> Vec<WatchEntry> {
+        let key = season_path(&self.provider, anime_url);
+        let mut entries: Vec<WatchEntry> = self
+            .entries
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|e| e.anime_url == key)
+            .cloned()
+            .collect();
+        entries.sort_by_key(|e| e.episode_number);
+        entries
+    }
+
+    pub fn is_watched(&self, anime_url: &str, episode_number: u32) -> bool {
+        self.get(anime_url, episode_number)
+            .map(|e| e.watched)
+            .unwrap_or(false)
+    }
+
+    /// Mark an episode as watched (or unwatched, which also resets its position)
+    pub fn mark_watched(
+        &self,
+        anime_url: &str,
+        anime_title: &str,
+        episode_number: u32,
+        watched: bool,
+    ) -> Result<WatchEntry> {
+        self.update(anime_url, anime_title, episode_number, |entry, now| {
+            entry.watched = watched;
+            entry.watched_at = if watched { Some(now) } else { None };
+            if !watched {
+                entry.position_secs = 0;
+            }
+        })
+    }
+
+    /// Save the playback position. Reaching the end of a known duration marks the episode watched.
+    pub fn set_position(
+        &self,
+        anime_url: &str,
+        anime_title: &str,
+        episode_number: u32,
+        position_secs: u64,
+        duration_secs: Option<u64>,
+    ) -> Result<WatchEntry> {
+        self.update(anime_url, anime_title, episode_number, |entry, now| {
+            entry.position_secs = position_secs;
+            if duration_secs.is_some() {
+                entry.duration_secs = duration_secs;
+            }
+            if let Some(duration) = entry.duration_secs.filter(|d| *d > 0) {
+                if !entry.watched && position_secs as f64 >= duration as f64 * WATCHED_THRESHOLD {
+                    entry.watched = true;
+                    entry.watched_at = Some(now);
+                }
+            }
+        })
+    }
+
+    /// Next episode to watch: an unfinished started episode, otherwise the first one
+    /// after the highest watched episode. `None` once everything available is watched.
+    pub fn next_episode(&self, anime_url: &str, available: &[Episode]) -> Option<Episode> {
+        next_episode(&self.episodes(anime_url), available)
+    }
+
+    /// Shows with any watch activity, most recently active first
+    pub fn in_progress_shows(&self) -> Vec<ShowProgress> {
+        let entries = self.entries.lock().unwrap();
+        let mut shows: HashMap<&str, ShowProgress> = HashMap::new();
+
+        for entry in entries.iter() {
+            let show = shows
+                .entry(entry.anime_url.as_str())
+                .or_insert_with(|| ShowProgress {
+                    anime_url: entry.anime_url.clone(),
+                    anime_title: entry.anime_title.clone(),
+                    watched_episodes: 0,
+                    last_watched_episode: None,
+                    resume_episode: None,
+                    last_activity: 0,
+                });
+
+            if entry.watched {
+                show.watched_episodes += 1;
+                show.last_watched_episode = show.last_watched_episode.max(Some(entry.episode_number));
+            } else if entry.position_secs > 0 {
+                show.resume_episode = Some(entry.episode_number);
+            }
+            if entry.updated_at >= show.last_activity {
+                show.last_activity = entry.updated_at;
+                show.anime_title = entry.anime_title.clone();
+            }
+        }
+
+        let mut shows: Vec<ShowProgress> = shows
+            .into_values()
+            .filter(|s| s.watched_episodes > 0 || s.resume_episode.is_some())
+            .collect();
+        shows.sort_by_key(|s| std::cmp::Reverse(s.last_activity));
+        shows
+    }
+
+    fn update(
+        &self,
+        anime_url: &str,
+        anime_title: &str,
+        episode_number: u32,
+        apply: impl FnOnce(&mut WatchEntry, u64),
+    ) -> Result<WatchEntry> {
+        let key = season_path(&self.provider, anime_url);
+        let now = unix_timestamp();
+
+        let entry = {
+            let mut entries = self.entries.lock().unwrap();
+            let idx = match entries
+                .iter()
+                .position(|e| e.anime_url == key && e.episode_number == episode_number)
+            {
+                Some(idx) => idx,
+                None => {
+                    entries.push(WatchEntry {
+                        anime_url: key,
+                        anime_title: anime_title.to_string(),
+                        episode_number,
+                        watched: false,
+                        position_secs: 0,
+                        duration_secs: None,
+                        updated_at: now,
+                        watched_at: None,
+                    });
+                    entries.len() - 1
+                }
+            };
+
+            let entry = &mut entries[idx];
+            if !anime_title.is_empty() {
+                entry.anime_title = anime_title.to_string();
+            }
+            entry.updated_at = now;
+            apply(entry, now);
+            entry.clone()
+        };
+        self.save()?;
+        Ok(entry)
+    }
+
+    fn save(&self) -> Result<()> {
+        store::save_list(&self.path, &self.entries.lock().unwrap())
+    }
+}
+
+/// `WatchStateStore::next_episode` for the watch state of one show, as returned by `episodes`
+pub fn next_episode(entries: &[WatchEntry], available: &[Episode]) -> Option<Episode> {
+    let mut sorted: Vec<&Episode> = available.iter().collect();
+    sorted.sort_by_key(|e| e.number);
+
+    if let Some(resume) = entries
+        .iter()
+        .filter(|e| !e.watched && e.position_secs > 0)
+        .max_by_key(|e| e.updated_at)
+    {
+        if let Some(ep) = sorted.iter().find(|ep| ep.number == resume.episode_number) {
+            return Some((*ep).clone());
+        }
+    }
+
+    let last_watched = entries
+        .iter()
+        .filter(|e| e.watched)
+        .map(|e| e.episode_number)
+        .max()
+        .unwrap_or(0);
+
+    sorted
+        .into_iter()
+        .find(|ep| ep.number > last_watched)
+        .cloned()
+}
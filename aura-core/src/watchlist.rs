@@ -0,0 +1,332 @@
+use crate::models::{ListStatus, WatchlistEntry};
+use crate::provider::Provider;
+use crate::scraper::season_path;
+use crate::store;
+use crate::unix_timestamp;
+use anyhow::{anyhow, bail, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Tag used to carry the AnimeHeaven URL through a MyAnimeList export
+const MAL_URL_TAG_PREFIX: &str = "aura:";
+
+impl ListStatus {
+    /// Status label used by the MyAnimeList XML export
+    pub fn mal_label(&self) -> &'static str {
+        match self {
+            ListStatus::PlanToWatch => "Plan to Watch",
+            ListStatus::Watching => "Watching",
+            ListStatus::Completed => "Completed",
+            ListStatus::OnHold => "On-Hold",
+            ListStatus::Dropped => "Dropped",
+        }
+    }
+
+    pub fn from_mal_label(label: &str) -> Option<Self> {
+        match label.trim() {
+            "Plan to Watch" => Some(ListStatus::PlanToWatch),
+            "Watching" => Some(ListStatus::Watching),
+            "Completed" => Some(ListStatus::Completed),
+            "On-Hold" => Some(ListStatus::OnHold),
+            "Dropped" => Some(ListStatus::Dropped),
+            _ => None,
+        }
+    }
+}
+
+impl std::str::FromStr for ListStatus {
+    type Err = anyhow::Error;
+
+    /// Accepts `plan`, `plan-to-watch`, `watching`, `completed`, `on-hold`, `dropped` and MAL labels
+    fn from_str(s: &str) -> Result<Self> {
+        if let Some(status) = Self::from_mal_label(s) {
+            return Ok(status);
+        }
+        match s.trim().to_lowercase().replace(['_', ' '], "-").as_str() {
+            "plan" | "plan-to-watch" | "plantowatch" | "ptw" => Ok(ListStatus::PlanToWatch),
+            "watching" => Ok(ListStatus::Watching),
+            "completed" | "done" => Ok(ListStatus::Completed),
+            "on-hold" | "onhold" | "hold" => Ok(ListStatus::OnHold),
+            "dropped" => Ok(ListStatus::Dropped),
+            other => Err(anyhow!("Unknown list status: {}", other)),
+        }
+    }
+}
+
+/// Personal anime list stored in `watchlist.json`
+pub struct WatchlistStore {
+    entries: Mutex<Vec<WatchlistEntry>>,
+    path: PathBuf,
+    /// Strips the site's origin from URLs used as keys
+    provider: Provider,
+}
+
+impl WatchlistStore {
+    /// Load the list from `watchlist.json` in the given config directory
+    pub fn new(config_dir: &Path) -> Result<Self> {
+        fs::create_dir_all(config_dir)?;
+        let path = config_dir.join("watchlist.json");
+
+        let provider = Provider::load_or_builtin(config_dir);
+        let mut entries: Vec<WatchlistEntry> = store::load_list(&path)?;
+        // Files written before keys always had a leading `/`
+        for entry in &mut entries {
+            entry.url = season_path(&provider, &entry.url);
+        }
+
+        Ok(Self {
+            entries: Mutex::new(entries),
+            path,
+            provider,
+        })
+    }
+
+    /// All entries, optionally filtered by status, ordered by title
+    pub fn list(&self, status: Option<ListStatus>) -> Vec<WatchlistEntry> {
+        let mut entries: Vec<WatchlistEntry> = self
+            .entries
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|e| status.is_none_or(|s| e.status == s))
+            .cloned()
+            .collect();
+        entries.sort_by_key(|e| e.title.to_lowercase());
+        entries
+    }
+
+    pub fn get(&self, url: &str) -> Option<WatchlistEntry> {
+        let key = season_path(&self.provider, url);
+        self.entries
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|e| e.url == key)
+            .cloned()
+    }
+
+    /// Add a show or change its status, keeping score and notes
+    pub fn set_status(&self, url: &str, title: &str, status: ListStatus) -> Result<WatchlistEntry> {
+        self.update(url, title, |entry| entry.status = status)
+    }
+
+    /// Set the personal score (1-10), `None` clears it
+    pub fn set_score(&self, url: &str, score: Option<u8>) -> Result<WatchlistEntry> {
+        if let Some(score) = score {
+            if !(1..=10).contains(&score) {
+                bail!("Score must be between 1 and 10, got {}", score);
+            }
+        }
+        self.existing(url)?;
+        self.update(url, "", |entry| entry.score = score)
+    }
+
+    pub fn set_notes(&self, url: &str, notes: &str) -> Result<WatchlistEntry> {
+        self.existing(url)?;
+        self.update(url, "", |entry| entry.notes = notes.to_string())
+    }
+
+    pub fn set_episodes_watched(&self, url: &str, episodes: u32) -> Result<WatchlistEntry> {
+        self.existing(url)?;
+        self.update(url, "", |entry| entry.episodes_watched = episodes)
+    }
+
+    /// Remove a show from the list. Returns false if it wasn't listed.
+    pub fn remove(&self, url: &str) -> Result<bool> {
+        let key = season_path(&self.provider, url);
+        let mut entries = self.entries.lock().unwrap();
+        let before = entries.len();
+        entries.retain(|e| e.url != key);
+        let removed = entries.len() != before;
+        drop(entries);
+        if removed {
+            self.save()?;
+        }
+        Ok(removed)
+    }
+
+    /// Export the list as pretty JSON
+    pub fn export_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(&self.list(None))?)
+    }
+
+    /// Merge entries from a JSON export. Returns the number of imported entries.
+    pub fn import_json(&self, data: &str) -> Result<usize> {
+        let imported: Vec<WatchlistEntry> = serde_json::from_str(data)?;
+        self.merge(imported)
+    }
+
+    /// Export the list in the MyAnimeList XML export format.
+    /// The AnimeHeaven URL is kept in `my_tags` so a re-import maps back to the same show.
+    pub fn export_mal_xml(&self) -> String {
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\" ?>\n<myanimelist>\n");
+        let entries = self.list(None);
+
+        xml.push_str("  <myinfo>\n");
+        xml.push_str("    <user_export_type>1</user_export_type>\n");
+        xml.push_str(&format!("    <user_total_anime>{}</user_total_anime>\n", entries.len()));
+        xml.push_str("  </myinfo>\n");
+
+        for entry in &entries {
+            xml.push_str("  <anime>\n");
+            xml.push_str("    <series_animedb_id>0</series_animedb_id>\n");
+            xml.push_str(&format!("    <series_title>{}</series_title>\n", cdata(&entry.title)));
+            xml.push_str(&format!(
+                "    <my_watched_episodes>{}</my_watched_episodes>\n",
+                entry.episodes_watched
+            ));
+            xml.push_str("    <my_start_date>0000-00-00</my_start_date>\n");
+            xml.push_str("    <my_finish_date>0000-00-00</my_finish_date>\n");
+            xml.push_str(&format!("    <my_score>{}</my_score>\n", entry.score.unwrap_or(0)));
+            xml.push_str(&format!("    <my_status>{}</my_status>\n", entry.status.mal_label()));
+            xml.push_str(&format!("    <my_comments>{}</my_comments>\n", cdata(&entry.notes)));
+            xml.push_str(&format!(
+                "    <my_tags>{}</my_tags>\n",
+                cdata(&format!("{}{}", MAL_URL_TAG_PREFIX, entry.url))
+            ));
+            xml.push_str("    <update_on_import>1</update_on_import>\n");
+            xml.push_str("  </anime>\n");
+        }
+
+        xml.push_str("</myanimelist>\n");
+        xml
+    }
+
+    /// Merge entries from a MyAnimeList XML export. Returns the number of imported entries.
+    /// Shows exported by other tools have no AnimeHeaven URL; they are matched by title
+    /// against existing entries, or stored under a `mal:` key until linked.
+    pub fn import_mal_xml(&self, xml: &str) -> Result<usize> {
+        let doc = roxmltree::Document::parse(xml)?;
+        let now = unix_timestamp();
+        let mut imported = Vec::new();
+
+        for anime in doc.descendants().filter(|n| n.has_tag_name("anime")) {
+            let field = |name: &str| -> String {
+                anime
+                    .children()
+                    .find(|c| c.has_tag_name(name))
+                    .map(|c| c.text().unwrap_or("").trim().to_string())
+                    .unwrap_or_default()
+            };
+
+            let title = field("series_title");
+            if title.is_empty() {
+                continue;
+            }
+
+            let url = field("my_tags")
+                .split(',')
+                .map(|t| t.trim())
+                .find_map(|t| t.strip_prefix(MAL_URL_TAG_PREFIX))
+                .map(|u| u.to_string())
+                .or_else(|| self.find_by_title(&title).map(|e| e.url))
+                .unwrap_or_else(|| match field("series_animedb_id").as_str() {
+                    "" | "0" => format!("mal:{}", title),
+                    id => format!("mal:{}", id),
+                });
+
+            let status = ListStatus::from_mal_label(&field("my_status"))
+                .unwrap_or(ListStatus::PlanToWatch);
+            let score = field("my_score")
+                .parse::<u8>()
+                .ok()
+                .filter(|s| (1..=10).contains(s));
+
+            imported.push(WatchlistEntry {
+                url,
+                title,
+                status,
+                score,
+                notes: field("my_comments"),
+                episodes_watched: field("my_watched_episodes").parse().unwrap_or(0),
+                added_at: now,
+                updated_at: now,
+            });
+        }
+
+        self.merge(imported)
+    }
+
+    fn find_by_title(&self, title: &str) -> Option<WatchlistEntry> {
+        let title = title.to_lowercase();
+        self.entries
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|e| e.title.to_lowercase() == title)
+            .cloned()
+    }
+
+    /// Insert or overwrite entries by URL, keeping the original `added_at`
+    fn merge(&self, imported: Vec<WatchlistEntry>) -> Result<usize> {
+        let count = imported.len();
+        let mut entries = self.entries.lock().unwrap();
+        for mut entry in imported {
+            entry.url = season_path(&self.provider, &entry.url);
+            if let Some(existing) = entries.iter_mut().find(|e| e.url == entry.url) {
+                entry.added_at = existing.added_at;
+                *existing = entry;
+            } else {
+                entries.push(entry);
+            }
+        }
+        drop(entries);
+        self.save()?;
+        Ok(count)
+    }
+
+    fn existing(&self, url: &str) -> Result<WatchlistEntry> {
+        self.get(url)
+            .ok_or_else(|| anyhow!("{} is not on your list", url))
+    }
+
+    fn update(
+        &self,
+        url: &str,
+        title: &str,
+        apply: impl FnOnce(&mut WatchlistEntry),
+    ) -> Result<WatchlistEntry> {
+        let key = season_path(&self.provider, url);
+        let now = unix_timestamp();
+
+        let entry = {
+            let mut entries = self.entries.lock().unwrap();
+            let idx = match entries.iter().position(|e| e.url == key) {
+                Some(idx) => idx,
+                None => {
+                    entries.push(WatchlistEntry {
+                        url: key,
+                        title: title.to_string(),
+                        status: ListStatus::PlanToWatch,
+                        score: None,
+                        notes: String::new(),
+                        episodes_watched: 0,
+                        added_at: now,
+                        updated_at: now,
+                    });
+                    entries.len() - 1
+                }
+            };
+
+            let entry = &mut entries[idx];
+            if !title.is_empty() {
+                entry.title = title.to_string();
+            }
+            entry.updated_at = now;
+            apply(entry);
+            entry.clone()
+        };
+        self.save()?;
+        Ok(entry)
+    }
+
+    fn save(&self) -> Result<()> {
+        store::save_list(&self.path, &self.entries.lock().unwrap())
+    }
+}
+
+/// Wrap text in a CDATA section, splitting any `]]>` it contains
+fn cdata(text: &str) -> String {
+    format!("<![CDATA[{}]]>", text.replace("]]>", "]]]]><![CDATA[>"))
+}
@@ -0,0 +1,364 @@
+use aura_core::daemon::DaemonClient;
+use aura_core::import::{self, ImportEntry, ImportReport};
+use aura_core::playlist::{self, PlaylistFormat};
+use aura_core::subscriptions::QueuedEpisode;
+use aura_core::{
+    AnimeInfo, DownloadJob, DownloadManager, ListEntry, ListStatus, ManagerEvent, MetadataWriter, Notification,
+    NotificationCenter, Page, SearchQuery, SearchResult, Settings, ShowProgress, Subscription, SubscriptionManager,
+    WatchEntry, WatchStateStore, WatchlistEntry, WatchlistStore,
+};
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::{broadcast, mpsc};
+
+use crate::PageArgs;
+
+/// Where commands run: in this process, or against a running `aura-cli daemon`.
+/// Only the local backend loads jobs.json and the other stores of the config directory.
+pub enum Backend {
+    Local {
+        manager: Arc<DownloadManager>,
+        notifications: Arc<NotificationCenter>,
+        metadata: Arc<MetadataWriter>,
+    },
+    Remote(DaemonClient),
+}
+
+impl Backend {
+    pub fn is_remote(&self) -> bool {
+        matches!(self, Backend::Remote(_))
+    }
+
+    /// The in-process manager, for commands that never go through a daemon
+    pub fn manager(&self) -> anyhow::Result<&Arc<DownloadManager>> {
+        match self {
+            Backend::Local { manager, .. } => Ok(manager),
+            Backend::Remote(_) => anyhow::bail!("This command can't run through the daemon; use --local"),
+        }
+    }
+
+    pub async fn search(&self, query: &SearchQuery, refresh: bool) -> anyhow::Result<Vec<SearchResult>> {
+        match self {
+            Backend::Local { manager, .. } => Ok(manager.get_cached_scraper().search_with(query, refresh).await?),
+            Backend::Remote(client) => client.search_with(query, refresh).await,
+        }
+    }
+
+    pub async fn search_page(&self, query: &SearchQuery, paging: PageArgs, refresh: bool) -> anyhow::Result<Page<SearchResult>> {
+        let PageArgs { page, limit } = paging;
+        match self {
+            Backend::Local { manager, .. } => Ok(manager.get_cached_scraper().search_page(query, page, limit, refresh).await?),
+            Backend::Remote(client) => client.search_page(query, page, limit, refresh).await,
+        }
+    }
+
+    pub async fn get_season(&self, url: &str, refresh: bool) -> anyhow::Result<AnimeInfo> {
+        match self {
+            Backend::Local { manager, .. } => Ok(manager.get_cached_scraper().get_season(url, refresh).await?),
+            Backend::Remote(client) => client.get_season(url, refresh).await,
+        }
+    }
+
+    pub async fn get_new_page(&self, paging: PageArgs, refresh: bool) -> anyhow::Result<Page<ListEntry>> {
+        let PageArgs { page, limit } = paging;
+        match self {
+            Backend::Local { manager, .. } => Ok(manager.get_cached_scraper().get_new_page(page, limit, refresh).await?),
+            Backend::Remote(client) => client.get_new_page(page, limit, refresh).await,
+        }
+    }
+
+    pub async fn get_popular_page(&self, paging: PageArgs, refresh: bool) -> anyhow::Result<Page<ListEntry>> {
+        let PageArgs { page, limit } = paging;
+        match self {
+            Backend::Local { manager, .. } => Ok(manager.get_cached_scraper().get_popular_page(page, limit, refresh).await?),
+            Backend::Remote(client) => client.get_popular_page(page, limit, refresh).await,
+        }
+    }
+
+    pub async fn get_jobs(&self) -> anyhow::Result<Vec<DownloadJob>> {
+        match self {
+            Backend::Local { manager, .. } => Ok(manager.get_jobs()),
+            Backend::Remote(client) => client.get_jobs().await,
+        }
+    }
+
+    pub async fn get_settings(&self) -> anyhow::Result<Settings> {
+        match self {
+            Backend::Local { manager, .. } => Ok(manager.get_settings()),
+            Backend::Remote(client) => client.get_settings().await,
+        }
+    }
+
+    /// Add a job (merging with an existing one of the same id) and start it
+    pub async fn queue(&self, job: DownloadJob) -> anyhow::Result<()> {
+        match self {
+            Backend::Local { manager, .. } => {
+                let job_id = job.id.clone();
+                manager.add_job(job);
+                manager.start_download(job_id).await
+            }
+            Backend::Remote(client) => client.queue(&job).await,
+        }
+    }
+
+    /// Add a job for later: saved to the local queue without starting workers, which would
+    /// die with this process; the daemon resumes pending jobs when it starts. A running
+    /// daemon starts the job right away.
+    pub async fn enqueue(&self, job: DownloadJob) -> anyhow::Result<()> {
+        match self {
+            Backend::Local { manager, .. } => {
+                manager.add_job(job);
+                Ok(())
+            }
+            Backend::Remote(client) => client.queue(&job).await,
+        }
+    }
+
+    /// Resolve a download list into jobs and add them like `enqueue`
+    pub async fn import(&self, entries: Vec<ImportEntry>, concurrency: usize) -> anyhow::Result<ImportReport> {
+        match self {
+            Backend::Local { manager, .. } => {
+                let report = import::resolve_entries(manager.clone(), entries, concurrency).await;
+                for job in &report.jobs {
+                    for task in &job.tasks {
+                        if let Some(parent) = std::path::Path::new(&task.filename).parent() {
+                            std::fs::create_dir_all(parent)?;
+                        }
+                    }
+                    self.enqueue(job.clone()).await?;
+                }
+                Ok(report)
+            }
+            Backend::Remote(client) => client.import(entries, concurrency).await,
+        }
+    }
+
+    /// Pause a task, or the whole job when `task_id` is None
+    pub async fn pause(&self, job_id: &str, task_id: Option<&str>) -> anyhow::Result<()> {
+        match self {
+            Backend::Local { manager, .. } => {
+                manager.pause(job_id.to_string(), task_id.map(str::to_string));
+                Ok(())
+            }
+            Backend::Remote(client) => client.pause(job_id, task_id).await,
+        }
+    }
+
+    pub async fn resume(&self, job_id: &str, task_id: Option<&str>) -> anyhow::Result<()> {
+        match self {
+            Backend::Local { manager, .. } => manager.resume(job_id.to_string(), task_id.map(str::to_string)).await,
+            Backend::Remote(client) => client.resume(job_id, task_id).await,
+        }
+    }
+
+    /// Remove a task (or the whole job) from the queue along with its partial download
+    pub async fn cancel(&self, job_id: &str, task_id: Option<&str>) -> anyhow::Result<()> {
+        match self {
+            Backend::Local { manager, .. } => {
+                manager.cancel(job_id, task_id);
+                Ok(())
+            }
+            Backend::Remote(client) => client.cancel(job_id, task_id).await,
+        }
+    }
+
+    /// Move a job towards the front (negative `offset`) or back of the queue
+    pub async fn move_job(&self, job_id: &str, offset: isize) -> anyhow::Result<()> {
+        match self {
+            Backend::Local { manager, .. } => {
+                manager.move_job(job_id, offset);
+                Ok(())
+            }
+            Backend::Remote(client) => client.move_job(job_id, offset).await,
+        }
+    }
+
+    pub async fn subscriptions(&self) -> anyhow::Result<Vec<Subscription>> {
+        match self {
+            Backend::Local { manager, .. } => Ok(SubscriptionManager::new(&manager.config_dir())?.list()),
+            Backend::Remote(client) => client.get_subscriptions().await,
+        }
+    }
+
+    /// Follow a show; episodes up to `from` (default: the latest released) count as seen
+    pub async fn subscribe(&self, url: &str, from: Option<u32>) -> anyhow::Result<Subscription> {
+        match self {
+            Backend::Local { manager, .. } => {
+                let subscriptions = SubscriptionManager::new(&manager.config_dir())?;
+                subscriptions.subscribe(&manager.get_scraper(), url, from).await
+            }
+            Backend::Remote(client) => client.subscribe(url, from).await,
+        }
+    }
+
+    /// Stop following a show. Returns false if it wasn't followed.
+    pub async fn unsubscribe(&self, url: &str) -> anyhow::Result<bool> {
+        match self {
+            Backend::Local { manager, .. } => SubscriptionManager::new(&manager.config_dir())?.unsubscribe(url),
+            Backend::Remote(client) => client.unsubscribe(url).await,
+        }
+    }
+
+    /// Check followed shows once and queue their new episodes
+    pub async fn check_subscriptions(&self) -> anyhow::Result<Vec<QueuedEpisode>> {
+        match self {
+            Backend::Local { manager, .. } => {
+                let subscriptions = SubscriptionManager::new(&manager.config_dir())?;
+                Ok(subscriptions.check_all(manager).await)
+            }
+            Backend::Remote(client) => client.check_subscriptions().await,
+        }
+    }
+
+    /// Write playlists for a show folder, or the job with this id, season URL or title
+    pub async fn export_playlist(&self, target: &str, format: Option<PlaylistFormat>) -> anyhow::Result<Vec<PathBuf>> {
+        match self {
+            Backend::Local { manager, .. } => {
+                let folder = playlist::resolve_target(manager, target)?;
+                let formats = format.map_or(PlaylistFormat::ALL.to_vec(), |f| vec![f]);
+                playlist::export_folder(manager, &folder, &formats).await
+            }
+            Backend::Remote(client) => client.export_playlist(target, format).await,
+        }
+    }
+
+    /// Notification feed, newest first
+    pub async fn notifications(&self) -> anyhow::Result<Vec<Notification>> {
+        match self {
+            Backend::Local { notifications, .. } => Ok(notifications.list()),
+            Backend::Remote(client) => client.get_notifications().await,
+        }
+    }
+
+    pub async fn mark_notifications_read(&self) -> anyhow::Result<()> {
+        match self {
+            Backend::Local { notifications, .. } => notifications.mark_all_read(),
+            Backend::Remote(client) => client.mark_notifications_read().await,
+        }
+    }
+
+    pub async fn clear_notifications(&self) -> anyhow::Result<()> {
+        match self {
+            Backend::Local { notifications, .. } => notifications.clear(),
+            Backend::Remote(client) => client.clear_notifications().await,
+        }
+    }
+
+    /// Watch state of every tracked episode of a show
+    pub async fn watch_state(&self, url: &str) -> anyhow::Result<Vec<WatchEntry>> {
+        match self {
+            Backend::Local { manager, .. } => Ok(WatchStateStore::new(&manager.config_dir())?.episodes(url)),
+            Backend::Remote(client) => client.watch_state(url).await,
+        }
+    }
+
+    /// Mark an episode as watched, or unwatched
+    pub async fn mark_watched(&self, url: &str, title: &str, episode_number: u32, watched: bool) -> anyhow::Result<WatchEntry> {
+        match self {
+            Backend::Local { manager, .. } => {
+                WatchStateStore::new(&manager.config_dir())?.mark_watched(url, title, episode_number, watched)
+            }
+            Backend::Remote(client) => client.mark_watched(url, title, episode_number, watched).await,
+        }
+    }
+
+    /// Shows with watch activity, most recent first
+    pub async fn in_progress_shows(&self) -> anyhow::Result<Vec<ShowProgress>> {
+        match self {
+            Backend::Local { manager, .. } => Ok(WatchStateStore::new(&manager.config_dir())?.in_progress_shows()),
+            Backend::Remote(client) => client.in_progress_shows().await,
+        }
+    }
+
+    pub async fn watchlist(&self, status: Option<ListStatus>) -> anyhow::Result<Vec<WatchlistEntry>> {
+        match self {
+            Backend::Local { manager, .. } => Ok(WatchlistStore::new(&manager.config_dir())?.list(status)),
+            Backend::Remote(client) => client.get_watchlist(status).await,
+        }
+    }
+
+    /// Add a show to the list or change its status
+    pub async fn set_watchlist_status(&self, url: &str, title: &str, status: ListStatus) -> anyhow::Result<WatchlistEntry> {
+        match self {
+            Backend::Local { manager, .. } => WatchlistStore::new(&manager.config_dir())?.set_status(url, title, status),
+            Backend::Remote(client) => client.set_watchlist_status(url, title, status).await,
+        }
+    }
+
+    pub async fn set_watchlist_score(&self, url: &str, score: Option<u8>) -> anyhow::Result<WatchlistEntry> {
+        match self {
+            Backend::Local { manager, .. } => WatchlistStore::new(&manager.config_dir())?.set_score(url, score),
+            Backend::Remote(client) => client.set_watchlist_score(url, score).await,
+        }
+    }
+
+    pub async fn set_watchlist_notes(&self, url: &str, notes: &str) -> anyhow::Result<WatchlistEntry> {
+        match self {
+            Backend::Local { manager, .. } => WatchlistStore::new(&manager.config_dir())?.set_notes(url, notes),
+            Backend::Remote(client) => client.set_watchlist_notes(url, notes).await,
+        }
+    }
+
+    /// Remove a show from the list. Returns false if it wasn't listed.
+    pub async fn remove_from_watchlist(&self, url: &str) -> anyhow::Result<bool> {
+        match self {
+            Backend::Local { manager, .. } => WatchlistStore::new(&manager.config_dir())?.remove(url),
+            Backend::Remote(client) => client.remove_from_watchlist(url).await,
+        }
+    }
+
+    /// The list as JSON, or in the MyAnimeList XML format when `mal` is set
+    pub async fn export_watchlist(&self, mal: bool) -> anyhow::Result<String> {
+        match self {
+            Backend::Local { manager, .. } => {
+                let watchlist = WatchlistStore::new(&manager.config_dir())?;
+                if mal {
+                    Ok(watchlist.export_mal_xml())
+                } else {
+                    watchlist.export_json()
+                }
+            }
+            Backend::Remote(client) => client.export_watchlist(mal).await,
+        }
+    }
+
+    /// Merge an exported list. Returns the number of imported entries.
+    pub async fn import_watchlist(&self, data: &str, mal: bool) -> anyhow::Result<usize> {
+        match self {
+            Backend::Local { manager, .. } => {
+                let watchlist = WatchlistStore::new(&manager.config_dir())?;
+                if mal {
+                    watchlist.import_mal_xml(data)
+                } else {
+                    watchlist.import_json(data)
+                }
+            }
+            Backend::Remote(client) => client.import_watchlist(data, mal).await,
+        }
+    }
+
+    /// Manager events as they happen
+    pub async fn events(&self) -> anyhow::Result<mpsc::Receiver<ManagerEvent>> {
+        match self {
+            Backend::Local { manager, .. } => {
+                let mut events = manager.subscribe_events();
+                let (tx, rx) = mpsc::channel(64);
+                tokio::spawn(async move {
+                    loop {
+                        match events.recv().await {
+                            Ok(event) => {
+                                if tx.send(event).await.is_err() {
+                                    break;
+                                }
+                            }
+                            Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                            Err(broadcast::error::RecvError::Closed) => break,
+                        }
+                    }
+                });
+                Ok(rx)
+            }
+            Backend::Remote(client) => client.events().await,
+        }
+    }
+}
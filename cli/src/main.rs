@@ -1,13 +1,46 @@
+use aura_core::cache::DEFAULT_PAGE_LIMIT;
+use aura_core::import::parse_episode_range;
+use aura_core::manager::{is_episode_downloaded, resolve_config_dir, safe_file_name};
+use aura_core::network::HttpClients;
 use aura_core::{
-    AnimeScraper, AnimeInfo, DownloadJob, DownloadManager, DownloadTask, Episode, PauseReason,
+    daemon::{self, DaemonClient}, import::{self, ListFormat}, QueueBundle, metadata::jellyfin_episode_filename, streaming, watch_state, AnimeScraper, AnimeInfo, DownloadJob, DownloadManager,
+    DownloadTask, Episode, ImageCache, ListStatus, MetadataWriter, Notification,
+    AiringStatus, AnimeKind, ConfigLayers, NotificationCenter, NotificationSink, PauseReason, PlaylistFormat, Provider, SearchQuery, Settings, SubscriptionManager,
     TaskStatus,
 };
-use clap::{Parser, Subcommand};
+use backend::Backend;
+use clap::{Args, Parser, Subcommand, ValueEnum};
 use console::{Style, Term};
 use dialoguer::{theme::ColorfulTheme, Input, Select};
-use std::collections::HashSet;
-use std::sync::Arc;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::{IsTerminal, Write};
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use tui::LogBuffer;
 
+mod backend;
+mod tui;
+
+/// Number of recent notifications summarised under the download list
+const NOTIFICATION_SUMMARY_LEN: usize = 5;
+
+/// Exit code of `get` when no released episode matches `--episodes`
+const EXIT_NO_EPISODES: i32 = 3;
+/// Exit code of `get` when at least one episode could not be queued or downloaded, and of
+/// `doctor` when a check fails
+const EXIT_FAILED: i32 = 4;
+
+/// Rings the terminal bell for every new notification
+struct TerminalBellSink;
+
+impl NotificationSink for TerminalBellSink {
+    fn deliver(&self, _notification: &Notification) {
+        let mut stderr = std::io::stderr();
+        let _ = stderr.write_all(b"\x07");
+        let _ = stderr.flush();
+    }
+}
 
 #[derive(Parser)]
 #[command(name = "aura-cli")]
@@ -19,6 +52,97 @@ struct Cli {
     /// Optional custom configuration directory
     #[arg(long, global = true)]
     config_dir: Option<String>,
+
+    /// Ignore cached search/season/listing results and fetch them again
+    #[arg(long, global = true)]
+    refresh: bool,
+
+    /// Run in this process even if a daemon is running
+    #[arg(long, global = true)]
+    local: bool,
+
+    /// Output format of every command except serve, daemon start and subs watch
+    #[arg(long, global = true, value_enum, default_value_t = OutputFormat::Table)]
+    format: OutputFormat,
+
+    /// Download directory for this run (overrides settings files and AURA_DOWNLOAD_DIR)
+    #[arg(long, global = true)]
+    download_dir: Option<PathBuf>,
+
+    /// Maximum parallel downloads for this run
+    #[arg(long, global = true)]
+    max_concurrent: Option<usize>,
+
+    /// Segments per file for this run
+    #[arg(long, global = true)]
+    segments_per_file: Option<usize>,
+
+    /// Override any setting for this run, e.g. 'cache.capacity=512' (repeatable)
+    #[arg(long = "set", global = true, value_name = "KEY=VALUE")]
+    set: Vec<String>,
+}
+
+impl Cli {
+    /// Settings layers with this run's command line overrides on top
+    fn config_layers(&self) -> anyhow::Result<ConfigLayers> {
+        let mut layers = ConfigLayers::discover();
+        if let Some(dir) = &self.download_dir {
+            layers = layers.with_cli("download_dir", dir.to_string_lossy(), "--download-dir");
+        }
+        if let Some(n) = self.max_concurrent {
+            layers = layers.with_cli("max_concurrent_downloads", n.to_string(), "--max-concurrent");
+        }
+        if let Some(n) = self.segments_per_file {
+            layers = layers.with_cli("segments_per_file", n.to_string(), "--segments-per-file");
+        }
+        for item in &self.set {
+            let (key, value) = item
+                .split_once('=')
+                .ok_or_else(|| anyhow::anyhow!("--set expects KEY=VALUE, got '{}'", item))?;
+            layers = layers.with_cli(key.trim(), value, "--set");
+        }
+        Ok(layers)
+    }
+
+    fn has_overrides(&self) -> bool {
+        self.download_dir.is_some() || self.max_concurrent.is_some() || self.segments_per_file.is_some() || !self.set.is_empty()
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    /// Human-readable text
+    Table,
+    /// One JSON document
+    Json,
+    /// One JSON object per line (`manage` streams events)
+    Ndjson,
+}
+
+/// Which slice of a listing to print
+#[derive(Args, Clone, Copy)]
+struct PageArgs {
+    /// Page to show, from 1
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..))]
+    page: u32,
+    /// Entries per page
+    #[arg(long, default_value_t = DEFAULT_PAGE_LIMIT, value_parser = parse_limit)]
+    limit: usize,
+}
+
+impl PageArgs {
+    /// Number of the first entry on the page, from 1
+    fn first_number(&self) -> usize {
+        (self.page as usize - 1) * self.limit + 1
+    }
+}
+
+fn parse_limit(value: &str) -> Result<usize, String> {
+    match value.parse::<usize>() {
+        Ok(0) => Err("must be at least 1".to_string()),
+        Ok(limit) => Ok(limit),
+        Err(e) => Err(e.to_string()),
+    }
 }
 
 #[derive(Subcommand)]
@@ -30,12 +154,63 @@ enum Commands {
         /// Enter interactive mode to select an anime and episodes
         #[arg(short, long)]
         interactive: bool,
+        /// Only shows that started airing in or after this year
+        #[arg(long)]
+        year_from: Option<u32>,
+        /// Only shows that started airing in or before this year
+        #[arg(long)]
+        year_to: Option<u32>,
+        /// Only shows with this tag (repeat for several)
+        #[arg(long = "tag")]
+        tags: Vec<String>,
+        /// Only shows of this type: tv, movie, ova or special (guessed from the title)
+        #[arg(long)]
+        kind: Option<AnimeKind>,
+        /// Only shows with at least this many episodes released
+        #[arg(long)]
+        min_episodes: Option<u32>,
+        #[command(flatten)]
+        paging: PageArgs,
     },
     /// Get season information and select episodes to download
     Season {
         /// The AnimeHeaven URL (e.g., https://animeheaven.me/anime.php?17c9p)
         url: String,
     },
+    /// Queue episodes of a season without prompts (for scripts)
+    Get {
+        /// The AnimeHeaven season URL
+        url: String,
+        /// Episodes to download (e.g. '1-12,15'); all released episodes when omitted
+        #[arg(short, long)]
+        episodes: Option<String>,
+        /// Block until the episodes finish instead of returning the job id
+        #[arg(long)]
+        wait: bool,
+        /// File name template relative to the download directory, e.g. '{title}/{title} - {episode}.mp4'
+        /// ({title}, {episode} and {name} are replaced)
+        #[arg(short, long)]
+        output: Option<String>,
+        /// Only print the job id (without --wait) and errors
+        #[arg(short, long, conflicts_with = "json")]
+        quiet: bool,
+        /// Print a JSON summary instead of progress lines
+        #[arg(long)]
+        json: bool,
+    },
+    /// Queue many shows or videos from a TOML, JSON or text list
+    Import {
+        /// List file (`.toml`, `.json`, anything else is read as text)
+        file: PathBuf,
+        /// Season pages and links fetched at the same time
+        #[arg(long, default_value_t = 4)]
+        concurrency: usize,
+    },
+    /// Move the unfinished download queue to another machine
+    Queue {
+        #[command(subcommand)]
+        action: QueueAction,
+    },
     /// Manage Downloads (Interactive Status View)
     Manage,
     /// Resolve the direct download link for an episode
@@ -46,20 +221,305 @@ enum Commands {
         gate_id: String,
     },
     /// Get new released anime list
-    New,
+    New {
+        #[command(flatten)]
+        paging: PageArgs,
+    },
     /// Get popular anime list
-    Popular,
+    Popular {
+        #[command(flatten)]
+        paging: PageArgs,
+    },
+    /// Show the notification feed and mark it as read
+    Notifications {
+        /// Delete all notifications afterwards
+        #[arg(long)]
+        clear: bool,
+    },
+    /// Follow shows and queue their new episodes automatically
+    Subs {
+        #[command(subcommand)]
+        action: SubsAction,
+    },
+    /// Track watched episodes
+    Watched {
+        #[command(subcommand)]
+        action: WatchedAction,
+    },
+    /// Manage your personal anime list
+    List {
+        #[command(subcommand)]
+        action: ListAction,
+    },
+    /// Write M3U8/XSPF playlists of the completed episodes of a show
+    Playlist {
+        /// Season URL, show title or show folder
+        target: String,
+        /// Only write one format (m3u8 or xspf). Not `--format`, which picks the output format.
+        #[arg(long)]
+        playlist_format: Option<PlaylistFormat>,
+    },
+    /// Serve the library over HTTP so other devices on the LAN can stream it
+    Serve {
+        /// Address to listen on (default from settings, 127.0.0.1)
+        #[arg(long)]
+        bind: Option<String>,
+        /// Port to listen on (default from settings, 8377)
+        #[arg(long)]
+        port: Option<u16>,
+    },
+    /// Run the download manager in the background and control it through a local API
+    Daemon {
+        #[command(subcommand)]
+        action: DaemonAction,
+    },
+    /// Settings merged from defaults, system, user and project files, environment and flags
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+    /// NFO and poster files for media servers (Jellyfin, Kodi, Plex)
+    Metadata {
+        #[command(subcommand)]
+        action: MetadataAction,
+    },
+    /// Check that Aura still works with the site
+    Doctor {
+        #[command(subcommand)]
+        action: DoctorAction,
+    },
+}
+
+#[derive(Subcommand)]
+enum DoctorAction {
+    /// Fetch each page the scraper reads and report which selectors still match
+    Scraper {
+        /// Search used to reach a season and a gate page
+        #[arg(long, default_value = "naruto")]
+        query: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigAction {
+    /// Print the effective settings
+    Show {
+        /// Also print where each value comes from (default, file, environment or flag)
+        #[arg(long)]
+        origin: bool,
+        /// Print secrets such as the proxy password instead of `***`
+        #[arg(long)]
+        show_secrets: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum DaemonAction {
+    /// Start the daemon in the foreground (Ctrl+C to stop)
+    Start {
+        /// Address to listen on (default from settings, 127.0.0.1)
+        #[arg(long)]
+        bind: Option<String>,
+        /// Port to listen on (default from settings, 8378)
+        #[arg(long)]
+        port: Option<u16>,
+    },
+    /// Show whether a daemon is running
+    Status,
+    /// Stop the running daemon
+    Stop,
+}
+
+#[derive(Subcommand)]
+enum MetadataAction {
+    /// Rewrite metadata for every show in the download directory
+    Regenerate,
+}
+
+#[derive(Subcommand)]
+enum ListAction {
+    /// Show your list
+    Show {
+        /// Only show one status (plan, watching, completed, on-hold, dropped)
+        #[arg(long)]
+        status: Option<ListStatus>,
+    },
+    /// Add a show or change its status
+    Add {
+        /// The AnimeHeaven season URL
+        url: String,
+        /// plan, watching, completed, on-hold or dropped
+        #[arg(long, default_value = "plan")]
+        status: ListStatus,
+    },
+    /// Remove a show from your list
+    Remove {
+        /// The AnimeHeaven season URL
+        url: String,
+    },
+    /// Score a show from 1 to 10 (0 clears the score)
+    Score {
+        /// The AnimeHeaven season URL
+        url: String,
+        score: u8,
+    },
+    /// Set notes for a show
+    Notes {
+        /// The AnimeHeaven season URL
+        url: String,
+        notes: String,
+    },
+    /// Export your list (`.xml` writes the MyAnimeList format, anything else JSON)
+    Export { file: PathBuf },
+    /// Import a list (`.xml` reads the MyAnimeList format, anything else JSON)
+    Import { file: PathBuf },
+}
+
+#[derive(Subcommand)]
+enum QueueAction {
+    /// Write the unfinished jobs to a portable JSON bundle
+    Export {
+        /// Bundle file to write
+        file: PathBuf,
+    },
+    /// Queue the jobs of a bundle, saving under this machine's download folder
+    Import {
+        /// Bundle file written by 'queue export'
+        file: PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+enum WatchedAction {
+    /// Mark episodes as watched
+    Mark {
+        /// The AnimeHeaven season URL
+        url: String,
+        /// Episodes to mark (e.g. '1-4, 6')
+        episodes: String,
+        /// Mark as unwatched instead
+        #[arg(long)]
+        unset: bool,
+    },
+    /// Show the next episode to watch
+    Next {
+        /// The AnimeHeaven season URL
+        url: String,
+    },
+    /// List shows you are watching
+    List,
+}
+
+#[derive(Subcommand)]
+enum SubsAction {
+    /// Follow a show by its season URL
+    Add {
+        /// The AnimeHeaven season URL
+        url: String,
+        /// Treat episodes up to this number as already seen (defaults to the latest released)
+        #[arg(long)]
+        from: Option<u32>,
+    },
+    /// Stop following a show
+    Remove {
+        /// The AnimeHeaven season URL
+        url: String,
+    },
+    /// List followed shows
+    List,
+    /// Check followed shows once and queue new episodes
+    Check,
+    /// Keep checking followed shows in the background while showing the download manager
+    Watch,
 }
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     let cli = Cli::parse();
 
+    // Logs go to the TUI log pane while it is open, otherwise warnings go to stderr
+    let logs = LogBuffer::default();
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::try_from_default_env()
+                .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("info")),
+        )
+        .with_writer(logs.clone())
+        .with_ansi(false)
+        .with_target(false)
+        .init();
+
     // Initialize components
-    let manager = Arc::new(DownloadManager::new(cli.config_dir)?);
-    println!("Manager initialized.");
-    
-    let scraper = manager.get_scraper();
+    // Scripted `get` keeps stdout for its own output
+    let format = cli.format;
+    let chatty = format == OutputFormat::Table
+        && !matches!(cli.command, Commands::Get { quiet: true, .. } | Commands::Get { json: true, .. });
+
+    // Servers and the follow loop run until interrupted and have nothing to serialize
+    if format != OutputFormat::Table
+        && matches!(
+            cli.command,
+            Commands::Serve { .. }
+                | Commands::Daemon { action: DaemonAction::Start { .. } }
+                | Commands::Subs { action: SubsAction::Watch }
+        )
+    {
+        anyhow::bail!("serve, daemon start and subs watch can only be used with --format table");
+    }
+
+    let config_dir = resolve_config_dir(cli.config_dir.clone())?;
+    let refresh = cli.refresh;
+
+    // Act as a thin client when a daemon owns the queue
+    let local_only = matches!(
+        cli.command,
+        Commands::Daemon { .. } | Commands::Config { .. } | Commands::Doctor { .. } | Commands::Serve { .. } | Commands::Metadata { .. }
+    );
+    let remote = if cli.local || local_only {
+        None
+    } else {
+        DaemonClient::discover(&config_dir).await
+    };
+    if remote.is_some() && cli.has_overrides() {
+        eprintln!("Warning: settings flags only apply to this process, not to the running daemon (use --local).");
+    }
+
+    // Only a local backend loads the queue and the other stores; a client just resolves links
+    let (backend, scraper) = match remote {
+        Some(client) => {
+            if chatty {
+                println!("Using daemon at {}", client.address());
+            }
+            let settings = Arc::new(RwLock::new(client.get_settings().await?));
+            let provider = Arc::new(Provider::load_or_builtin(&config_dir));
+            let scraper = Arc::new(AnimeScraper::with_clients(Arc::new(HttpClients::new(settings))).with_provider(provider));
+            (Backend::Remote(client), scraper)
+        }
+        None => {
+            let config_path = Some(config_dir.to_string_lossy().to_string());
+            let manager = Arc::new(DownloadManager::with_layers(config_path, cli.config_layers()?)?);
+            if chatty {
+                println!("Manager initialized.");
+            }
+            for error in manager.settings_errors() {
+                eprintln!("Warning: {}; using the default.", error);
+            }
+
+            let notifications = Arc::new(NotificationCenter::new(&config_dir)?);
+            notifications.add_sink(Box::new(TerminalBellSink));
+            tokio::spawn(notifications.clone().run(manager.subscribe_events()));
+
+            let images = Arc::new(ImageCache::new(&config_dir, manager.http_clients())?);
+            let metadata = Arc::new(MetadataWriter::new(images));
+            tokio::spawn(metadata.clone().run(manager.clone()));
+
+            // Hand edits of settings.toml apply to running downloads and the manage view
+            tokio::spawn(manager.clone().watch_settings());
+
+            let scraper = manager.get_scraper();
+            (Backend::Local { manager, notifications, metadata }, scraper)
+        }
+    };
 
     let bold = Style::new().bold();
     let dim = Style::new().dim();
@@ -67,11 +527,30 @@ async fn main() -> anyhow::Result<()> {
     let mut should_enter_manager = false;
 
     match cli.command {
-        Commands::Search { query, interactive } => {
-            println!("Searching for: '{}'...\n", query);
-            let results = scraper.search(&query).await?;
+        Commands::Search { query, interactive, year_from, year_to, tags, kind, min_episodes, paging } => {
+            if interactive && format != OutputFormat::Table {
+                anyhow::bail!("--interactive can only be used with --format table");
+            }
+            if chatty {
+                println!("Searching for: '{}'...\n", query);
+            }
+            let search = SearchQuery {
+                text: query,
+                year_from,
+                year_to,
+                tags,
+                kind,
+                min_episodes,
+            };
+            if chatty && search.has_filters() {
+                println!("Checking each result against the filters (fetches every season page)...\n");
+            }
+            let page = backend.search_page(&search, paging, refresh).await?;
+            let results = page.items;
 
-            if results.is_empty() {
+            if format != OutputFormat::Table {
+                print_items(format, &results)?;
+            } else if results.is_empty() {
                 println!("No results found.");
             } else if interactive {
                 let items: Vec<String> = results
@@ -91,10 +570,11 @@ async fn main() -> anyhow::Result<()> {
                     let selected = &results[selection];
                     println!("\n{}: {}\n", bold.apply_to("Selected"), selected.title);
 
-                    match scraper.get_season(&selected.url).await {
+                    match backend.get_season(&selected.url, refresh).await {
                         Ok(info) => {
                             display_anime_info(&info, &bold);
-                            if handle_download_selection(&scraper, &info, &manager).await? {
+                            let cover = Some(selected.image.clone());
+                            if handle_download_selection(&scraper, &info, cover, &backend).await? {
                                 should_enter_manager = true;
                             }
                         }
@@ -105,26 +585,129 @@ async fn main() -> anyhow::Result<()> {
                 }
             } else {
                 for (i, res) in results.iter().enumerate() {
-                    println!("{}. {}", i + 1, res.title);
+                    println!("{}. {}", paging.first_number() + i, res.title);
+                    if let Some(jp) = &res.japanese_title {
+                        println!("   {}", dim.apply_to(jp));
+                    }
                     println!("   URL: {}", res.url);
+                    println!("   Match: {:.0}%", res.relevance * 100.0);
                     println!();
                 }
+                print_more_hint(page.has_more, paging, &dim);
             }
         }
 
         Commands::Season { url } => {
-            println!("Fetching season data...\n");
-            let info = scraper.get_season(&url).await?;
+            if chatty {
+                println!("Fetching season data...\n");
+            }
+            let info = backend.get_season(&url, refresh).await?;
+            if format != OutputFormat::Table {
+                print_json(format, &info)?;
+                return Ok(());
+            }
             display_anime_info(&info, &bold);
-            if handle_download_selection(&scraper, &info, &manager).await? {
+            if handle_download_selection(&scraper, &info, None, &backend).await? {
                 should_enter_manager = true;
             }
         }
 
-        Commands::Manage => {
-            should_enter_manager = true;
+        Commands::Get { url, episodes, wait, output, quiet, json } => {
+            let info = backend.get_season(&url, refresh).await?;
+            let json = json || format != OutputFormat::Table;
+            let options = GetOptions { episodes, wait, output, quiet, json };
+            let code = run_get(&scraper, &info, &backend, options).await?;
+            if code != 0 {
+                std::process::exit(code);
+            }
         }
 
+        Commands::Import { file, concurrency } => {
+            let text = std::fs::read_to_string(&file)?;
+            let entries = import::parse_list(&text, ListFormat::from_path(&file))?;
+            if chatty {
+                println!("Resolving {} entries from {}...\n", entries.len(), file.display());
+            }
+            let report = backend.import(entries, concurrency).await?;
+
+            if format != OutputFormat::Table {
+                print_json(format, &report)?;
+            } else {
+                let label = |o: &import::ImportOutcome| match o.episode {
+                    Some(n) => format!("{} - Ep {:02}", o.title, n),
+                    None => o.title.clone(),
+                };
+                for o in &report.queued {
+                    println!("  [OK  ] {}", label(o));
+                }
+                for o in &report.skipped {
+                    println!("  [SKIP] {} ({})", label(o), o.detail);
+                }
+                for o in &report.failed {
+                    println!("  [ERR ] {}: {}", label(o), o.detail);
+                }
+                println!(
+                    "\n{}",
+                    bold.apply_to(format!(
+                        "Queued {}, skipped {}, failed {}.",
+                        report.queued.len(),
+                        report.skipped.len(),
+                        report.failed.len()
+                    ))
+                );
+                if !report.queued.is_empty() && !backend.is_remote() {
+                    println!("No daemon is running; the queued episodes download when 'aura-cli daemon start' runs.");
+                }
+            }
+            if !report.failed.is_empty() {
+                std::process::exit(EXIT_FAILED);
+            }
+        }
+
+        Commands::Queue { action } => match action {
+            QueueAction::Export { file } => {
+                let settings = backend.get_settings().await?;
+                let bundle = QueueBundle::from_jobs(&backend.get_jobs().await?, &settings.download_dir);
+                std::fs::write(&file, serde_json::to_string_pretty(&bundle)?)?;
+                let tasks: usize = bundle.jobs.iter().map(|j| j.tasks.len()).sum();
+                if format != OutputFormat::Table {
+                    print_json(format, &bundle)?;
+                } else {
+                    println!("Exported {} jobs ({} episodes) to {}.", bundle.jobs.len(), tasks, file.display());
+                }
+            }
+            QueueAction::Import { file } => {
+                let bundle: QueueBundle = serde_json::from_str(&std::fs::read_to_string(&file)?)?;
+                let settings = backend.get_settings().await?;
+                let jobs = bundle.into_jobs(&settings.download_dir)?;
+                for job in &jobs {
+                    for task in &job.tasks {
+                        if let Some(parent) = std::path::Path::new(&task.filename).parent() {
+                            std::fs::create_dir_all(parent)?;
+                        }
+                    }
+                    backend.enqueue(job.clone()).await?;
+                }
+                if format != OutputFormat::Table {
+                    print_items(format, &jobs)?;
+                } else {
+                    for job in &jobs {
+                        println!("  [OK  ] {} ({} episodes)", job.name, job.tasks.len());
+                    }
+                    println!("\n{}", bold.apply_to(format!("Imported {} jobs.", jobs.len())));
+                    if !jobs.is_empty() && !backend.is_remote() {
+                        println!("No daemon is running; the imported jobs download when 'aura-cli daemon start' runs.");
+                    }
+                }
+            }
+        },
+
+        Commands::Manage => match format {
+            OutputFormat::Table => should_enter_manager = true,
+            OutputFormat::Json => print_items(format, &backend.get_jobs().await?)?,
+            OutputFormat::Ndjson => stream_progress(&backend).await?,
+        },
+
         Commands::Download { url, gate_id } => {
             let ep = Episode {
                 name: "Test Download".to_string(),
@@ -133,27 +716,46 @@ async fn main() -> anyhow::Result<()> {
                 gate_id,
             };
 
-            println!("Resolving link for Episode...\n");
+            if chatty {
+                println!("Resolving link for Episode...\n");
+            }
             let link = scraper.get_download_link(&ep).await?;
+            if format != OutputFormat::Table {
+                print_json(format, &serde_json::json!({ "url": link }))?;
+                return Ok(());
+            }
 
             println!("{}", bold.apply_to("SUCCESS"));
             println!("Download Link: {}", link);
             println!("\nYou can copy this URL into your browser to download the .mp4 file.");
         }
 
-        Commands::New => {
-            println!("Fetching New Releases...\n");
-            let list = scraper.get_new().await?;
-            for item in list {
+        Commands::New { paging } => {
+            if chatty {
+                println!("Fetching New Releases...\n");
+            }
+            let page = backend.get_new_page(paging, refresh).await?;
+            if format != OutputFormat::Table {
+                print_items(format, &page.items)?;
+                return Ok(());
+            }
+            for item in page.items {
                 println!("{} - Ep {} ({})", item.title, item.latest_ep, item.time_ago);
                 println!("   {}\n", item.url);
             }
+            print_more_hint(page.has_more, paging, &dim);
         }
 
-        Commands::Popular => {
-            println!("Fetching Popular Releases...\n");
-            let list = scraper.get_popular().await?;
-            for item in list {
+        Commands::Popular { paging } => {
+            if chatty {
+                println!("Fetching Popular Releases...\n");
+            }
+            let page = backend.get_popular_page(paging, refresh).await?;
+            if format != OutputFormat::Table {
+                print_items(format, &page.items)?;
+                return Ok(());
+            }
+            for item in page.items {
                 println!(
                     "{} - Ep {} ({}) #{}",
                     item.title,
@@ -163,11 +765,475 @@ async fn main() -> anyhow::Result<()> {
                 );
                 println!("   {}\n", item.url);
             }
+            print_more_hint(page.has_more, paging, &dim);
+        }
+
+        Commands::Notifications { clear } => {
+            let list = backend.notifications().await?;
+            if format != OutputFormat::Table {
+                print_items(format, &list)?;
+            } else if list.is_empty() {
+                println!("No notifications.");
+            } else {
+                for n in &list {
+                    let marker = if n.read { " " } else { "*" };
+                    println!("{} {}: {}", marker, bold.apply_to(&n.title), n.body);
+                }
+            }
+            if clear {
+                backend.clear_notifications().await?;
+            } else {
+                backend.mark_notifications_read().await?;
+            }
+        }
+
+        Commands::Watched { action } => match action {
+            WatchedAction::Mark { url, episodes, unset } => {
+                let info = backend.get_season(&url, refresh).await?;
+                let selected = parse_episode_range(&episodes);
+                let mut marked: Vec<u32> = info
+                    .episodes
+                    .iter()
+                    .map(|ep| ep.number)
+                    .filter(|n| selected.contains(n))
+                    .collect();
+                marked.sort_unstable();
+                let mut entries = Vec::new();
+                for number in &marked {
+                    entries.push(backend.mark_watched(&info.url, &info.title, *number, !unset).await?);
+                }
+                if format != OutputFormat::Table {
+                    print_items(format, &entries)?;
+                    return Ok(());
+                }
+                let verb = if unset { "unwatched" } else { "watched" };
+                println!("Marked {} episode(s) of {} as {}.", marked.len(), bold.apply_to(&info.title), verb);
+            }
+            WatchedAction::Next { url } => {
+                let info = backend.get_season(&url, refresh).await?;
+                let entries = backend.watch_state(&info.url).await?;
+                let next = watch_state::next_episode(&entries, &info.episodes);
+                if format != OutputFormat::Table {
+                    let position_secs = next.as_ref().and_then(|ep| {
+                        entries
+                            .iter()
+                            .find(|e| e.episode_number == ep.number)
+                            .map(|e| e.position_secs)
+                    });
+                    print_json(
+                        format,
+                        &serde_json::json!({ "title": info.title, "next": next, "position_secs": position_secs }),
+                    )?;
+                    return Ok(());
+                }
+                match next {
+                    Some(ep) => {
+                        let resume = entries
+                            .iter()
+                            .find(|e| e.episode_number == ep.number && e.position_secs > 0)
+                            .map(|e| format!(" (resume at {}:{:02})", e.position_secs / 60, e.position_secs % 60))
+                            .unwrap_or_default();
+                        println!("Next up in {}: Ep {:02}{}", bold.apply_to(&info.title), ep.number, resume);
+                    }
+                    None => println!("All released episodes of {} are watched.", info.title),
+                }
+            }
+            WatchedAction::List => {
+                let shows = backend.in_progress_shows().await?;
+                if format != OutputFormat::Table {
+                    print_items(format, &shows)?;
+                    return Ok(());
+                }
+                if shows.is_empty() {
+                    println!("Nothing in progress. Mark episodes with 'watched mark <URL> <EPISODES>'.");
+                }
+                for show in shows {
+                    let last = show
+                        .last_watched_episode
+                        .map(|n| format!("last watched Ep {:02}", n))
+                        .unwrap_or_else(|| "not finished any episode".to_string());
+                    println!("{} - {} ({} watched)", show.anime_title, last, show.watched_episodes);
+                    if let Some(ep) = show.resume_episode {
+                        println!("   Resume Ep {:02}", ep);
+                    }
+                    println!("   {}\n", show.anime_url);
+                }
+            }
+        },
+
+        Commands::Playlist { target, playlist_format } => {
+            let paths = backend.export_playlist(&target, playlist_format).await?;
+            if format != OutputFormat::Table {
+                print_items(format, &paths)?;
+                return Ok(());
+            }
+            for path in paths {
+                println!("Wrote {}", path.display());
+            }
+        }
+
+        Commands::Serve { bind, port } => {
+            let manager = backend.manager()?;
+            let settings = manager.get_settings().streaming;
+            let bind = bind.unwrap_or(settings.bind_address);
+            let port = port.unwrap_or(settings.port);
+            let listener = tokio::net::TcpListener::bind((bind.as_str(), port)).await?;
+            println!("Serving {} on http://{}", manager.get_settings().download_dir.display(), listener.local_addr()?);
+            println!("Library index: http://{}/api/library (Ctrl+C to stop)", listener.local_addr()?);
+            streaming::serve_listener(manager.clone(), listener).await?;
+        }
+
+        Commands::Config { action } => match action {
+            ConfigAction::Show { origin, show_secrets } => {
+                let manager = backend.manager()?;
+                let mut settings = manager.get_settings();
+                // `config show` output ends up in bug reports
+                if !show_secrets && !settings.network.proxy_password.is_empty() {
+                    settings.network.proxy_password = "***".to_string();
+                }
+                let origins = manager.settings_origins();
+                let values = serde_json::to_value(&settings)?;
+                let value_of = |key: &str| values.pointer(&format!("/{}", key.replace('.', "/"))).cloned();
+                if format != OutputFormat::Table {
+                    if origin {
+                        let entries: serde_json::Map<String, serde_json::Value> = origins
+                            .iter()
+                            .map(|(key, source)| {
+                                (key.clone(), serde_json::json!({ "value": value_of(key), "origin": source }))
+                            })
+                            .collect();
+                        print_json(format, &entries)?;
+                    } else {
+                        print_json(format, &settings)?;
+                    }
+                } else if origin {
+                    for (key, source) in &origins {
+                        let value = value_of(key).map(|v| v.to_string()).unwrap_or_default();
+                        println!("{} = {}  {}", key, value, dim.apply_to(format!("# {}", source)));
+                    }
+                } else {
+                    print!("{}", toml::to_string_pretty(&settings)?);
+                }
+            }
+        },
+
+        Commands::Daemon { action } => match action {
+            DaemonAction::Start { bind, port } => {
+                let Backend::Local { manager, notifications, .. } = &backend else {
+                    unreachable!("daemon commands always run locally");
+                };
+                if let Some(running) = DaemonClient::discover(&config_dir).await {
+                    anyhow::bail!("A daemon is already running at {}", running.address());
+                }
+                let settings = manager.get_settings();
+                let bind = bind.unwrap_or(settings.daemon.bind_address);
+                let port = port.unwrap_or(settings.daemon.port);
+                let listener = tokio::net::TcpListener::bind((bind.as_str(), port)).await?;
+
+                let subscriptions = Arc::new(SubscriptionManager::new(&config_dir)?);
+                tokio::spawn(subscriptions.clone().run_poller(manager.clone()));
+                if settings.streaming.enabled {
+                    tokio::spawn(streaming::serve(manager.clone()));
+                }
+
+                println!("Daemon listening on http://{} (Ctrl+C to stop)", listener.local_addr()?);
+                println!("Token written to {}", daemon::DaemonInfo::path(&config_dir).display());
+                daemon::run(manager.clone(), notifications.clone(), subscriptions, listener).await?;
+                println!("Daemon stopped.");
+            }
+            DaemonAction::Status => match DaemonClient::discover(&config_dir).await {
+                Some(client) => {
+                    let jobs = client.get_jobs().await?;
+                    let active = jobs
+                        .iter()
+                        .flat_map(|j| &j.tasks)
+                        .filter(|t| matches!(t.status, TaskStatus::Pending | TaskStatus::Downloading))
+                        .count();
+                    if format != OutputFormat::Table {
+                        print_json(
+                            format,
+                            &serde_json::json!({
+                                "running": true,
+                                "address": client.address(),
+                                "jobs": jobs.len(),
+                                "active_tasks": active,
+                            }),
+                        )?;
+                        return Ok(());
+                    }
+                    println!("Daemon running at {}", client.address());
+                    println!("{} job(s), {} episode(s) queued or downloading", jobs.len(), active);
+                }
+                None if format != OutputFormat::Table => print_json(format, &serde_json::json!({ "running": false }))?,
+                None => println!("No daemon running."),
+            },
+            DaemonAction::Stop => match DaemonClient::discover(&config_dir).await {
+                Some(client) => {
+                    client.shutdown().await?;
+                    if format != OutputFormat::Table {
+                        print_json(format, &serde_json::json!({ "running": true, "address": client.address() }))?;
+                    } else {
+                        println!("Daemon at {} is shutting down.", client.address());
+                    }
+                }
+                None if format != OutputFormat::Table => print_json(format, &serde_json::json!({ "running": false }))?,
+                None => println!("No daemon running."),
+            },
+        },
+
+        Commands::Metadata { action } => match action {
+            MetadataAction::Regenerate => {
+                let Backend::Local { manager, metadata, .. } = &backend else {
+                    unreachable!("metadata commands always run locally");
+                };
+                if chatty {
+                    println!("Writing metadata for {}...", manager.get_settings().download_dir.display());
+                }
+                let report = metadata.regenerate_library(manager).await?;
+                if format != OutputFormat::Table {
+                    let failed: Vec<_> = report
+                        .failed
+                        .iter()
+                        .map(|(folder, error)| serde_json::json!({ "folder": folder, "error": error }))
+                        .collect();
+                    print_json(
+                        format,
+                        &serde_json::json!({ "shows": report.shows, "episodes": report.episodes, "failed": failed }),
+                    )?;
+                    return Ok(());
+                }
+                println!(
+                    "Updated {} show(s), {} episode(s).",
+                    report.shows, report.episodes
+                );
+                for (folder, error) in &report.failed {
+                    println!("  [ERR] {}: {}", folder.display(), error);
+                }
+            }
+        },
+
+        Commands::Doctor { action } => match action {
+            DoctorAction::Scraper { query } => {
+                // The manager falls back to the built-in definition when the override is broken
+                let provider_error = Provider::load(&config_dir).err();
+                if let Some(e) = &provider_error {
+                    eprintln!("{:#}", e);
+                }
+                if chatty {
+                    let provider = scraper.provider();
+                    let source = match &provider.source {
+                        Some(path) => path.display().to_string(),
+                        None => "built-in".to_string(),
+                    };
+                    println!("Provider: {} (version {}, {})", provider.name, provider.version, source);
+                    println!("Checking the site layout (search for '{}')...\n", query);
+                }
+                let checks = scraper.diagnose(&query).await;
+                if format != OutputFormat::Table {
+                    print_items(format, &checks)?;
+                } else {
+                    let ok = Style::new().green();
+                    let bad = Style::new().red();
+                    for check in &checks {
+                        let status = if check.healthy() { ok.apply_to("OK") } else { bad.apply_to("FAIL") };
+                        println!("{} {}  {}", bold.apply_to(format!("{:<8}", check.page)), status, dim.apply_to(&check.url));
+                        if let Some(error) = &check.error {
+                            println!("  {}", bad.apply_to(error));
+                        }
+                        for selector in &check.selectors {
+                            let mark = if selector.drifted() {
+                                bad.apply_to("drifted")
+                            } else if selector.matches == 0 {
+                                dim.apply_to("absent")
+                            } else {
+                                ok.apply_to("ok")
+                            };
+                            println!("  {:<16} {:<28} {:>4}  {}", selector.name, selector.css, selector.matches, mark);
+                        }
+                        println!();
+                    }
+                }
+                if provider_error.is_some() || !checks.iter().all(|c| c.healthy()) {
+                    std::process::exit(EXIT_FAILED);
+                }
+            }
+        },
+
+        // With a daemon running, it is the only process writing the list and the watch state
+        Commands::List { action } => {
+            match action {
+                ListAction::Show { status } => {
+                    let entries = backend.watchlist(status).await?;
+                    if format != OutputFormat::Table {
+                        print_items(format, &entries)?;
+                        return Ok(());
+                    }
+                    if entries.is_empty() {
+                        println!("Your list is empty. Add shows with 'list add <URL>'.");
+                    }
+                    for entry in entries {
+                        let score = entry
+                            .score
+                            .map(|s| format!(" - {}/10", s))
+                            .unwrap_or_default();
+                        println!("[{}] {}{}", entry.status.mal_label(), bold.apply_to(&entry.title), score);
+                        if !entry.notes.is_empty() {
+                            println!("   {}", dim.apply_to(&entry.notes));
+                        }
+                        println!("   {}\n", entry.url);
+                    }
+                }
+                ListAction::Add { url, status } => {
+                    let info = backend.get_season(&url, refresh).await?;
+                    let entry = backend.set_watchlist_status(&info.url, &info.title, status).await?;
+                    if format != OutputFormat::Table {
+                        print_json(format, &entry)?;
+                        return Ok(());
+                    }
+                    println!("{} is now on your list as '{}'.", bold.apply_to(&entry.title), entry.status.mal_label());
+                }
+                ListAction::Remove { url } => {
+                    let removed = backend.remove_from_watchlist(&url).await?;
+                    if format != OutputFormat::Table {
+                        print_json(format, &serde_json::json!({ "url": url, "removed": removed }))?;
+                    } else if removed {
+                        println!("Removed {} from your list.", url);
+                    } else {
+                        println!("{} is not on your list.", url);
+                    }
+                }
+                ListAction::Score { url, score } => {
+                    let entry = backend.set_watchlist_score(&url, (score > 0).then_some(score)).await?;
+                    if format != OutputFormat::Table {
+                        print_json(format, &entry)?;
+                        return Ok(());
+                    }
+                    println!("Scored {}.", entry.title);
+                }
+                ListAction::Notes { url, notes } => {
+                    let entry = backend.set_watchlist_notes(&url, &notes).await?;
+                    if format != OutputFormat::Table {
+                        print_json(format, &entry)?;
+                        return Ok(());
+                    }
+                    println!("Updated notes for {}.", entry.title);
+                }
+                ListAction::Export { file } => {
+                    let data = backend.export_watchlist(is_mal_file(&file)).await?;
+                    std::fs::write(&file, data)?;
+                    if format != OutputFormat::Table {
+                        print_json(format, &serde_json::json!({ "file": file }))?;
+                        return Ok(());
+                    }
+                    println!("Exported your list to {}.", file.display());
+                }
+                ListAction::Import { file } => {
+                    let data = std::fs::read_to_string(&file)?;
+                    let count = backend.import_watchlist(&data, is_mal_file(&file)).await?;
+                    if format != OutputFormat::Table {
+                        print_json(format, &serde_json::json!({ "file": file, "imported": count }))?;
+                        return Ok(());
+                    }
+                    println!("Imported {} entries from {}.", count, file.display());
+                }
+            }
         }
+
+        // With a daemon running, its store and poller are used so only one process writes the
+        // subscriptions and the queue
+        Commands::Subs { action } => match action {
+            SubsAction::Add { url, from } => {
+                if chatty {
+                    println!("Fetching season data...\n");
+                }
+                let sub = backend.subscribe(&url, from).await?;
+                if format != OutputFormat::Table {
+                    print_json(format, &sub)?;
+                    return Ok(());
+                }
+                println!(
+                    "Following {} (new episodes after Ep {:02} will be queued).",
+                    bold.apply_to(&sub.title),
+                    sub.last_seen_episode
+                );
+            }
+            SubsAction::Remove { url } => {
+                let removed = backend.unsubscribe(&url).await?;
+                if format != OutputFormat::Table {
+                    print_json(format, &serde_json::json!({ "url": url, "removed": removed }))?;
+                } else if removed {
+                    println!("Unfollowed {}.", url);
+                } else {
+                    println!("Not following {}.", url);
+                }
+            }
+            SubsAction::List => {
+                let list = backend.subscriptions().await?;
+                if format != OutputFormat::Table {
+                    print_items(format, &list)?;
+                    return Ok(());
+                }
+                if list.is_empty() {
+                    println!("Not following any shows. Add one with 'subs add <URL>'.");
+                }
+                for sub in list {
+                    println!("{} - last seen Ep {:02}", sub.title, sub.last_seen_episode);
+                    println!("   {}\n", sub.url);
+                }
+            }
+            SubsAction::Check => {
+                if chatty {
+                    println!("Checking followed shows...\n");
+                }
+                let queued = backend.check_subscriptions().await?;
+                if format != OutputFormat::Table {
+                    print_items(format, &queued)?;
+                    return Ok(());
+                }
+                if queued.is_empty() {
+                    println!("No new episodes.");
+                } else {
+                    for ep in &queued {
+                        println!("  [NEW] {} - Ep {:02}", ep.anime_title, ep.episode_number);
+                    }
+                    should_enter_manager = true;
+                }
+            }
+            SubsAction::Watch => {
+                match &backend {
+                    Backend::Remote(_) => println!("The daemon is already checking followed shows."),
+                    Backend::Local { manager, .. } => {
+                        let subscriptions = Arc::new(SubscriptionManager::new(&config_dir)?);
+                        tokio::spawn(subscriptions.run_poller(manager.clone()));
+                    }
+                }
+                should_enter_manager = true;
+            }
+        },
     }
 
     if should_enter_manager {
-        monitor_downloads(&manager).await;
+        if let Backend::Local { manager, .. } = &backend {
+            if manager.get_settings().streaming.enabled {
+                let manager = manager.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = streaming::serve(manager).await {
+                        eprintln!("Streaming server stopped: {}", e);
+                    }
+                });
+            }
+        }
+        if std::io::stdout().is_terminal() {
+            tui::run(tui::Context {
+                backend: &backend,
+                scraper: &scraper,
+                logs: &logs,
+                refresh,
+            })
+            .await?;
+        } else {
+            monitor_downloads(&backend).await;
+        }
     }
 
     Ok(())
@@ -181,6 +1247,12 @@ fn display_anime_info(info: &AnimeInfo, bold: &Style) {
     if let Some(year) = &info.year {
         println!("Year: {}", year);
     }
+    if let Some(status) = info.status {
+        println!("Status: {}", if status == AiringStatus::Airing { "Airing" } else { "Finished" });
+    }
+    if let Some(score) = info.score {
+        println!("Score: {}", score);
+    }
     if let Some(tags) = &info.tags {
         if !tags.is_empty() {
             println!("Tags: {}", tags.join(", "));
@@ -189,8 +1261,12 @@ fn display_anime_info(info: &AnimeInfo, bold: &Style) {
     if let Some(desc) = &info.description {
         println!("\nDescription:\n{}", Style::new().dim().apply_to(textwrap::fill(desc, 80)));
     }
+    if !info.related.is_empty() {
+        let related: Vec<&str> = info.related.iter().map(|r| r.title.as_str()).collect();
+        println!("\nRelated: {}", related.join(", "));
+    }
 
-    println!("\nTotal Episodes: {}\n", info.episodes.len());
+    println!("\n{}\n", info.release_summary());
 
     println!("EPISODES LIST:");
     for ep in &info.episodes {
@@ -202,7 +1278,8 @@ fn display_anime_info(info: &AnimeInfo, bold: &Style) {
 async fn handle_download_selection(
     scraper: &Arc<AnimeScraper>,
     info: &AnimeInfo,
-    manager: &Arc<DownloadManager>,
+    cover_url: Option<String>,
+    backend: &Backend,
 ) -> anyhow::Result<bool> {
     let bold = Style::new().bold();
     let green = Style::new().green();
@@ -211,11 +1288,16 @@ async fn handle_download_selection(
     let job_id = info.url.clone();
     let job_name = info.title.clone();
 
-    let download_root = manager.settings.download_dir.clone();
-    let safe_anime_name = info
-        .title
-        .replace(|c: char| !c.is_alphanumeric() && c != ' ' && c != '-', "_");
-    let anime_folder_path = download_root.join(&safe_anime_name);
+    let settings = backend.get_settings().await?;
+    let queued_tasks = backend
+        .get_jobs()
+        .await?
+        .into_iter()
+        .find(|j| j.id == job_id)
+        .map(|j| j.tasks)
+        .unwrap_or_default();
+    let watched = backend.watch_state(&info.url).await?;
+    let anime_folder_path = settings.download_dir.join(safe_file_name(&info.title));
 
     std::fs::create_dir_all(&anime_folder_path)?;
 
@@ -223,8 +1305,11 @@ async fn handle_download_selection(
     println!("\nEPISODE STATUS:");
     let yellow = Style::new().yellow();
     for ep in &info.episodes {
-        let is_downloaded = manager.is_episode_downloaded(&anime_folder_path, ep.number);
-        let queue_status = manager.is_episode_in_queue(&job_id, ep.number);
+        let is_downloaded = is_episode_downloaded(&anime_folder_path, ep.number);
+        let queue_status = queued_tasks
+            .iter()
+            .find(|t| t.episode_number == Some(ep.number))
+            .map(|t| t.status.clone());
         
         let status = if is_downloaded {
             green.apply_to("[DONE]").to_string()
@@ -239,7 +1324,12 @@ async fn handle_download_selection(
         } else {
             "[    ]".to_string()
         };
-        println!("  {} Ep {:02}: {}", status, ep.number, ep.name);
+        let watch_marker = match watched.iter().find(|e| e.episode_number == ep.number) {
+            Some(entry) if entry.watched => green.apply_to("[W]").to_string(),
+            Some(entry) if entry.position_secs > 0 => yellow.apply_to("[>]").to_string(),
+            _ => "[ ]".to_string(),
+        };
+        println!("  {} {} Ep {:02}: {}", status, watch_marker, ep.number, ep.name);
     }
     println!();
 
@@ -260,79 +1350,393 @@ async fn handle_download_selection(
     println!("\nFetching links and adding to queue...\n");
     println!("Target directory: {}", anime_folder_path.display());
 
-    let mut tasks = Vec::new();
-    let mut skipped = 0;
+    let selected: Vec<&Episode> = info
+        .episodes
+        .iter()
+        .filter(|ep| selected_numbers.contains(&ep.number))
+        .collect();
+    let fetched = fetch_episode_tasks(scraper, &job_id, &selected, true, |ep| {
+        default_episode_path(&settings, info, ep)
+    })
+    .await;
+    let tasks = fetched.tasks;
+    let skipped = fetched.skipped.len();
 
-    for ep in &info.episodes {
-        if selected_numbers.contains(&ep.number) {
-            // Skip already downloaded
-            if manager.is_episode_downloaded(&anime_folder_path, ep.number) {
+    if !tasks.is_empty() {
+        let count = tasks.len();
+        let job = DownloadJob {
+            id: job_id.clone(),
+            name: job_name,
+            tasks,
+            anime_url: Some(info.url.clone()),
+            cover_url,
+        };
+        backend.queue(job).await?;
+
+        let msg = if skipped > 0 {
+            format!("Added {} episodes to queue ({} already downloaded).", count, skipped)
+        } else {
+            format!("Added {} episodes to download queue.", count)
+        };
+        println!("\n{}", bold.apply_to(msg));
+        return Ok(true);
+    } else if skipped > 0 {
+        println!("\nAll selected episodes are already downloaded!");
+    }
+
+    Ok(false)
+}
+
+/// Tasks built for a set of episodes, plus the episodes that were left out
+struct EpisodeTasks {
+    tasks: Vec<DownloadTask>,
+    /// Already on disk
+    skipped: Vec<u32>,
+    /// Episode number and why its link could not be resolved
+    failed: Vec<(u32, String)>,
+}
+
+/// Resolve download links for `episodes` and build their tasks. `path_for` picks the output
+/// file of each episode; episodes whose file already exists are skipped.
+async fn fetch_episode_tasks(
+    scraper: &Arc<AnimeScraper>,
+    job_id: &str,
+    episodes: &[&Episode],
+    verbose: bool,
+    path_for: impl Fn(&Episode) -> PathBuf,
+) -> EpisodeTasks {
+    let mut result = EpisodeTasks {
+        tasks: Vec::new(),
+        skipped: Vec::new(),
+        failed: Vec::new(),
+    };
+
+    for ep in episodes {
+        let path = path_for(ep);
+        let folder = path.parent().map(PathBuf::from).unwrap_or_default();
+
+        // Skip already downloaded
+        if path.exists() || is_episode_downloaded(&folder, ep.number) {
+            if verbose {
                 println!("  [SKIP] Ep {:02} already downloaded.", ep.number);
-                skipped += 1;
-                continue;
             }
+            result.skipped.push(ep.number);
+            continue;
+        }
 
-            let filename_full = anime_folder_path.join(format!("Ep{:02}.mp4", ep.number));
-            let filename_str = filename_full.to_string_lossy().to_string();
-
+        if verbose {
             println!("Fetching link for Ep {:02}...", ep.number);
+        }
 
-            if ep.gate_id.is_empty() {
+        if ep.gate_id.is_empty() {
+            if verbose {
                 println!("  [WARN] Gate ID is empty for Ep {}, skipping.", ep.number);
-                continue;
             }
+            result.failed.push((ep.number, "Gate ID is empty".to_string()));
+            continue;
+        }
 
-            match scraper.get_download_link(ep).await {
-                Ok(link) => {
+        match scraper.get_download_link(ep).await {
+            Ok(link) => {
+                if verbose {
                     println!("  [OK] Link fetched for Ep {:02}.", ep.number);
-
-                    let task_id = format!("{}-ep{}", job_id, ep.number);
-
-                    tasks.push(DownloadTask {
-                        id: task_id,
-                        url: link,
-                        filename: filename_str,
-                        status: TaskStatus::Pending,
-                        progress_bytes: 0,
-                        total_bytes: 0,
-                        episode_url: Some(ep.url.clone()),
-                        gate_id: Some(ep.gate_id.clone()),
-                        episode_number: Some(ep.number),
-                        segments: vec![],
-                    });
                 }
-                Err(e) => {
+
+                result.tasks.push(DownloadTask {
+                    id: format!("{}-ep{}", job_id, ep.number),
+                    url: link,
+                    filename: path.to_string_lossy().to_string(),
+                    status: TaskStatus::Pending,
+                    progress_bytes: 0,
+                    total_bytes: 0,
+                    episode_url: Some(ep.url.clone()),
+                    gate_id: Some(ep.gate_id.clone()),
+                    episode_number: Some(ep.number),
+                    segments: vec![],
+                });
+            }
+            Err(e) => {
+                if verbose {
                     println!("  [ERR] Failed to fetch link for Ep {}: {}", ep.number, e);
                 }
+                result.failed.push((ep.number, e.to_string()));
             }
         }
     }
 
-    if !tasks.is_empty() {
-        let count = tasks.len();
+    result
+}
+
+struct GetOptions {
+    episodes: Option<String>,
+    wait: bool,
+    output: Option<String>,
+    quiet: bool,
+    json: bool,
+}
+
+/// Non-interactive counterpart of `handle_download_selection`. Returns the process exit code.
+async fn run_get(
+    scraper: &Arc<AnimeScraper>,
+    info: &AnimeInfo,
+    backend: &Backend,
+    options: GetOptions,
+) -> anyhow::Result<i32> {
+    let verbose = !options.quiet && !options.json;
+    let job_id = info.url.clone();
+
+    let selected_numbers = options.episodes.as_deref().map(parse_episode_range);
+    let selected: Vec<&Episode> = info
+        .episodes
+        .iter()
+        .filter(|ep| selected_numbers.as_ref().is_none_or(|s| s.contains(&ep.number)))
+        .collect();
+    if selected.is_empty() {
+        eprintln!("No released episode of {} matches the selection.", info.title);
+        return Ok(EXIT_NO_EPISODES);
+    }
+
+    let settings = backend.get_settings().await?;
+    let fetched = fetch_episode_tasks(scraper, &job_id, &selected, verbose, |ep| match &options.output {
+        Some(template) => settings.download_dir.join(render_output_template(template, &info.title, ep)),
+        None => default_episode_path(&settings, info, ep),
+    })
+    .await;
+
+    let mut queued: Vec<(u32, String)> = fetched
+        .tasks
+        .iter()
+        .filter_map(|t| Some((t.episode_number?, t.filename.clone())))
+        .collect();
+    queued.sort_unstable();
+    let mut failed = fetched.failed;
+
+    if !fetched.tasks.is_empty() {
+        for task in &fetched.tasks {
+            if let Some(parent) = std::path::Path::new(&task.filename).parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+        }
         let job = DownloadJob {
             id: job_id.clone(),
-            name: job_name,
-            tasks,
+            name: info.title.clone(),
+            tasks: fetched.tasks,
+            anime_url: Some(info.url.clone()),
+            cover_url: None,
         };
-        manager.add_job(job);
-        manager.start_download(job_id).await?;
-
-        let msg = if skipped > 0 {
-            format!("Added {} episodes to queue ({} already downloaded).", count, skipped)
+        // Without --wait this process exits right away, so only a daemon downloads the job
+        if options.wait {
+            backend.queue(job).await?;
         } else {
-            format!("Added {} episodes to download queue.", count)
-        };
-        println!("\n{}", bold.apply_to(msg));
-        return Ok(true);
-    } else if skipped > 0 {
-        println!("\nAll selected episodes are already downloaded!");
+            backend.enqueue(job).await?;
+        }
+        if verbose {
+            println!("Queued {} episode(s) of {}.", queued.len(), info.title);
+        }
     }
 
-    Ok(false)
+    let mut statuses: Vec<TaskStatus> = vec![TaskStatus::Pending; queued.len()];
+    if options.wait && !queued.is_empty() {
+        statuses = wait_for_episodes(backend, &job_id, &queued, verbose).await?;
+        for ((number, _), status) in queued.iter().zip(&statuses) {
+            match status {
+                TaskStatus::Error(e) => failed.push((*number, e.clone())),
+                TaskStatus::Paused(reason) => failed.push((*number, format!("Paused ({:?})", reason))),
+                _ => {}
+            }
+        }
+    } else if !options.wait && !queued.is_empty() && !backend.is_remote() && verbose {
+        println!("No daemon is running; the queued episodes download when 'aura-cli daemon start' runs (or use --wait).");
+    }
+    failed.sort_unstable_by_key(|(number, _)| *number);
+
+    if options.json {
+        let episodes: Vec<serde_json::Value> = queued
+            .iter()
+            .zip(&statuses)
+            .map(|((number, file), status)| {
+                serde_json::json!({ "episode": number, "file": file, "status": status_label(status) })
+            })
+            .collect();
+        let failures: Vec<serde_json::Value> = failed
+            .iter()
+            .map(|(number, error)| serde_json::json!({ "episode": number, "error": error }))
+            .collect();
+        let summary = serde_json::json!({
+            "job_id": job_id,
+            "title": info.title,
+            "queued": episodes,
+            "skipped": fetched.skipped,
+            "failed": failures,
+        });
+        println!("{}", serde_json::to_string_pretty(&summary)?);
+    } else {
+        if verbose {
+            if !fetched.skipped.is_empty() {
+                println!("{} episode(s) already downloaded.", fetched.skipped.len());
+            }
+            println!("Job: {}", job_id);
+        } else if !options.wait && !queued.is_empty() {
+            println!("{}", job_id);
+        }
+        for (number, error) in &failed {
+            eprintln!("Ep {:02} failed: {}", number, error);
+        }
+    }
+
+    Ok(if failed.is_empty() { 0 } else { EXIT_FAILED })
+}
+
+/// Poll the queue until every episode in `queued` has completed, failed or stalled.
+/// Returns the final status of each, in the same order.
+async fn wait_for_episodes(
+    backend: &Backend,
+    job_id: &str,
+    queued: &[(u32, String)],
+    verbose: bool,
+) -> anyhow::Result<Vec<TaskStatus>> {
+    let mut last: Vec<Option<TaskStatus>> = vec![None; queued.len()];
+    loop {
+        let job = backend.get_jobs().await?.into_iter().find(|j| j.id == job_id);
+        let statuses: Vec<TaskStatus> = queued
+            .iter()
+            .map(|(number, _)| {
+                job.as_ref()
+                    .and_then(|j| j.tasks.iter().find(|t| t.episode_number == Some(*number)))
+                    .map(|t| t.status.clone())
+                    .unwrap_or_else(|| TaskStatus::Error("Removed from the queue".to_string()))
+            })
+            .collect();
+
+        for (i, status) in statuses.iter().enumerate() {
+            if verbose && last[i].as_ref() != Some(status) {
+                println!("  Ep {:02}: {}", queued[i].0, status_label(status));
+            }
+            last[i] = Some(status.clone());
+        }
+
+        let finished = statuses.iter().all(|s| match s {
+            TaskStatus::Completed | TaskStatus::Error(_) => true,
+            TaskStatus::Paused(reason) => !matches!(reason, PauseReason::UserRequest),
+            TaskStatus::Pending | TaskStatus::Downloading => false,
+        });
+        if finished {
+            return Ok(statuses);
+        }
+        tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+    }
+}
+
+fn status_label(status: &TaskStatus) -> &'static str {
+    match status {
+        TaskStatus::Pending => "pending",
+        TaskStatus::Downloading => "downloading",
+        TaskStatus::Paused(_) => "paused",
+        TaskStatus::Completed => "completed",
+        TaskStatus::Error(_) => "error",
+    }
+}
+
+/// Expand `{title}`, `{episode}` (zero-padded) and `{name}` in a `--output` template.
+/// Substituted values are made safe for file names; `.mp4` is appended when missing.
+fn render_output_template(template: &str, title: &str, ep: &Episode) -> PathBuf {
+    let mut rendered = template
+        .replace("{title}", &safe_file_name(title))
+        .replace("{episode}", &format!("{:02}", ep.number))
+        .replace("{name}", &safe_file_name(&ep.name));
+    if !rendered.to_lowercase().ends_with(".mp4") {
+        rendered.push_str(".mp4");
+    }
+    PathBuf::from(rendered)
+}
+
+/// `<download dir>/<Show>/Ep01.mp4`, or `<Show> S01E01.mp4` with Jellyfin naming
+fn default_episode_path(settings: &Settings, info: &AnimeInfo, ep: &Episode) -> PathBuf {
+    let filename = if settings.metadata.jellyfin_naming {
+        jellyfin_episode_filename(&info.title, ep.number)
+    } else {
+        format!("Ep{:02}.mp4", ep.number)
+    };
+    settings.download_dir.join(safe_file_name(&info.title)).join(filename)
 }
 
-async fn monitor_downloads(manager: &Arc<DownloadManager>) {
+/// Print one value as pretty JSON, or on a single line for ndjson
+fn print_json<T: Serialize + ?Sized>(format: OutputFormat, value: &T) -> anyhow::Result<()> {
+    if format == OutputFormat::Ndjson {
+        println!("{}", serde_json::to_string(value)?);
+    } else {
+        println!("{}", serde_json::to_string_pretty(value)?);
+    }
+    Ok(())
+}
+
+/// Point at the next page when the listing goes on
+fn print_more_hint(has_more: bool, paging: PageArgs, dim: &Style) {
+    if has_more {
+        println!("{}", dim.apply_to(format!("More on --page {}", paging.page + 1)));
+    }
+}
+
+/// Print `items` as one JSON array, or one object per line for ndjson
+fn print_items<T: Serialize>(format: OutputFormat, items: &[T]) -> anyhow::Result<()> {
+    if format == OutputFormat::Ndjson {
+        for item in items {
+            print_json(format, item)?;
+        }
+        Ok(())
+    } else {
+        print_json(format, items)
+    }
+}
+
+/// `manage --format ndjson`: forward manager events and add a `TaskProgress` line whenever a
+/// task's status or byte count changes, until interrupted
+async fn stream_progress(backend: &Backend) -> anyhow::Result<()> {
+    let mut events = backend.events().await?;
+    let mut last: HashMap<String, (&'static str, u64, u64)> = HashMap::new();
+    let mut ticker = tokio::time::interval(tokio::time::Duration::from_secs(1));
+
+    loop {
+        tokio::select! {
+            event = events.recv() => match event {
+                Some(event) => print_json(OutputFormat::Ndjson, &event)?,
+                None => anyhow::bail!("Event stream closed"),
+            },
+            _ = ticker.tick() => {
+                for job in backend.get_jobs().await? {
+                    for task in &job.tasks {
+                        let state = (status_label(&task.status), task_bytes(task), task.total_bytes);
+                        if last.get(&task.id) == Some(&state) {
+                            continue;
+                        }
+                        last.insert(task.id.clone(), state);
+                        let line = serde_json::json!({
+                            "type": "TaskProgress",
+                            "job_id": job.id,
+                            "job_name": job.name,
+                            "task_id": task.id,
+                            "episode_number": task.episode_number,
+                            "status": state.0,
+                            "progress_bytes": state.1,
+                            "total_bytes": state.2,
+                        });
+                        print_json(OutputFormat::Ndjson, &line)?;
+                    }
+                }
+                std::io::stdout().flush()?;
+            }
+        }
+    }
+}
+
+/// Bytes downloaded so far: the file on disk once it exists, otherwise the recorded progress
+fn task_bytes(task: &DownloadTask) -> u64 {
+    std::fs::metadata(&task.filename)
+        .map(|m| m.len())
+        .unwrap_or(task.progress_bytes)
+}
+
+async fn monitor_downloads(backend: &Backend) {
     let bold = Style::new().bold();
     let term = Term::stdout();
 
@@ -341,7 +1745,13 @@ async fn monitor_downloads(manager: &Arc<DownloadManager>) {
         println!("--- Download Manager ---");
         println!("[Watching... Press Ctrl+C to exit]\n");
 
-        let jobs = manager.get_jobs();
+        let jobs = match backend.get_jobs().await {
+            Ok(jobs) => jobs,
+            Err(e) => {
+                println!("Lost connection to the daemon: {}", e);
+                return;
+            }
+        };
 
         for job in &jobs {
             println!("Job: {}", bold.apply_to(&job.name));
@@ -359,14 +1769,11 @@ async fn monitor_downloads(manager: &Arc<DownloadManager>) {
                     TaskStatus::Error(_) => "[ERR ]",
                 };
 
-                let bytes_on_disk = std::fs::metadata(&task.filename)
-                    .map(|m| m.len())
-                    .unwrap_or(task.progress_bytes);
+                let bytes_on_disk = task_bytes(task);
 
-                let progress = if task.total_bytes > 0 {
-                    format!("{}%", (bytes_on_disk * 100 / task.total_bytes))
-                } else {
-                    "?%".to_string()
+                let progress = match (bytes_on_disk * 100).checked_div(task.total_bytes) {
+                    Some(percent) => format!("{}%", percent),
+                    None => "?%".to_string(),
                 };
 
                 let display_name = std::path::Path::new(&task.filename)
@@ -396,34 +1803,51 @@ async fn monitor_downloads(manager: &Arc<DownloadManager>) {
             println!("No active jobs. Add episodes using 'search' or 'season'.");
         }
 
+        let unread: Vec<Notification> = backend
+            .notifications()
+            .await
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|n| !n.read)
+            .collect();
+        if !unread.is_empty() {
+            println!("Notifications ({} unread):", unread.len());
+            for n in unread.iter().take(NOTIFICATION_SUMMARY_LEN) {
+                println!("  * {}: {}", bold.apply_to(&n.title), n.body);
+            }
+        }
+
         tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
     }
 }
 
-fn parse_episode_range(input: &str) -> HashSet<u32> {
-    let mut selection = HashSet::new();
+fn is_mal_file(path: &std::path::Path) -> bool {
+    path.extension()
+        .map(|ext| ext.eq_ignore_ascii_case("xml"))
+        .unwrap_or(false)
+}
 
-    for part in input.split(',') {
-        let part = part.trim();
-        if part.is_empty() {
-            continue;
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
 
-        if part.contains('-') {
-            let range_parts: Vec<&str> = part.split('-').collect();
-            if range_parts.len() == 2 {
-                if let (Ok(start), Ok(end)) = (
-                    range_parts[0].trim().parse::<u32>(),
-                    range_parts[1].trim().parse::<u32>(),
-                ) {
-                    for i in start..=end {
-                        selection.insert(i);
-                    }
-                }
+    #[test]
+    fn cli_definition_and_format_flags() {
+        Cli::command().debug_assert();
+
+        let cli = Cli::try_parse_from(["aura-cli", "playlist", "Show", "--playlist-format", "xspf", "--format", "json"]).unwrap();
+        assert!(cli.format == OutputFormat::Json);
+        match cli.command {
+            Commands::Playlist { target, playlist_format } => {
+                assert_eq!(target, "Show");
+                assert_eq!(playlist_format, Some(PlaylistFormat::Xspf));
             }
-        } else if let Ok(num) = part.parse::<u32>() {
-            selection.insert(num);
+            _ => panic!("expected the playlist command"),
         }
+
+        let cli = Cli::try_parse_from(["aura-cli", "playlist", "Show"]).unwrap();
+        assert!(cli.format == OutputFormat::Table);
+        assert!(matches!(cli.command, Commands::Playlist { playlist_format: None, .. }));
     }
-    selection
-}
\ No newline at end of file
+}
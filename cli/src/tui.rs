@@ -0,0 +1,718 @@
+use crate::backend::Backend;
+use crate::{default_episode_path, fetch_episode_tasks, status_label, task_bytes};
+use aura_core::import::parse_episode_range;
+use aura_core::{
+    AnimeInfo, AnimeScraper, DownloadJob, DownloadTask, Episode, PauseReason,
+    SearchQuery, SearchResult, SegmentStatus, TaskStatus,
+};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph, Wrap};
+use ratatui::{DefaultTerminal, Frame};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tracing::{Level, Metadata};
+use tracing_subscriber::fmt::MakeWriter;
+
+/// Lines kept for the log pane
+const LOG_CAPACITY: usize = 500;
+const REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+/// Weight of the newest sample in the smoothed download speed
+const SPEED_SMOOTHING: f64 = 0.3;
+const BAR_WIDTH: usize = 16;
+
+/// Tracing output of the CLI. While the TUI is open, log lines are kept for its log pane;
+/// otherwise warnings and errors go to stderr and everything else is dropped.
+#[derive(Clone, Default)]
+pub struct LogBuffer {
+    lines: Arc<Mutex<VecDeque<String>>>,
+    capture: Arc<AtomicBool>,
+}
+
+impl LogBuffer {
+    fn set_capture(&self, capture: bool) {
+        self.capture.store(capture, Ordering::Relaxed);
+    }
+
+    fn last(&self, count: usize) -> Vec<String> {
+        let lines = self.lines.lock().unwrap();
+        lines.iter().skip(lines.len().saturating_sub(count)).cloned().collect()
+    }
+}
+
+pub enum LogWriter {
+    Buffer(LogBuffer),
+    Stderr(io::Stderr),
+    Discard,
+}
+
+impl Write for LogWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            LogWriter::Buffer(buffer) => {
+                let text = String::from_utf8_lossy(buf);
+                let mut lines = buffer.lines.lock().unwrap();
+                for line in text.lines().filter(|l| !l.trim().is_empty()) {
+                    if lines.len() == LOG_CAPACITY {
+                        lines.pop_front();
+                    }
+                    lines.push_back(line.to_string());
+                }
+                Ok(buf.len())
+            }
+            LogWriter::Stderr(stderr) => stderr.write(buf),
+            LogWriter::Discard => Ok(buf.len()),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            LogWriter::Stderr(stderr) => stderr.flush(),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl<'a> MakeWriter<'a> for LogBuffer {
+    type Writer = LogWriter;
+
+    fn make_writer(&'a self) -> LogWriter {
+        if self.capture.load(Ordering::Relaxed) {
+            LogWriter::Buffer(self.clone())
+        } else {
+            LogWriter::Stderr(io::stderr())
+        }
+    }
+
+    fn make_writer_for(&'a self, meta: &Metadata<'_>) -> LogWriter {
+        if self.capture.load(Ordering::Relaxed) {
+            LogWriter::Buffer(self.clone())
+        } else if *meta.level() <= Level::WARN {
+            LogWriter::Stderr(io::stderr())
+        } else {
+            LogWriter::Discard
+        }
+    }
+}
+
+/// What the TUI needs to act on the queue and to add episodes
+pub struct Context<'a> {
+    pub backend: &'a Backend,
+    pub scraper: &'a Arc<AnimeScraper>,
+    pub logs: &'a LogBuffer,
+    pub refresh: bool,
+}
+
+/// Run the full-screen download manager until the user quits
+pub async fn run(ctx: Context<'_>) -> anyhow::Result<()> {
+    let mut terminal = ratatui::init();
+    ctx.logs.set_capture(true);
+    let result = event_loop(&mut terminal, &ctx).await;
+    ctx.logs.set_capture(false);
+    ratatui::restore();
+    result
+}
+
+enum Row {
+    Job(usize),
+    Task(usize, usize),
+}
+
+struct Speed {
+    bytes: u64,
+    at: Instant,
+    per_sec: f64,
+}
+
+enum Search {
+    Query(String),
+    Results {
+        results: Vec<SearchResult>,
+        selected: usize,
+    },
+    Episodes {
+        info: Box<AnimeInfo>,
+        cover: Option<String>,
+        input: String,
+    },
+}
+
+enum Action {
+    Quit,
+    Pause(String, Option<String>),
+    Resume(String, Option<String>),
+    Cancel(String, Option<String>),
+    Move(String, isize),
+    Search(String),
+    OpenSeason(SearchResult),
+    Queue(Box<AnimeInfo>, Option<String>, String),
+}
+
+#[derive(Default)]
+struct App {
+    jobs: Vec<DownloadJob>,
+    collapsed: HashSet<String>,
+    selected: usize,
+    speeds: HashMap<String, Speed>,
+    search: Option<Search>,
+    show_logs: bool,
+    confirm_cancel: bool,
+    status: Option<String>,
+}
+
+impl App {
+    fn rows(&self) -> Vec<Row> {
+        let mut rows = Vec::new();
+        for (j, job) in self.jobs.iter().enumerate() {
+            rows.push(Row::Job(j));
+            if !self.collapsed.contains(&job.id) {
+                rows.extend((0..job.tasks.len()).map(|t| Row::Task(j, t)));
+            }
+        }
+        rows
+    }
+
+    fn refresh(&mut self, jobs: Vec<DownloadJob>) {
+        let now = Instant::now();
+        for task in jobs.iter().flat_map(|j| &j.tasks) {
+            let bytes = task_bytes(task);
+            let speed = self.speeds.entry(task.id.clone()).or_insert(Speed { bytes, at: now, per_sec: 0.0 });
+            let elapsed = now.duration_since(speed.at).as_secs_f64();
+            if task.status != TaskStatus::Downloading {
+                speed.per_sec = 0.0;
+            } else if elapsed > 0.0 {
+                let sample = bytes.saturating_sub(speed.bytes) as f64 / elapsed;
+                speed.per_sec = SPEED_SMOOTHING * sample + (1.0 - SPEED_SMOOTHING) * speed.per_sec;
+            }
+            speed.bytes = bytes;
+            speed.at = now;
+        }
+        self.jobs = jobs;
+        self.selected = self.selected.min(self.rows().len().saturating_sub(1));
+    }
+
+    /// Selected job, and the selected task when a task row is highlighted
+    fn selection(&self) -> Option<(&DownloadJob, Option<&DownloadTask>)> {
+        match self.rows().get(self.selected)? {
+            Row::Job(j) => Some((&self.jobs[*j], None)),
+            Row::Task(j, t) => Some((&self.jobs[*j], Some(&self.jobs[*j].tasks[*t]))),
+        }
+    }
+
+    fn selected_ids(&self) -> Option<(String, Option<String>)> {
+        self.selection()
+            .map(|(job, task)| (job.id.clone(), task.map(|t| t.id.clone())))
+    }
+
+    fn speed(&self, task: &DownloadTask) -> f64 {
+        self.speeds.get(&task.id).map(|s| s.per_sec).unwrap_or(0.0)
+    }
+}
+
+async fn event_loop(terminal: &mut DefaultTerminal, ctx: &Context<'_>) -> anyhow::Result<()> {
+    let mut keys = spawn_input_thread();
+    let mut ticker = tokio::time::interval(REFRESH_INTERVAL);
+    let mut app = App::default();
+
+    loop {
+        terminal.draw(|frame| draw(frame, &app, ctx.logs))?;
+
+        let action = tokio::select! {
+            _ = ticker.tick() => {
+                match ctx.backend.get_jobs().await {
+                    Ok(jobs) => app.refresh(jobs),
+                    Err(e) => app.status = Some(format!("Lost connection to the daemon: {}", e)),
+                }
+                None
+            }
+            key = keys.recv() => match key {
+                Some(key) => handle_key(&mut app, key),
+                None => Some(Action::Quit),
+            },
+        };
+
+        let Some(action) = action else {
+            continue;
+        };
+        if matches!(action, Action::Quit) {
+            return Ok(());
+        }
+        if matches!(action, Action::Search(_) | Action::OpenSeason(_) | Action::Queue(..)) {
+            app.status = Some("Working...".to_string());
+            terminal.draw(|frame| draw(frame, &app, ctx.logs))?;
+        }
+        if let Err(e) = perform(&mut app, action, ctx).await {
+            tracing::warn!("{}", e);
+            app.status = Some(format!("Error: {}", e));
+        }
+        if let Ok(jobs) = ctx.backend.get_jobs().await {
+            app.refresh(jobs);
+        }
+    }
+}
+
+/// crossterm reads block, so keys are read on their own thread
+fn spawn_input_thread() -> mpsc::UnboundedReceiver<KeyEvent> {
+    let (tx, rx) = mpsc::unbounded_channel();
+    std::thread::spawn(move || loop {
+        match event::read() {
+            Ok(Event::Key(key)) if key.kind == KeyEventKind::Press => {
+                if tx.send(key).is_err() {
+                    break;
+                }
+            }
+            Ok(_) => {}
+            Err(_) => break,
+        }
+    });
+    rx
+}
+
+fn handle_key(app: &mut App, key: KeyEvent) -> Option<Action> {
+    if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+        return Some(Action::Quit);
+    }
+    if app.search.is_some() {
+        return handle_search_key(app, key);
+    }
+
+    let confirm_cancel = std::mem::take(&mut app.confirm_cancel);
+    app.status = None;
+    let rows = app.rows().len();
+    match key.code {
+        KeyCode::Char('q') | KeyCode::Esc => return Some(Action::Quit),
+        KeyCode::Up | KeyCode::Char('k') => app.selected = app.selected.saturating_sub(1),
+        KeyCode::Down | KeyCode::Char('j') => app.selected = (app.selected + 1).min(rows.saturating_sub(1)),
+        KeyCode::Home => app.selected = 0,
+        KeyCode::End => app.selected = rows.saturating_sub(1),
+        KeyCode::Enter | KeyCode::Char(' ') => {
+            if let Some(Row::Job(j)) = app.rows().get(app.selected) {
+                let id = app.jobs[*j].id.clone();
+                if !app.collapsed.remove(&id) {
+                    app.collapsed.insert(id);
+                }
+            }
+        }
+        KeyCode::Char('p') => return app.selected_ids().map(|(job, task)| Action::Pause(job, task)),
+        KeyCode::Char('r') => return app.selected_ids().map(|(job, task)| Action::Resume(job, task)),
+        KeyCode::Char('c') => {
+            let (job, task) = app.selected_ids()?;
+            if confirm_cancel {
+                return Some(Action::Cancel(job, task));
+            }
+            app.confirm_cancel = true;
+            app.status = Some("Press c again to cancel and delete the partial download".to_string());
+        }
+        KeyCode::Char('K') | KeyCode::Char('+') => return app.selected_ids().map(|(job, _)| Action::Move(job, -1)),
+        KeyCode::Char('J') | KeyCode::Char('-') => return app.selected_ids().map(|(job, _)| Action::Move(job, 1)),
+        KeyCode::Char('/') | KeyCode::Char('s') => app.search = Some(Search::Query(String::new())),
+        KeyCode::Char('l') => app.show_logs = !app.show_logs,
+        _ => {}
+    }
+    None
+}
+
+fn handle_search_key(app: &mut App, key: KeyEvent) -> Option<Action> {
+    let search = app.search.as_mut()?;
+    match search {
+        Search::Query(query) => match key.code {
+            KeyCode::Esc => app.search = None,
+            KeyCode::Enter if !query.trim().is_empty() => return Some(Action::Search(query.clone())),
+            KeyCode::Backspace => {
+                query.pop();
+            }
+            KeyCode::Char(c) => query.push(c),
+            _ => {}
+        },
+        Search::Results { results, selected } => match key.code {
+            KeyCode::Esc => app.search = Some(Search::Query(String::new())),
+            KeyCode::Up => *selected = selected.saturating_sub(1),
+            KeyCode::Down => *selected = (*selected + 1).min(results.len().saturating_sub(1)),
+            KeyCode::Enter => return results.get(*selected).cloned().map(Action::OpenSeason),
+            _ => {}
+        },
+        Search::Episodes { info, cover, input } => match key.code {
+            KeyCode::Esc => app.search = None,
+            KeyCode::Enter if !input.trim().is_empty() => {
+                return Some(Action::Queue(info.clone(), cover.clone(), input.clone()));
+            }
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Char(c) => input.push(c),
+            _ => {}
+        },
+    }
+    None
+}
+
+async fn perform(app: &mut App, action: Action, ctx: &Context<'_>) -> anyhow::Result<()> {
+    let backend = ctx.backend;
+    app.status = None;
+    match action {
+        Action::Quit => {}
+        Action::Pause(job, task) => backend.pause(&job, task.as_deref()).await?,
+        Action::Resume(job, task) => backend.resume(&job, task.as_deref()).await?,
+        Action::Cancel(job, task) => backend.cancel(&job, task.as_deref()).await?,
+        Action::Move(job, offset) => {
+            backend.move_job(&job, offset).await?;
+            // Keep the moved job selected
+            let jobs = backend.get_jobs().await?;
+            app.refresh(jobs);
+            if let Some(row) = app.rows().iter().position(|r| matches!(r, Row::Job(j) if app.jobs[*j].id == job)) {
+                app.selected = row;
+            }
+        }
+        Action::Search(query) => {
+            let results = backend.search(&SearchQuery::new(&query), ctx.refresh).await?;
+            if results.is_empty() {
+                app.status = Some(format!("No results for '{}'", query));
+            } else {
+                app.search = Some(Search::Results { results, selected: 0 });
+            }
+        }
+        Action::OpenSeason(result) => {
+            let info = backend.get_season(&result.url, ctx.refresh).await?;
+            app.search = Some(Search::Episodes {
+                info: Box::new(info),
+                cover: Some(result.image),
+                input: String::new(),
+            });
+        }
+        Action::Queue(info, cover, input) => {
+            let selected = parse_episode_range(&input);
+            let episodes: Vec<&Episode> = info.episodes.iter().filter(|ep| selected.contains(&ep.number)).collect();
+            if episodes.is_empty() {
+                app.status = Some(format!("No released episode matches '{}'", input));
+                return Ok(());
+            }
+
+            let settings = backend.get_settings().await?;
+            let fetched = fetch_episode_tasks(ctx.scraper, &info.url, &episodes, false, |ep| {
+                default_episode_path(&settings, &info, ep)
+            })
+            .await;
+            for (number, error) in &fetched.failed {
+                tracing::warn!("Ep {:02} of {}: {}", number, info.title, error);
+            }
+
+            let count = fetched.tasks.len();
+            if count > 0 {
+                for task in &fetched.tasks {
+                    if let Some(parent) = std::path::Path::new(&task.filename).parent() {
+                        std::fs::create_dir_all(parent)?;
+                    }
+                }
+                backend
+                    .queue(DownloadJob {
+                        id: info.url.clone(),
+                        name: info.title.clone(),
+                        tasks: fetched.tasks,
+                        anime_url: Some(info.url.clone()),
+                        cover_url: cover,
+                    })
+                    .await?;
+            }
+            app.search = None;
+            app.status = Some(format!(
+                "Queued {} episode(s) of {} ({} already downloaded, {} failed)",
+                count,
+                info.title,
+                fetched.skipped.len(),
+                fetched.failed.len()
+            ));
+        }
+    }
+    Ok(())
+}
+
+// ============ DRAWING ============
+
+fn draw(frame: &mut Frame, app: &App, logs: &LogBuffer) {
+    let log_height = if app.show_logs { 10 } else { 0 };
+    let [header, body, log_area, footer] = Layout::vertical([
+        Constraint::Length(1),
+        Constraint::Min(6),
+        Constraint::Length(log_height),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+    let [queue_area, detail_area] =
+        Layout::horizontal([Constraint::Percentage(62), Constraint::Percentage(38)]).areas(body);
+
+    let downloading: Vec<&DownloadTask> = app
+        .jobs
+        .iter()
+        .flat_map(|j| &j.tasks)
+        .filter(|t| t.status == TaskStatus::Downloading)
+        .collect();
+    let total_speed: f64 = downloading.iter().map(|t| app.speed(t)).sum();
+    let title = format!(
+        " Aura Download Manager - {} job(s), {} downloading, {}/s",
+        app.jobs.len(),
+        downloading.len(),
+        format_bytes(total_speed as u64)
+    );
+    frame.render_widget(Paragraph::new(title).style(Style::new().add_modifier(Modifier::BOLD)), header);
+
+    draw_queue(frame, app, queue_area);
+    draw_details(frame, app, detail_area);
+
+    if app.show_logs {
+        let lines: Vec<Line> = logs
+            .last(log_area.height.saturating_sub(2) as usize)
+            .into_iter()
+            .map(Line::from)
+            .collect();
+        frame.render_widget(Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(" Log ")), log_area);
+    }
+
+    let help = app.status.clone().unwrap_or_else(|| {
+        "↑↓ select  enter fold  p pause  r resume  c cancel  K/J move  / search  l log  q quit".to_string()
+    });
+    frame.render_widget(Paragraph::new(help).style(Style::new().fg(Color::DarkGray)), footer);
+
+    if let Some(search) = &app.search {
+        draw_search(frame, search);
+    }
+}
+
+fn draw_queue(frame: &mut Frame, app: &App, area: Rect) {
+    let items: Vec<ListItem> = app
+        .rows()
+        .iter()
+        .map(|row| match row {
+            Row::Job(j) => {
+                let job = &app.jobs[*j];
+                let done = job.tasks.iter().filter(|t| t.status == TaskStatus::Completed).count();
+                let fold = if app.collapsed.contains(&job.id) { "▸" } else { "▾" };
+                ListItem::new(Line::from(vec![
+                    Span::raw(format!("{} ", fold)),
+                    Span::styled(job.name.clone(), Style::new().add_modifier(Modifier::BOLD)),
+                    Span::raw(format!(" ({}/{})", done, job.tasks.len())),
+                ]))
+            }
+            Row::Task(j, t) => ListItem::new(task_line(app, &app.jobs[*j].tasks[*t])),
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::ALL).title(" Queue "))
+        .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+    let mut state = ListState::default().with_selected(Some(app.selected));
+    frame.render_stateful_widget(list, area, &mut state);
+
+    if app.jobs.is_empty() {
+        let hint = Paragraph::new("No jobs. Press / to search for a show.").style(Style::new().fg(Color::DarkGray));
+        frame.render_widget(hint, inner(area));
+    }
+}
+
+fn task_line(app: &App, task: &DownloadTask) -> Line<'static> {
+    let bytes = task_bytes(task);
+    let fraction = if task.status == TaskStatus::Completed {
+        1.0
+    } else if task.total_bytes > 0 {
+        bytes as f64 / task.total_bytes as f64
+    } else {
+        0.0
+    };
+    let speed = app.speed(task);
+    let rate = if task.status == TaskStatus::Downloading {
+        let eta = match task.total_bytes.checked_sub(bytes) {
+            Some(left) if speed > 0.0 => format_duration(left as f64 / speed),
+            _ => "--:--".to_string(),
+        };
+        format!("{:>9}/s ETA {}", format_bytes(speed as u64), eta)
+    } else {
+        String::new()
+    };
+    let name = match task.episode_number {
+        Some(n) => format!("Ep {:02}", n),
+        None => file_name(&task.filename),
+    };
+
+    Line::from(vec![
+        Span::raw(format!("   {} ", name)),
+        Span::styled(bar(fraction, BAR_WIDTH), Style::new().fg(status_color(&task.status))),
+        Span::raw(format!(" {:>3.0}% ", fraction * 100.0)),
+        Span::styled(format!("{:<11}", status_text(&task.status)), Style::new().fg(status_color(&task.status))),
+        Span::raw(rate),
+    ])
+}
+
+fn draw_details(frame: &mut Frame, app: &App, area: Rect) {
+    let block = Block::default().borders(Borders::ALL).title(" Details ");
+    let Some((job, task)) = app.selection() else {
+        frame.render_widget(block, area);
+        return;
+    };
+
+    let mut lines = vec![Line::styled(job.name.clone(), Style::new().add_modifier(Modifier::BOLD))];
+    match task {
+        None => {
+            let total: u64 = job.tasks.iter().map(|t| t.total_bytes).sum();
+            let done: u64 = job.tasks.iter().map(task_bytes).sum();
+            lines.push(Line::raw(format!("{} episode(s)", job.tasks.len())));
+            lines.push(Line::raw(format!("{} of {}", format_bytes(done), format_bytes(total))));
+            if let Some(url) = &job.anime_url {
+                lines.push(Line::raw(url.clone()));
+            }
+        }
+        Some(task) => {
+            lines.push(Line::raw(file_name(&task.filename)));
+            lines.push(Line::raw(format!("Status: {}", status_text(&task.status))));
+            if let TaskStatus::Error(e) = &task.status {
+                lines.push(Line::styled(e.clone(), Style::new().fg(Color::Red)));
+            }
+            lines.push(Line::raw(format!(
+                "{} of {}",
+                format_bytes(task_bytes(task)),
+                format_bytes(task.total_bytes)
+            )));
+            if !task.segments.is_empty() {
+                lines.push(Line::raw(""));
+                lines.push(Line::raw("Segments:"));
+                for segment in &task.segments {
+                    let size = segment.end.saturating_sub(segment.start) + 1;
+                    let (fraction, label, color) = match segment.status {
+                        SegmentStatus::Completed => (1.0, "done", Color::Green),
+                        SegmentStatus::Downloading => (0.5, "active", Color::Yellow),
+                        SegmentStatus::Error => (0.0, "error", Color::Red),
+                        SegmentStatus::Pending => (0.0, "pending", Color::DarkGray),
+                    };
+                    lines.push(Line::from(vec![
+                        Span::raw(format!(" #{:<2} ", segment.index)),
+                        Span::styled(bar(fraction, 10), Style::new().fg(color)),
+                        Span::raw(format!(" {:<8}{:>10}", label, format_bytes(size))),
+                    ]));
+                }
+            }
+        }
+    }
+
+    frame.render_widget(Paragraph::new(lines).block(block).wrap(Wrap { trim: false }), area);
+}
+
+fn draw_search(frame: &mut Frame, search: &Search) {
+    let area = centered(frame.area(), 70, 70);
+    frame.render_widget(Clear, area);
+
+    match search {
+        Search::Query(query) => {
+            let text = vec![
+                Line::raw(format!("> {}█", query)),
+                Line::raw(""),
+                Line::styled("enter search  esc close", Style::new().fg(Color::DarkGray)),
+            ];
+            let block = Block::default().borders(Borders::ALL).title(" Search ");
+            frame.render_widget(Paragraph::new(text).block(block), area);
+        }
+        Search::Results { results, selected } => {
+            let items: Vec<ListItem> = results.iter().map(|r| ListItem::new(r.title.clone())).collect();
+            let list = List::new(items)
+                .block(Block::default().borders(Borders::ALL).title(" Results (enter to open, esc back) "))
+                .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+            let mut state = ListState::default().with_selected(Some(*selected));
+            frame.render_stateful_widget(list, area, &mut state);
+        }
+        Search::Episodes { info, input, .. } => {
+            let mut text = vec![
+                Line::styled(info.title.clone(), Style::new().add_modifier(Modifier::BOLD)),
+                Line::raw(info.release_summary()),
+                Line::raw(""),
+                Line::raw(format!("Episodes (e.g. 1-4, 6): {}█", input)),
+                Line::styled("enter queue  esc close", Style::new().fg(Color::DarkGray)),
+                Line::raw(""),
+            ];
+            text.extend(info.episodes.iter().map(|ep| Line::raw(format!("Ep {:02}: {}", ep.number, ep.name))));
+            let block = Block::default().borders(Borders::ALL).title(" Add episodes ");
+            frame.render_widget(Paragraph::new(text).block(block).wrap(Wrap { trim: false }), area);
+        }
+    }
+}
+
+fn centered(area: Rect, percent_x: u16, percent_y: u16) -> Rect {
+    let [_, middle, _] = Layout::vertical([
+        Constraint::Percentage((100 - percent_y) / 2),
+        Constraint::Percentage(percent_y),
+        Constraint::Percentage((100 - percent_y) / 2),
+    ])
+    .areas(area);
+    let [_, center, _] = Layout::horizontal([
+        Constraint::Percentage((100 - percent_x) / 2),
+        Constraint::Percentage(percent_x),
+        Constraint::Percentage((100 - percent_x) / 2),
+    ])
+    .areas(middle);
+    center
+}
+
+fn inner(area: Rect) -> Rect {
+    Rect {
+        x: area.x + 2,
+        y: area.y + 1,
+        width: area.width.saturating_sub(4),
+        height: area.height.saturating_sub(2).min(1),
+    }
+}
+
+fn bar(fraction: f64, width: usize) -> String {
+    let filled = ((fraction.clamp(0.0, 1.0) * width as f64).round() as usize).min(width);
+    format!("{}{}", "█".repeat(filled), "░".repeat(width - filled))
+}
+
+fn status_text(status: &TaskStatus) -> String {
+    match status {
+        TaskStatus::Paused(PauseReason::LinkExpired) => "link expired".to_string(),
+        TaskStatus::Paused(PauseReason::NetworkError) => "network error".to_string(),
+        other => status_label(other).to_string(),
+    }
+}
+
+fn status_color(status: &TaskStatus) -> Color {
+    match status {
+        TaskStatus::Pending => Color::DarkGray,
+        TaskStatus::Downloading => Color::Cyan,
+        TaskStatus::Paused(_) => Color::Yellow,
+        TaskStatus::Completed => Color::Green,
+        TaskStatus::Error(_) => Color::Red,
+    }
+}
+
+fn file_name(path: &str) -> String {
+    std::path::Path::new(path)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string())
+}
+
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+fn format_duration(secs: f64) -> String {
+    let secs = secs.round() as u64;
+    if secs >= 3600 {
+        format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+    } else {
+        format!("{}:{:02}", secs / 60, secs % 60)
+    }
+}
@@ -1,4 +1,11 @@
-use aura_core::{AnimeInfo, DownloadManager, DownloadJob, DownloadTask, Episode, ListEntry, SearchResult, Settings, TaskStatus};
+use aura_core::metadata::jellyfin_episode_filename;
+use aura_core::playlist;
+use aura_core::scraper::absolute_url;
+use aura_core::watch_state;
+use aura_core::streaming;
+use aura_core::cache::DEFAULT_PAGE_LIMIT;
+use aura_core::daemon::DaemonClient;
+use aura_core::{AnimeInfo, DownloadManager, DownloadJob, DownloadTask, Episode, ImageCache, LibraryReport, ListEntry, ListStatus, ManagerEvent, MetadataWriter, Notification, Page, PlaylistFormat, NotificationCenter, Provider, NotificationSink, SearchQuery, SearchResult, Settings, SettingsError, ShowProgress, Subscription, SubscriptionManager, TaskStatus, WatchEntry, WatchStateStore, WatchlistEntry, WatchlistStore};
 use std::sync::Arc;
 use uuid::Uuid;
 
@@ -7,6 +14,55 @@ use uuid::Uuid;
 // ------------------------------------------------------------------
 pub struct AppState {
     manager: Arc<DownloadManager>,
+    subscriptions: Arc<SubscriptionManager>,
+    notifications: Arc<NotificationCenter>,
+    watch_state: Arc<WatchStateStore>,
+    watchlist: Arc<WatchlistStore>,
+    images: Arc<ImageCache>,
+    metadata: Arc<MetadataWriter>,
+    /// Set when an `aura-cli daemon` was running at startup; the queue, settings and
+    /// scraping then go through it instead of the in-process manager
+    daemon: Option<DaemonClient>,
+}
+
+/// Summary of a library metadata refresh for the frontend
+#[derive(serde::Serialize)]
+struct MetadataReport {
+    shows: usize,
+    episodes: usize,
+    failed: Vec<String>,
+}
+
+impl From<LibraryReport> for MetadataReport {
+    fn from(report: LibraryReport) -> Self {
+        Self {
+            shows: report.shows,
+            episodes: report.episodes,
+            failed: report
+                .failed
+                .into_iter()
+                .map(|(folder, error)| format!("{}: {}", folder.display(), error))
+                .collect(),
+        }
+    }
+}
+
+/// Shows every new notification as a native OS notification
+struct NativeNotificationSink {
+    app_handle: tauri::AppHandle,
+}
+
+impl NotificationSink for NativeNotificationSink {
+    fn deliver(&self, notification: &Notification) {
+        use tauri_plugin_notification::NotificationExt;
+        let _ = self
+            .app_handle
+            .notification()
+            .builder()
+            .title(&notification.title)
+            .body(&notification.body)
+            .show();
+    }
 }
 
 
@@ -21,19 +77,19 @@ pub struct AppState {
 
 async fn search_anime_impl(
     manager: &Arc<DownloadManager>,
-    query: &str,
+    query: &SearchQuery,
+    refresh: bool,
 ) -> Result<Vec<SearchResult>, String> {
-    let mut results = manager.get_scraper().search(query).await.map_err(|e| e.to_string())?;
+    let mut results = manager.get_cached_scraper().search_with(query, refresh).await.map_err(|e| e.to_string())?;
+    let provider = manager.get_scraper().provider();
     for r in &mut results {
-        if !r.image.starts_with("http") {
-             r.image = format!("https://animeheaven.me/{}", r.image);
-        }
+        r.image = absolute_url(&provider, &r.image);
     }
     Ok(results)
 }
 
-async fn get_season_data_impl(manager: &Arc<DownloadManager>, url: &str) -> Result<AnimeInfo, String> {
-    manager.get_scraper().get_season(url).await.map_err(|e| e.to_string())
+async fn get_season_data_impl(manager: &Arc<DownloadManager>, url: &str, refresh: bool) -> Result<AnimeInfo, String> {
+    manager.get_cached_scraper().get_season(url, refresh).await.map_err(|e| e.to_string())
 }
 
 async fn resolve_link_impl(manager: &Arc<DownloadManager>, episode: Episode) -> Result<String, String> {
@@ -44,38 +100,85 @@ async fn resolve_link_impl(manager: &Arc<DownloadManager>, episode: Episode) ->
         .map_err(|e| e.to_string())
 }
 
-async fn get_new_releases_impl(manager: &Arc<DownloadManager>) -> Result<Vec<ListEntry>, String> {
-    let mut results = manager.get_scraper().get_new().await.map_err(|e| e.to_string())?;
+async fn get_new_releases_impl(manager: &Arc<DownloadManager>, refresh: bool) -> Result<Vec<ListEntry>, String> {
+    let mut results = manager.get_cached_scraper().get_new(refresh).await.map_err(|e| e.to_string())?;
+    let provider = manager.get_scraper().provider();
     for r in &mut results {
-        if !r.image.starts_with("http") {
-             r.image = format!("https://animeheaven.me/{}", r.image);
-        }
+        r.image = absolute_url(&provider, &r.image);
     }
     Ok(results)
 }
 
-async fn get_popular_impl(manager: &Arc<DownloadManager>) -> Result<Vec<ListEntry>, String> {
-    let mut results = manager.get_scraper().get_popular().await.map_err(|e| e.to_string())?;
+async fn get_popular_impl(manager: &Arc<DownloadManager>, refresh: bool) -> Result<Vec<ListEntry>, String> {
+    let mut results = manager.get_cached_scraper().get_popular(refresh).await.map_err(|e| e.to_string())?;
+    let provider = manager.get_scraper().provider();
     for r in &mut results {
-        if !r.image.starts_with("http") {
-             r.image = format!("https://animeheaven.me/{}", r.image);
-        }
+        r.image = absolute_url(&provider, &r.image);
     }
     Ok(results)
 }
 
+/// Site-relative cover paths become absolute on the provider's site so the webview can load them
+fn absolute_images<T>(provider: &Provider, mut page: Page<T>, image: fn(&mut T) -> &mut String) -> Page<T> {
+    for item in &mut page.items {
+        let image = image(item);
+        *image = absolute_url(provider, image);
+    }
+    page
+}
+
+async fn search_anime_page_impl(
+    manager: &Arc<DownloadManager>,
+    query: &SearchQuery,
+    page: u32,
+    limit: usize,
+    refresh: bool,
+) -> Result<Page<SearchResult>, String> {
+    let cache = manager.get_cached_scraper();
+    let page = cache.search_page(query, page, limit, refresh).await.map_err(|e| e.to_string())?;
+    Ok(absolute_images(&manager.get_scraper().provider(), page, |r| &mut r.image))
+}
+
+async fn get_new_releases_page_impl(
+    manager: &Arc<DownloadManager>,
+    page: u32,
+    limit: usize,
+    refresh: bool,
+) -> Result<Page<ListEntry>, String> {
+    let page = manager.get_cached_scraper().get_new_page(page, limit, refresh).await.map_err(|e| e.to_string())?;
+    Ok(absolute_images(&manager.get_scraper().provider(), page, |r| &mut r.image))
+}
+
+async fn get_popular_page_impl(
+    manager: &Arc<DownloadManager>,
+    page: u32,
+    limit: usize,
+    refresh: bool,
+) -> Result<Page<ListEntry>, String> {
+    let page = manager.get_cached_scraper().get_popular_page(page, limit, refresh).await.map_err(|e| e.to_string())?;
+    Ok(absolute_images(&manager.get_scraper().provider(), page, |r| &mut r.image))
+}
+
 async fn start_download_impl(
     manager: &Arc<DownloadManager>,
     anime_title: String,
     episodes: Vec<Episode>,
+    anime_url: Option<String>,
+    cover_url: Option<String>,
+    daemon: Option<&DaemonClient>,
 ) -> Result<usize, String> {
     let job_id = Uuid::new_v4().to_string();
+    let jellyfin_naming = manager.get_settings().metadata.jellyfin_naming;
     let tasks: Vec<DownloadTask> = episodes
         .into_iter()
         .map(|ep| DownloadTask {
             id: Uuid::new_v4().to_string(),
             episode_number: Some(ep.number),
-            filename: format!("{} - Episode {}.mp4", anime_title, ep.number),
+            filename: if jellyfin_naming {
+                jellyfin_episode_filename(&anime_title, ep.number)
+            } else {
+                format!("{} - Episode {}.mp4", anime_title, ep.number)
+            },
             url: "pending".to_string(), 
             episode_url: Some(ep.url),
             gate_id: Some(ep.gate_id),
@@ -92,9 +195,16 @@ async fn start_download_impl(
             id: job_id.clone(),
             name: anime_title,
             tasks,
+            anime_url,
+            cover_url,
         };
-        manager.add_job(job);
-        manager.start_download(job_id).await.map_err(|e| e.to_string())?;
+        match daemon {
+            Some(client) => client.queue(&job).await.map_err(|e| e.to_string())?,
+            None => {
+                manager.add_job(job);
+                manager.start_download(job_id).await.map_err(|e| e.to_string())?;
+            }
+        }
     }
 
     Ok(count)
@@ -104,27 +214,121 @@ async fn get_downloads_impl(manager: &DownloadManager) -> Result<Vec<DownloadJob
     Ok(manager.get_jobs())
 }
 
+async fn subscribe_anime_impl(
+    manager: &Arc<DownloadManager>,
+    subscriptions: &SubscriptionManager,
+    url: &str,
+) -> Result<Subscription, String> {
+    subscriptions
+        .subscribe(&manager.get_scraper(), url, None)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+async fn get_next_episode_impl(
+    manager: &Arc<DownloadManager>,
+    watch_state: &WatchStateStore,
+    url: &str,
+) -> Result<Option<Episode>, String> {
+    let info = manager.get_cached_scraper().get_season(url, false).await.map_err(|e| e.to_string())?;
+    Ok(watch_state.next_episode(&info.url, &info.episodes))
+}
+
+/// `format` is "mal" for the MyAnimeList XML format, anything else writes JSON
+fn export_watchlist_impl(watchlist: &WatchlistStore, path: &str, format: &str) -> Result<(), String> {
+    let data = if format == "mal" {
+        watchlist.export_mal_xml()
+    } else {
+        watchlist.export_json().map_err(|e| e.to_string())?
+    };
+    std::fs::write(path, data).map_err(|e| e.to_string())
+}
+
+fn import_watchlist_impl(watchlist: &WatchlistStore, path: &str, format: &str) -> Result<usize, String> {
+    let data = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let result = if format == "mal" {
+        watchlist.import_mal_xml(&data)
+    } else {
+        watchlist.import_json(&data)
+    };
+    result.map_err(|e| e.to_string())
+}
+
+/// Serve `aura-img://localhost/<encoded source url>[?w=<width>]` from the image cache
+async fn serve_cover_impl(images: &ImageCache, path: &str, query: &str) -> tauri::http::Response<Vec<u8>> {
+    let src = percent_encoding::percent_decode_str(path.trim_start_matches('/'))
+        .decode_utf8_lossy()
+        .to_string();
+    let width = query
+        .split('&')
+        .find_map(|p| p.strip_prefix("w="))
+        .and_then(|w| w.parse::<u32>().ok());
+
+    let result = match width {
+        Some(w) => images.thumbnail(&src, w).await,
+        None => images.get(&src).await,
+    };
+
+    match result {
+        Ok(image) => tauri::http::Response::builder()
+            .header("Content-Type", image.content_type)
+            .header("Cache-Control", "max-age=604800")
+            .body(image.bytes)
+            .unwrap_or_default(),
+        Err(e) => tauri::http::Response::builder()
+            .status(404)
+            .body(e.to_string().into_bytes())
+            .unwrap_or_default(),
+    }
+}
+
+async fn check_subscriptions_impl(
+    manager: &Arc<DownloadManager>,
+    subscriptions: &SubscriptionManager,
+) -> Result<usize, String> {
+    Ok(subscriptions.check_all(manager).await.len())
+}
+
 
 // ------------------------------------------------------------------
 // 3. Tauri Commands
 // ------------------------------------------------------------------
 
-/// Search for anime by query
+/// Search for anime by query, optionally filtered by year, tags, type and episode count
+/// (`refresh` bypasses the cache)
 #[tauri::command]
 async fn search_anime(
     state: tauri::State<'_, AppState>,
     query: String,
+    filters: Option<SearchQuery>,
+    refresh: Option<bool>,
 ) -> Result<Vec<SearchResult>, String> {
-    search_anime_impl(&state.manager, &query).await
+    let query = SearchQuery {
+        text: query,
+        ..filters.unwrap_or_default()
+    };
+    if let Some(client) = &state.daemon {
+        let mut results = client.search_with(&query, refresh.unwrap_or(false)).await.map_err(|e| e.to_string())?;
+        let provider = state.manager.get_scraper().provider();
+        for r in &mut results {
+            r.image = absolute_url(&provider, &r.image);
+        }
+        return Ok(results);
+    }
+    search_anime_impl(&state.manager, &query, refresh.unwrap_or(false)).await
 }
 
-/// Fetch season details and episode list
+/// Fetch season details and episode list (`refresh` bypasses the cache)
 #[tauri::command]
 async fn get_season_data(
     state: tauri::State<'_, AppState>,
     url: String,
+    refresh: Option<bool>,
 ) -> Result<AnimeInfo, String> {
-    get_season_data_impl(&state.manager, &url).await
+    if let Some(client) = &state.daemon {
+        return client.get_season(&url, refresh.unwrap_or(false)).await.map_err(|e| e.to_string());
+    }
+    get_season_data_impl(&state.manager, &url, refresh.unwrap_or(false)).await
 }
 
 /// Resolve the direct MP4 download link
@@ -138,14 +342,145 @@ async fn resolve_link(
 
 /// Fetch 'New Releases' list
 #[tauri::command]
-async fn get_new_releases(state: tauri::State<'_, AppState>) -> Result<Vec<ListEntry>, String> {
-    get_new_releases_impl(&state.manager).await
+async fn get_new_releases(state: tauri::State<'_, AppState>, refresh: Option<bool>) -> Result<Vec<ListEntry>, String> {
+    if let Some(client) = &state.daemon {
+        let mut results = client.get_new(refresh.unwrap_or(false)).await.map_err(|e| e.to_string())?;
+        let provider = state.manager.get_scraper().provider();
+        for r in &mut results {
+            r.image = absolute_url(&provider, &r.image);
+        }
+        return Ok(results);
+    }
+    get_new_releases_impl(&state.manager, refresh.unwrap_or(false)).await
 }
 
 /// Fetch 'Popular Today' list
 #[tauri::command]
-async fn get_popular(state: tauri::State<'_, AppState>) -> Result<Vec<ListEntry>, String> {
-    get_popular_impl(&state.manager).await
+async fn get_popular(state: tauri::State<'_, AppState>, refresh: Option<bool>) -> Result<Vec<ListEntry>, String> {
+    if let Some(client) = &state.daemon {
+        let mut results = client.get_popular(refresh.unwrap_or(false)).await.map_err(|e| e.to_string())?;
+        let provider = state.manager.get_scraper().provider();
+        for r in &mut results {
+            r.image = absolute_url(&provider, &r.image);
+        }
+        return Ok(results);
+    }
+    get_popular_impl(&state.manager, refresh.unwrap_or(false)).await
+}
+
+/// One page of search results for infinite scroll; `page` counts from 1 and `limit` defaults
+/// to 30. Later pages are read from the site as they are requested.
+#[tauri::command]
+async fn search_anime_page(
+    state: tauri::State<'_, AppState>,
+    query: String,
+    filters: Option<SearchQuery>,
+    page: Option<u32>,
+    limit: Option<usize>,
+    refresh: Option<bool>,
+) -> Result<Page<SearchResult>, String> {
+    let query = SearchQuery {
+        text: query,
+        ..filters.unwrap_or_default()
+    };
+    let (page, limit, refresh) = (page.unwrap_or(1), limit.unwrap_or(DEFAULT_PAGE_LIMIT), refresh.unwrap_or(false));
+    if let Some(client) = &state.daemon {
+        let page = client.search_page(&query, page, limit, refresh).await.map_err(|e| e.to_string())?;
+        return Ok(absolute_images(&state.manager.get_scraper().provider(), page, |r| &mut r.image));
+    }
+    search_anime_page_impl(&state.manager, &query, page, limit, refresh).await
+}
+
+/// One page of 'New Releases' for infinite scroll
+#[tauri::command]
+async fn get_new_releases_page(
+    state: tauri::State<'_, AppState>,
+    page: Option<u32>,
+    limit: Option<usize>,
+    refresh: Option<bool>,
+) -> Result<Page<ListEntry>, String> {
+    let (page, limit, refresh) = (page.unwrap_or(1), limit.unwrap_or(DEFAULT_PAGE_LIMIT), refresh.unwrap_or(false));
+    if let Some(client) = &state.daemon {
+        let page = client.get_new_page(page, limit, refresh).await.map_err(|e| e.to_string())?;
+        return Ok(absolute_images(&state.manager.get_scraper().provider(), page, |r| &mut r.image));
+    }
+    get_new_releases_page_impl(&state.manager, page, limit, refresh).await
+}
+
+/// One page of 'Popular Today' for infinite scroll, ranked across pages
+#[tauri::command]
+async fn get_popular_page(
+    state: tauri::State<'_, AppState>,
+    page: Option<u32>,
+    limit: Option<usize>,
+    refresh: Option<bool>,
+) -> Result<Page<ListEntry>, String> {
+    let (page, limit, refresh) = (page.unwrap_or(1), limit.unwrap_or(DEFAULT_PAGE_LIMIT), refresh.unwrap_or(false));
+    if let Some(client) = &state.daemon {
+        let page = client.get_popular_page(page, limit, refresh).await.map_err(|e| e.to_string())?;
+        return Ok(absolute_images(&state.manager.get_scraper().provider(), page, |r| &mut r.image));
+    }
+    get_popular_page_impl(&state.manager, page, limit, refresh).await
+}
+
+/// Drop all cached scrape results
+#[tauri::command]
+async fn clear_scrape_cache(state: tauri::State<'_, AppState>) -> Result<(), String> {
+    state.manager.get_cached_scraper().clear();
+    Ok(())
+}
+
+/// Size of the cover image cache in bytes
+#[tauri::command]
+async fn get_image_cache_size(state: tauri::State<'_, AppState>) -> Result<u64, String> {
+    Ok(state.images.disk_usage())
+}
+
+/// Write playlists of a job's completed episodes into its folder. Returns the written paths.
+#[tauri::command]
+async fn export_playlist(
+    state: tauri::State<'_, AppState>,
+    job_id: String,
+    format: Option<String>,
+) -> Result<Vec<String>, String> {
+    let format = format
+        .map(|f| f.parse::<PlaylistFormat>())
+        .transpose()
+        .map_err(|e| e.to_string())?;
+    if let Some(client) = &state.daemon {
+        return client
+            .export_playlist(&job_id, format)
+            .await
+            .map(|paths| paths.iter().map(|p| p.to_string_lossy().to_string()).collect())
+            .map_err(|e| e.to_string());
+    }
+    let manager = &state.manager;
+    let job = manager
+        .get_jobs()
+        .into_iter()
+        .find(|j| j.id == job_id)
+        .ok_or_else(|| format!("Unknown download job {}", job_id))?;
+    let formats = format.map_or(PlaylistFormat::ALL.to_vec(), |f| vec![f]);
+    playlist::export_folder(manager, &manager.job_folder(&job), &formats)
+        .await
+        .map(|paths| paths.iter().map(|p| p.to_string_lossy().to_string()).collect())
+        .map_err(|e| e.to_string())
+}
+
+/// Rewrite NFO and poster files for every show in the download directory
+#[tauri::command]
+async fn regenerate_metadata(state: tauri::State<'_, AppState>) -> Result<MetadataReport, String> {
+    state
+        .metadata
+        .regenerate_library(&state.manager)
+        .await
+        .map(MetadataReport::from)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn clear_image_cache(state: tauri::State<'_, AppState>) -> Result<(), String> {
+    state.images.clear().map_err(|e| e.to_string())
 }
 
 #[tauri::command]
@@ -153,23 +488,298 @@ async fn start_download(
     state: tauri::State<'_, AppState>,
     anime_title: String,
     episodes: Vec<Episode>,
+    anime_url: Option<String>,
+    cover_url: Option<String>,
 ) -> Result<usize, String> {
-    start_download_impl(&state.manager, anime_title, episodes).await
+    start_download_impl(&state.manager, anime_title, episodes, anime_url, cover_url, state.daemon.as_ref()).await
 }
 
 #[tauri::command]
 async fn get_downloads(state: tauri::State<'_, AppState>) -> Result<Vec<DownloadJob>, String> {
+    if let Some(client) = &state.daemon {
+        return client.get_jobs().await.map_err(|e| e.to_string());
+    }
     get_downloads_impl(&state.manager).await
 }
 
 #[tauri::command]
 async fn get_settings(state: tauri::State<'_, AppState>) -> Result<Settings, String> {
+    if let Some(client) = &state.daemon {
+        return client.get_settings().await.map_err(|e| e.to_string());
+    }
     Ok(state.manager.get_settings())
 }
 
+/// Invalid settings that were replaced by their defaults at startup
+#[tauri::command]
+async fn get_settings_errors(state: tauri::State<'_, AppState>) -> Result<Vec<String>, String> {
+    if let Some(client) = &state.daemon {
+        return client.get_settings_errors().await.map_err(|e| e.to_string());
+    }
+    Ok(state.manager.settings_errors())
+}
+
+/// Tell the Settings view to refetch when settings.toml is edited by hand or rejected
+async fn forward_settings_events(app_handle: tauri::AppHandle, mut events: tokio::sync::broadcast::Receiver<ManagerEvent>) {
+    use tauri::Emitter;
+    use tokio::sync::broadcast::error::RecvError;
+
+    loop {
+        match events.recv().await {
+            Ok(ManagerEvent::SettingsChanged { keys, from_disk: true }) => {
+                let _ = app_handle.emit("settings-changed", keys);
+            }
+            Ok(ManagerEvent::SettingsRejected { error }) => {
+                let _ = app_handle.emit("settings-rejected", error);
+            }
+            Ok(_) | Err(RecvError::Lagged(_)) => {}
+            Err(RecvError::Closed) => break,
+        }
+    }
+}
+
+/// Why settings could not be saved; `field` names the invalid setting, if any
+#[derive(serde::Serialize)]
+struct SettingsSaveError {
+    field: Option<String>,
+    message: String,
+}
+
+impl From<SettingsError> for SettingsSaveError {
+    fn from(e: SettingsError) -> Self {
+        Self { field: Some(e.field()), message: e.to_string() }
+    }
+}
+
+#[tauri::command]
+async fn update_settings(state: tauri::State<'_, AppState>, settings: Settings) -> Result<(), SettingsSaveError> {
+    settings.validate()?;
+    let result = match &state.daemon {
+        Some(client) => client.update_settings(&settings).await,
+        None => state.manager.update_settings(settings),
+    };
+    result.map_err(|e| SettingsSaveError {
+        field: e.downcast_ref::<SettingsError>().map(|e| e.field()),
+        message: e.to_string(),
+    })
+}
+
+/// List followed shows
+#[tauri::command]
+async fn get_subscriptions(state: tauri::State<'_, AppState>) -> Result<Vec<Subscription>, String> {
+    if let Some(client) = &state.daemon {
+        return client.get_subscriptions().await.map_err(|e| e.to_string());
+    }
+    Ok(state.subscriptions.list())
+}
+
+/// Follow a show; new episodes are queued automatically
+#[tauri::command]
+async fn subscribe_anime(
+    state: tauri::State<'_, AppState>,
+    url: String,
+) -> Result<Subscription, String> {
+    if let Some(client) = &state.daemon {
+        return client.subscribe(&url, None).await.map_err(|e| e.to_string());
+    }
+    subscribe_anime_impl(&state.manager, &state.subscriptions, &url).await
+}
+
+/// Stop following a show
+#[tauri::command]
+async fn unsubscribe_anime(state: tauri::State<'_, AppState>, url: String) -> Result<bool, String> {
+    if let Some(client) = &state.daemon {
+        return client.unsubscribe(&url).await.map_err(|e| e.to_string());
+    }
+    state.subscriptions.unsubscribe(&url).map_err(|e| e.to_string())
+}
+
+/// Check followed shows now, returns the number of queued episodes
+#[tauri::command]
+async fn check_subscriptions(state: tauri::State<'_, AppState>) -> Result<usize, String> {
+    if let Some(client) = &state.daemon {
+        return client.check_subscriptions().await.map(|queued| queued.len()).map_err(|e| e.to_string());
+    }
+    check_subscriptions_impl(&state.manager, &state.subscriptions).await
+}
+
+/// In-app notification feed, newest first
+#[tauri::command]
+async fn get_notifications(state: tauri::State<'_, AppState>) -> Result<Vec<Notification>, String> {
+    Ok(state.notifications.list())
+}
+
+#[tauri::command]
+async fn mark_notification_read(state: tauri::State<'_, AppState>, id: u64) -> Result<bool, String> {
+    state.notifications.mark_read(id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn mark_all_notifications_read(state: tauri::State<'_, AppState>) -> Result<(), String> {
+    state.notifications.mark_all_read().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn clear_notifications(state: tauri::State<'_, AppState>) -> Result<(), String> {
+    state.notifications.clear().map_err(|e| e.to_string())
+}
+
+/// Watch state of every tracked episode of a show. With a daemon running, the watch state
+/// and the watchlist are read and written through it so only one process owns the files.
+#[tauri::command]
+async fn get_watch_state(state: tauri::State<'_, AppState>, url: String) -> Result<Vec<WatchEntry>, String> {
+    if let Some(client) = &state.daemon {
+        return client.watch_state(&url).await.map_err(|e| e.to_string());
+    }
+    Ok(state.watch_state.episodes(&url))
+}
+
+#[tauri::command]
+async fn mark_episode_watched(
+    state: tauri::State<'_, AppState>,
+    url: String,
+    anime_title: String,
+    episode_number: u32,
+    watched: bool,
+) -> Result<WatchEntry, String> {
+    if let Some(client) = &state.daemon {
+        return client
+            .mark_watched(&url, &anime_title, episode_number, watched)
+            .await
+            .map_err(|e| e.to_string());
+    }
+    state
+        .watch_state
+        .mark_watched(&url, &anime_title, episode_number, watched)
+        .map_err(|e| e.to_string())
+}
+
+/// Save the playback position of an episode
+#[tauri::command]
+async fn set_watch_position(
+    state: tauri::State<'_, AppState>,
+    url: String,
+    anime_title: String,
+    episode_number: u32,
+    position_secs: u64,
+    duration_secs: Option<u64>,
+) -> Result<WatchEntry, String> {
+    if let Some(client) = &state.daemon {
+        return client
+            .set_watch_position(&url, &anime_title, episode_number, position_secs, duration_secs)
+            .await
+            .map_err(|e| e.to_string());
+    }
+    state
+        .watch_state
+        .set_position(&url, &anime_title, episode_number, position_secs, duration_secs)
+        .map_err(|e| e.to_string())
+}
+
+/// Next episode to watch for a show, `None` when everything released is watched
 #[tauri::command]
-async fn update_settings(state: tauri::State<'_, AppState>, settings: Settings) -> Result<(), String> {
-    state.manager.update_settings(settings).map_err(|e| e.to_string())
+async fn get_next_episode(state: tauri::State<'_, AppState>, url: String) -> Result<Option<Episode>, String> {
+    if let Some(client) = &state.daemon {
+        let info = client.get_season(&url, false).await.map_err(|e| e.to_string())?;
+        let entries = client.watch_state(&info.url).await.map_err(|e| e.to_string())?;
+        return Ok(watch_state::next_episode(&entries, &info.episodes));
+    }
+    get_next_episode_impl(&state.manager, &state.watch_state, &url).await
+}
+
+/// Shows with watch activity, most recent first
+#[tauri::command]
+async fn get_continue_watching(state: tauri::State<'_, AppState>) -> Result<Vec<ShowProgress>, String> {
+    if let Some(client) = &state.daemon {
+        return client.in_progress_shows().await.map_err(|e| e.to_string());
+    }
+    Ok(state.watch_state.in_progress_shows())
+}
+
+/// Personal anime list, optionally filtered by status
+#[tauri::command]
+async fn get_watchlist(
+    state: tauri::State<'_, AppState>,
+    status: Option<ListStatus>,
+) -> Result<Vec<WatchlistEntry>, String> {
+    if let Some(client) = &state.daemon {
+        return client.get_watchlist(status).await.map_err(|e| e.to_string());
+    }
+    Ok(state.watchlist.list(status))
+}
+
+#[tauri::command]
+async fn get_watchlist_entry(state: tauri::State<'_, AppState>, url: String) -> Result<Option<WatchlistEntry>, String> {
+    if let Some(client) = &state.daemon {
+        return client.get_watchlist_entry(&url).await.map_err(|e| e.to_string());
+    }
+    Ok(state.watchlist.get(&url))
+}
+
+/// Add a show to the list or change its status
+#[tauri::command]
+async fn set_watchlist_status(
+    state: tauri::State<'_, AppState>,
+    url: String,
+    title: String,
+    status: ListStatus,
+) -> Result<WatchlistEntry, String> {
+    if let Some(client) = &state.daemon {
+        return client.set_watchlist_status(&url, &title, status).await.map_err(|e| e.to_string());
+    }
+    state.watchlist.set_status(&url, &title, status).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn set_watchlist_score(
+    state: tauri::State<'_, AppState>,
+    url: String,
+    score: Option<u8>,
+) -> Result<WatchlistEntry, String> {
+    if let Some(client) = &state.daemon {
+        return client.set_watchlist_score(&url, score).await.map_err(|e| e.to_string());
+    }
+    state.watchlist.set_score(&url, score).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn set_watchlist_notes(
+    state: tauri::State<'_, AppState>,
+    url: String,
+    notes: String,
+) -> Result<WatchlistEntry, String> {
+    if let Some(client) = &state.daemon {
+        return client.set_watchlist_notes(&url, &notes).await.map_err(|e| e.to_string());
+    }
+    state.watchlist.set_notes(&url, &notes).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn remove_from_watchlist(state: tauri::State<'_, AppState>, url: String) -> Result<bool, String> {
+    if let Some(client) = &state.daemon {
+        return client.remove_from_watchlist(&url).await.map_err(|e| e.to_string());
+    }
+    state.watchlist.remove(&url).map_err(|e| e.to_string())
+}
+
+/// Export the list to a file as "json" or "mal" (MyAnimeList XML)
+#[tauri::command]
+async fn export_watchlist(state: tauri::State<'_, AppState>, path: String, format: String) -> Result<(), String> {
+    if let Some(client) = &state.daemon {
+        let data = client.export_watchlist(format == "mal").await.map_err(|e| e.to_string())?;
+        return std::fs::write(&path, data).map_err(|e| e.to_string());
+    }
+    export_watchlist_impl(&state.watchlist, &path, &format)
+}
+
+/// Import a "json" or "mal" (MyAnimeList XML) list file, returns the number of entries
+#[tauri::command]
+async fn import_watchlist(state: tauri::State<'_, AppState>, path: String, format: String) -> Result<usize, String> {
+    if let Some(client) = &state.daemon {
+        let data = std::fs::read_to_string(&path).map_err(|e| e.to_string())?;
+        return client.import_watchlist(&data, format == "mal").await.map_err(|e| e.to_string());
+    }
+    import_watchlist_impl(&state.watchlist, &path, &format)
 }
 
 #[tauri::command]
@@ -190,6 +800,20 @@ pub fn run() {
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_os::init())
+        .plugin(tauri_plugin_notification::init())
+        .register_asynchronous_uri_scheme_protocol("aura-img", |ctx, request, responder| {
+            use tauri::Manager;
+            let Some(state) = ctx.app_handle().try_state::<AppState>() else {
+                responder.respond(tauri::http::Response::builder().status(503).body(Vec::new()).unwrap_or_default());
+                return;
+            };
+            let images = state.images.clone();
+            let path = request.uri().path().to_string();
+            let query = request.uri().query().unwrap_or("").to_string();
+            tauri::async_runtime::spawn(async move {
+                responder.respond(serve_cover_impl(&images, &path, &query).await);
+            });
+        })
         .setup(|app| {
             use tauri::Manager;
             
@@ -208,7 +832,66 @@ pub fn run() {
                 Err(e) => return Err(Box::new(std::io::Error::new(std::io::ErrorKind::Other, format!("Failed to initialize manager: {}", e)))),
             };
 
-            app.manage(AppState { manager: Arc::new(manager) });
+            let manager = Arc::new(manager);
+            let subscriptions = match SubscriptionManager::new(&app_config_dir) {
+                Ok(s) => Arc::new(s),
+                Err(e) => return Err(Box::new(std::io::Error::new(std::io::ErrorKind::Other, format!("Failed to load subscriptions: {}", e)))),
+            };
+
+            let notifications = match NotificationCenter::new(&app_config_dir) {
+                Ok(n) => Arc::new(n),
+                Err(e) => return Err(Box::new(std::io::Error::new(std::io::ErrorKind::Other, format!("Failed to load notifications: {}", e)))),
+            };
+            notifications.add_sink(Box::new(NativeNotificationSink { app_handle: app.handle().clone() }));
+
+            // A running daemon owns the queue and the subscription poller; the app becomes a client
+            // and raises notifications for the daemon's events instead of its own idle manager's
+            let daemon = tauri::async_runtime::block_on(DaemonClient::discover(&app_config_dir));
+            if let Some(client) = &daemon {
+                println!("[Aura] Using daemon at {}", client.address());
+                let client = client.clone();
+                let notifications = notifications.clone();
+                tauri::async_runtime::spawn(async move {
+                    match client.events().await {
+                        Ok(events) => notifications.run_remote(events).await,
+                        Err(e) => println!("[Aura] Daemon event stream unavailable: {}", e),
+                    }
+                });
+            } else {
+                tauri::async_runtime::spawn(notifications.clone().run(manager.subscribe_events()));
+                tauri::async_runtime::spawn(subscriptions.clone().run_poller(manager.clone()));
+                tauri::async_runtime::spawn(manager.clone().watch_settings());
+                tauri::async_runtime::spawn(forward_settings_events(app.handle().clone(), manager.subscribe_events()));
+            }
+
+            let watch_state = match WatchStateStore::new(&app_config_dir) {
+                Ok(w) => Arc::new(w),
+                Err(e) => return Err(Box::new(std::io::Error::new(std::io::ErrorKind::Other, format!("Failed to load watch state: {}", e)))),
+            };
+            let watchlist = match WatchlistStore::new(&app_config_dir) {
+                Ok(w) => Arc::new(w),
+                Err(e) => return Err(Box::new(std::io::Error::new(std::io::ErrorKind::Other, format!("Failed to load watchlist: {}", e)))),
+            };
+            let images = match ImageCache::new(&app_config_dir, manager.http_clients()) {
+                Ok(i) => Arc::new(i),
+                Err(e) => return Err(Box::new(std::io::Error::new(std::io::ErrorKind::Other, format!("Failed to create image cache: {}", e)))),
+            };
+            let metadata = Arc::new(MetadataWriter::new(images.clone()));
+            if daemon.is_none() {
+                tauri::async_runtime::spawn(metadata.clone().run(manager.clone()));
+            }
+
+            // LAN streaming server; changes to these settings apply on the next start
+            if daemon.is_none() && manager.get_settings().streaming.enabled {
+                let manager = manager.clone();
+                tauri::async_runtime::spawn(async move {
+                    if let Err(e) = streaming::serve(manager).await {
+                        println!("[Aura] Streaming server stopped: {}", e);
+                    }
+                });
+            }
+
+            app.manage(AppState { manager, subscriptions, notifications, watch_state, watchlist, images, metadata, daemon });
             
             println!("[Aura] Core initialized with DownloadManager.");
             Ok(())
@@ -219,10 +902,40 @@ pub fn run() {
             resolve_link,
             get_new_releases,
             get_popular,
+            search_anime_page,
+            get_new_releases_page,
+            get_popular_page,
             start_download,
             get_downloads,
             get_settings,
+            get_settings_errors,
             update_settings,
+            get_subscriptions,
+            subscribe_anime,
+            unsubscribe_anime,
+            check_subscriptions,
+            get_notifications,
+            mark_notification_read,
+            mark_all_notifications_read,
+            clear_notifications,
+            get_watch_state,
+            mark_episode_watched,
+            set_watch_position,
+            get_next_episode,
+            get_continue_watching,
+            get_watchlist,
+            get_watchlist_entry,
+            set_watchlist_status,
+            set_watchlist_score,
+            set_watchlist_notes,
+            remove_from_watchlist,
+            export_watchlist,
+            import_watchlist,
+            clear_scrape_cache,
+            get_image_cache_size,
+            clear_image_cache,
+            regenerate_metadata,
+            export_playlist,
             greet
         ])
         .build(tauri::generate_context!())
@@ -250,7 +963,7 @@ mod tests {
         let query = "Naruto";
 
         // This is an integration test hitting the real API
-        let result = search_anime_impl(&manager, query).await;
+        let result = search_anime_impl(&manager, &SearchQuery::new(query), true).await;
 
         match result {
             Ok(results) => {
@@ -268,7 +981,7 @@ mod tests {
     #[tokio::test]
     async fn test_get_new_releases() {
         let manager = Arc::new(get_manager());
-        let result = get_new_releases_impl(&manager).await;
+        let result = get_new_releases_impl(&manager, true).await;
 
         match result {
             Ok(list) => {
@@ -1,8 +1,8 @@
 import React, { useState, useEffect } from 'react';
 import { useQuery, useMutation, useQueryClient } from '@tanstack/react-query';
 import AuraLoader from '../AuraLoader';
-import { getSeason, startDownload } from '../../lib/api/tauri';
-import { parseEpisodeRange } from '../../lib/utils';
+import { getSeason, startDownload, getSubscriptions, subscribeAnime, unsubscribeAnime, getWatchState, markEpisodeWatched } from '../../lib/api/tauri';
+import { coverSrc, parseEpisodeRange } from '../../lib/utils';
 import { AnimeSearchResult, Episode } from '../../lib/api/types';
 
 interface DetailsViewProps {
@@ -35,7 +35,7 @@ const DetailsView: React.FC<DetailsViewProps> = ({
 
   const { mutate: addDownloads, isPending } = useMutation({
     mutationFn: (variables: { anime_title: string; episodes: Episode[] }) =>
-      startDownload(variables.anime_title, variables.episodes),
+      startDownload(variables.anime_title, variables.episodes, anime?.url, anime?.image),
     onSuccess: (count) => {
       showToast(`Added ${count} tasks`, 'success');
       queryClient.invalidateQueries({ queryKey: ['downloads'] });
@@ -46,6 +46,37 @@ const DetailsView: React.FC<DetailsViewProps> = ({
     },
   });
 
+  const { data: subscriptions } = useQuery({
+    queryKey: ['subscriptions'],
+    queryFn: getSubscriptions,
+  });
+  const isFollowing = !!season && !!subscriptions?.some((s) => s.url === season.url);
+
+  const { mutate: toggleFollow, isPending: isFollowPending } = useMutation({
+    mutationFn: (url: string) =>
+      isFollowing ? unsubscribeAnime(url).then(() => null) : subscribeAnime(url),
+    onSuccess: (sub) => {
+      showToast(sub ? `Following ${sub.title}` : 'Unfollowed', 'success');
+      queryClient.invalidateQueries({ queryKey: ['subscriptions'] });
+    },
+    onError: (err) => {
+      showToast(err.message, 'error');
+    },
+  });
+
+  const { data: watchState } = useQuery({
+    queryKey: ['watch-state', season?.url],
+    queryFn: () => getWatchState(season!.url),
+    enabled: !!season?.url,
+  });
+
+  const toggleWatched = async (epNum: number) => {
+    if (!season) return;
+    const watched = !!watchState?.find((w) => w.episode_number === epNum)?.watched;
+    await markEpisodeWatched(season.url, season.title, epNum, !watched);
+    queryClient.invalidateQueries({ queryKey: ['watch-state', season.url] });
+  };
+
   useEffect(() => {
     setSelectedEpisodes([]);
     setRangeInput('');
@@ -95,7 +126,7 @@ const DetailsView: React.FC<DetailsViewProps> = ({
   return (
     <div id="view-details" className="view-container active">
       <div className="details-header">
-        <img src={anime.image} className="details-poster" alt={anime.title} />
+        <img src={coverSrc(anime.image)} className="details-poster" alt={anime.title} />
         <div
           className="details-content"
           style={{ display: 'flex', flexDirection: 'column', flex: 1 }}
@@ -111,12 +142,28 @@ const DetailsView: React.FC<DetailsViewProps> = ({
               )}
               <div style={{ display: 'flex', gap: '10px', alignItems: 'center', flexWrap: 'wrap' }}>
                 {season.year && <span className="badge" style={{ fontSize: '12px' }}>{season.year}</span>}
+                {season.status && (
+                  <span className="badge" style={{ fontSize: '12px' }}>
+                    {season.status === 'airing' ? 'Airing' : 'Finished'}
+                  </span>
+                )}
+                {season.score != null && <span className="badge" style={{ fontSize: '12px' }}>★ {season.score}</span>}
                 {season.tags && season.tags.map(tag => (
                   <span key={tag} className="badge" style={{ fontSize: '11px', background: 'rgba(102, 252, 241, 0.1)', color: 'var(--primary)' }}>
                     {tag}
                   </span>
                 ))}
               </div>
+              <div style={{ fontSize: '13px', color: 'var(--text-muted)' }}>
+                {season.total_episodes != null
+                  ? `${season.episodes.length} of ${season.total_episodes} episodes released`
+                  : `${season.episodes.length} episodes released`}
+              </div>
+              {season.related.length > 0 && (
+                <div style={{ fontSize: '13px', color: 'var(--text-muted)' }}>
+                  Related: {season.related.map(r => r.title).join(', ')}
+                </div>
+              )}
             </div>
           )}
 
@@ -129,6 +176,15 @@ const DetailsView: React.FC<DetailsViewProps> = ({
               onChange={handleRangeInputChange}
             />
             <div style={{ flex: 1 }}></div>
+            {season && (
+              <button
+                className="btn"
+                onClick={() => toggleFollow(season.url)}
+                disabled={isFollowPending}
+              >
+                {isFollowing ? 'Following' : 'Follow'}
+              </button>
+            )}
             <button
               className="btn btn-primary"
               onClick={handleAddDownloads}
@@ -144,8 +200,10 @@ const DetailsView: React.FC<DetailsViewProps> = ({
       {error && <div className="error">{error.message}</div>}
       {season && (
         <div className="episodes-grid">
-          {season.episodes.map((ep) => (
-            <div key={ep.number}>
+          {season.episodes.map((ep) => {
+            const watch = watchState?.find((w) => w.episode_number === ep.number);
+            return (
+            <div key={ep.number} style={{ position: 'relative' }}>
               <input
                 type="checkbox"
                 id={`e-${ep.number}`}
@@ -173,8 +231,27 @@ const DetailsView: React.FC<DetailsViewProps> = ({
                   return <span className="ep-num">{ep.name}</span>;
                 })()}
               </label>
+              <button
+                className="ep-watch-marker"
+                title={watch?.watched ? 'Watched (click to unmark)' : 'Mark as watched'}
+                onClick={() => toggleWatched(ep.number)}
+                style={{
+                  position: 'absolute',
+                  top: '4px',
+                  right: '4px',
+                  background: 'none',
+                  border: 'none',
+                  cursor: 'pointer',
+                  fontSize: '11px',
+                  color: watch?.watched ? 'var(--primary)' : 'var(--text-muted)',
+                  opacity: watch?.watched || (watch?.position_secs ?? 0) > 0 ? 1 : 0.4,
+                }}
+              >
+                {watch?.watched ? '✓' : (watch?.position_secs ?? 0) > 0 ? '▶' : '○'}
+              </button>
             </div>
-          ))}
+            );
+          })}
         </div>
       )}
     </div>
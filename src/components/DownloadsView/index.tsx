@@ -1,11 +1,13 @@
 import React from 'react';
-import { useQuery } from '@tanstack/react-query';
+import { useMutation, useQuery } from '@tanstack/react-query';
 import AuraLoader from '../AuraLoader';
-import { getDownloads } from '../../lib/api/tauri';
+import { exportPlaylist, getDownloads } from '../../lib/api/tauri';
 import { DownloadJob, DownloadTask } from '../../lib/api/types';
 
 const DownloadsView: React.FC = () => {
-
+  const { mutate: writePlaylist, isPending: isWritingPlaylist } = useMutation({
+    mutationFn: (jobId: string) => exportPlaylist(jobId),
+  });
 
   const { data: downloads, isLoading } = useQuery({
     queryKey: ['downloads'],
@@ -50,7 +52,19 @@ const DownloadsView: React.FC = () => {
         ) : (
           [...downloads].reverse().map((job: DownloadJob) => (
             <div key={job.id} className="download-group">
-              <div className="download-group-header">{job.name}</div>
+              <div className="download-group-header">
+                {job.name}
+                {job.tasks.some((t) => t.status === 'Completed') && (
+                  <button
+                    className="btn btn-ghost"
+                    style={{ marginLeft: '10px' }}
+                    onClick={() => writePlaylist(job.id)}
+                    disabled={isWritingPlaylist}
+                  >
+                    Playlist
+                  </button>
+                )}
+              </div>
               {job.tasks.map((d: DownloadTask) => {
                 const progress = d.total_bytes > 0 ? (d.progress_bytes / d.total_bytes) * 100 : 0;
                 return (
@@ -1,7 +1,10 @@
 import React, { useEffect, useRef } from 'react';
-import { useQuery } from '@tanstack/react-query';
+import { useInfiniteQuery } from '@tanstack/react-query';
 import AuraLoader from '../AuraLoader';
-import { getPopularAnime, getNewAnime } from '../../lib/api/tauri';
+import { getPopularAnimePage, getNewAnimePage } from '../../lib/api/tauri';
+import type { Page } from '../../lib/api/types';
+import { coverSrc } from '../../lib/utils';
+import { useInfiniteScroll } from '../../hooks/use-infinite-scroll';
 
 interface HomeViewProps {
   onNavigate: (view: string, data?: any) => void;
@@ -15,23 +18,37 @@ const HomeView: React.FC<HomeViewProps> = ({ onNavigate, showToast }) => {
   const popularScrollRef = useRef<HTMLDivElement>(null);
   const newScrollRef = useRef<HTMLDivElement>(null);
 
-  const {
-    data: popularAnime,
-    isLoading: isLoadingPopular,
-    error: errorPopular,
-  } = useQuery({
+  const nextPage = (last: Page<unknown>) => (last.has_more ? last.page + 1 : undefined);
+
+  const popular = useInfiniteQuery({
     queryKey: ['popularAnime'],
-    queryFn: getPopularAnime,
+    queryFn: ({ pageParam }) => getPopularAnimePage(pageParam),
+    initialPageParam: 1,
+    getNextPageParam: nextPage,
   });
+  const { isLoading: isLoadingPopular, error: errorPopular } = popular;
+  const popularAnime = popular.data?.pages.flatMap((page) => page.items);
 
-  const {
-    data: newAnime,
-    isLoading: isLoadingNew,
-    error: errorNew,
-  } = useQuery({
+  const newReleases = useInfiniteQuery({
     queryKey: ['newAnime'],
-    queryFn: getNewAnime,
+    queryFn: ({ pageParam }) => getNewAnimePage(pageParam),
+    initialPageParam: 1,
+    getNextPageParam: nextPage,
   });
+  const { isLoading: isLoadingNew, error: errorNew } = newReleases;
+  const newAnime = newReleases.data?.pages.flatMap((page) => page.items);
+
+  // Load the next page when a row is scrolled to its end
+  const popularEndRef = useInfiniteScroll<HTMLDivElement>(
+    () => popular.fetchNextPage(),
+    popular.hasNextPage && !popular.isFetchingNextPage,
+    popularScrollRef
+  );
+  const newEndRef = useInfiniteScroll<HTMLDivElement>(
+    () => newReleases.fetchNextPage(),
+    newReleases.hasNextPage && !newReleases.isFetchingNextPage,
+    newScrollRef
+  );
 
   useEffect(() => {
     if (errorPopular) {
@@ -78,7 +95,7 @@ const HomeView: React.FC<HomeViewProps> = ({ onNavigate, showToast }) => {
               onClick={() => onNavigate('details', anime)}
             >
               <img
-                src={anime.image}
+                src={coverSrc(anime.image, 300)}
                 className="anime-poster-horizontal"
                 loading="lazy"
                 alt={anime.title}
@@ -86,6 +103,7 @@ const HomeView: React.FC<HomeViewProps> = ({ onNavigate, showToast }) => {
               <div className="anime-title-horizontal">{anime.title}</div>
             </div>
           ))}
+          <div ref={popularEndRef} aria-hidden="true" />
         </div>
       </div>
 
@@ -109,7 +127,7 @@ const HomeView: React.FC<HomeViewProps> = ({ onNavigate, showToast }) => {
               onClick={() => onNavigate('details', anime)}
             >
               <img
-                src={anime.image}
+                src={coverSrc(anime.image, 300)}
                 className="anime-poster-horizontal"
                 loading="lazy"
                 alt={anime.title}
@@ -117,6 +135,7 @@ const HomeView: React.FC<HomeViewProps> = ({ onNavigate, showToast }) => {
               <div className="anime-title-horizontal">{anime.title}</div>
             </div>
           ))}
+          <div ref={newEndRef} aria-hidden="true" />
         </div>
       </div>
     </div>
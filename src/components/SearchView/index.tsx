@@ -1,7 +1,9 @@
 import React, { useState, useEffect } from 'react';
-import { useQuery } from '@tanstack/react-query';
+import { useInfiniteQuery } from '@tanstack/react-query';
 import AuraLoader from '../AuraLoader';
-import { searchAnime } from '../../lib/api/tauri';
+import { searchAnimePage } from '../../lib/api/tauri';
+import { coverSrc } from '../../lib/utils';
+import { useInfiniteScroll } from '../../hooks/use-infinite-scroll';
 
 interface SearchViewProps {
     onNavigate: (view: string, data?: any) => void;
@@ -11,15 +13,28 @@ interface SearchViewProps {
     ) => void;
 }
 
+// Keeps the last query across remounts so returning from a details page shows the same results
+const LAST_SEARCH_KEY = 'aura:lastSearch';
+
 const SearchView: React.FC<SearchViewProps> = ({ onNavigate, showToast }) => {
-    const [inputValue, setInputValue] = useState('');
-    const [searchQuery, setSearchQuery] = useState('');
+    const [inputValue, setInputValue] = useState(() => sessionStorage.getItem(LAST_SEARCH_KEY) ?? '');
+    const [searchQuery, setSearchQuery] = useState(() => sessionStorage.getItem(LAST_SEARCH_KEY) ?? '');
 
-    const { data, isLoading, error, isSuccess } = useQuery({
+    const results = useInfiniteQuery({
         queryKey: ['animeSearch', searchQuery],
-        queryFn: () => searchAnime(searchQuery),
+        queryFn: ({ pageParam }) => searchAnimePage(searchQuery, pageParam),
+        initialPageParam: 1,
+        getNextPageParam: (last) => (last.has_more ? last.page + 1 : undefined),
         enabled: searchQuery.length > 0,
     });
+    const { isLoading, error, isSuccess } = results;
+    const data = results.data?.pages.flatMap((page) => page.items);
+
+    // More results are read from the site as the end of the grid comes into view
+    const endRef = useInfiniteScroll<HTMLDivElement>(
+        () => results.fetchNextPage(),
+        results.hasNextPage && !results.isFetchingNextPage
+    );
 
     useEffect(() => {
         if (error) {
@@ -34,6 +49,7 @@ const SearchView: React.FC<SearchViewProps> = ({ onNavigate, showToast }) => {
     const handleSearch = () => {
         if (inputValue.trim()) {
             setSearchQuery(inputValue.trim());
+            sessionStorage.setItem(LAST_SEARCH_KEY, inputValue.trim());
         }
     };
 
@@ -92,7 +108,7 @@ const SearchView: React.FC<SearchViewProps> = ({ onNavigate, showToast }) => {
                             >
                                 <div className="poster-wrapper">
                                     <img
-                                        src={anime.image}
+                                        src={coverSrc(anime.image, 300)}
                                         className="anime-poster"
                                         loading="lazy"
                                         alt={anime.title}
@@ -104,6 +120,8 @@ const SearchView: React.FC<SearchViewProps> = ({ onNavigate, showToast }) => {
                             </div>
                         ))}
                     </div>
+                    <div ref={endRef} aria-hidden="true" />
+                    {results.isFetchingNextPage && <AuraLoader />}
                 </div>
             )}
 
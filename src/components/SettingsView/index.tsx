@@ -2,8 +2,8 @@ import React, { useState, useEffect } from 'react';
 import { useQuery, useMutation, useQueryClient } from '@tanstack/react-query';
 import { open } from '@tauri-apps/plugin-dialog';
 import { platform } from '@tauri-apps/plugin-os';
-import { getSettings, updateSettings } from '../../lib/api/tauri';
-import { SettingsUpdateRequest } from '../../lib/api/types';
+import { getSettings, getSettingsErrors, updateSettings, regenerateMetadata, onSettingsChanged, onSettingsRejected } from '../../lib/api/tauri';
+import { MetadataSettings, SettingsSaveError, SettingsUpdateRequest, StreamingSettings } from '../../lib/api/types';
 
 interface SettingsViewProps {
   showToast: (
@@ -16,6 +16,7 @@ const SettingsView: React.FC<SettingsViewProps> = ({ showToast }) => {
   const queryClient = useQueryClient();
   const [formData, setFormData] = useState<SettingsUpdateRequest>({});
   const [isAndroid, setIsAndroid] = useState(false);
+  const [fieldError, setFieldError] = useState<SettingsSaveError | null>(null);
 
   useEffect(() => {
     const osPlatform = platform();
@@ -27,12 +28,55 @@ const SettingsView: React.FC<SettingsViewProps> = ({ showToast }) => {
     queryFn: getSettings,
   });
 
+  // Hand edits of settings.toml are applied by the core; show the new values
+  useEffect(() => {
+    const unlisten = [
+      onSettingsChanged(() => {
+        queryClient.invalidateQueries({ queryKey: ['settings'] });
+        showToast('Settings reloaded from settings.toml', 'info');
+      }),
+      onSettingsRejected((error) => showToast(`settings.toml ignored: ${error}`, 'warning')),
+    ];
+    // Invalid values found at startup were replaced by defaults; saving fixes the file
+    getSettingsErrors()
+      .then((errors) => errors.forEach((error) => showToast(`${error}; using the default`, 'warning')))
+      .catch(() => {});
+    return () => {
+      unlisten.forEach((p) => p.then((stop) => stop()));
+    };
+  }, [queryClient, showToast]);
+
   const { mutate: saveSettings, isPending } = useMutation({
     mutationFn: updateSettings,
     onSuccess: () => {
+      setFieldError(null);
       showToast('Settings saved successfully', 'success');
       queryClient.invalidateQueries({ queryKey: ['settings'] });
     },
+    onError: (error: unknown) => {
+      const saveError = error as SettingsSaveError;
+      setFieldError(saveError.field ? saveError : null);
+      showToast(saveError.message ?? String(error), 'error');
+    },
+  });
+
+  const errorFor = (field: string) =>
+    fieldError?.field === field ? (
+      <div style={{ color: '#EF4444', fontSize: '12px', marginTop: '4px' }}>
+        {fieldError.message}
+      </div>
+    ) : null;
+
+  const { mutate: runRegenerate, isPending: isRegenerating } = useMutation({
+    mutationFn: regenerateMetadata,
+    onSuccess: (report) => {
+      const message = `Updated metadata for ${report.shows} shows (${report.episodes} episodes)`;
+      if (report.failed.length > 0) {
+        showToast(`${message}, ${report.failed.length} failed`, 'warning');
+      } else {
+        showToast(message, 'success');
+      }
+    },
     onError: (error) => {
       showToast(error.message, 'error');
     },
@@ -72,6 +116,22 @@ const SettingsView: React.FC<SettingsViewProps> = ({ showToast }) => {
     }));
   };
 
+  const handleMetadataToggle = (key: keyof MetadataSettings) => {
+    setFormData((prev) => {
+      const current = prev.metadata ?? settings?.metadata;
+      if (!current) return prev;
+      return { ...prev, metadata: { ...current, [key]: !current[key] } };
+    });
+  };
+
+  const handleStreamingChange = (changes: Partial<StreamingSettings>) => {
+    setFormData((prev) => {
+      const current = prev.streaming ?? settings?.streaming;
+      if (!current) return prev;
+      return { ...prev, streaming: { ...current, ...changes } };
+    });
+  };
+
   const handleSaveChanges = () => {
     if (formData) {
       if (settings) {
@@ -152,6 +212,7 @@ const SettingsView: React.FC<SettingsViewProps> = ({ showToast }) => {
                 {isAndroid ? 'Set Public' : 'Browse'}
               </button>
             </div>
+            {errorFor('download_dir')}
           </div>
           <div>
             <label
@@ -174,6 +235,7 @@ const SettingsView: React.FC<SettingsViewProps> = ({ showToast }) => {
               max="10"
               style={{ width: '100%' }}
             />
+            {errorFor('max_concurrent_downloads')}
           </div>
           <div>
             <label
@@ -196,6 +258,73 @@ const SettingsView: React.FC<SettingsViewProps> = ({ showToast }) => {
               max="16"
               style={{ width: '100%' }}
             />
+            {errorFor('segments_per_file')}
+          </div>
+          <div>
+            <label
+              style={{
+                color: 'var(--text-muted)',
+                fontSize: '12px',
+                display: 'block',
+                marginBottom: '5px',
+              }}
+            >
+              Media Server Metadata
+            </label>
+            {([
+              ['write_nfo', 'Write NFO files (Jellyfin, Kodi, Plex)'],
+              ['save_poster', 'Save cover as poster'],
+              ['jellyfin_naming', 'Name episodes "Title S01E01"'],
+              ['write_playlists', 'Write playlists when a show finishes'],
+            ] as [keyof MetadataSettings, string][]).map(([key, label]) => (
+              <label
+                key={key}
+                style={{ display: 'flex', alignItems: 'center', gap: '8px', marginBottom: '5px' }}
+              >
+                <input
+                  type="checkbox"
+                  checked={formData.metadata?.[key] ?? false}
+                  onChange={() => handleMetadataToggle(key)}
+                />
+                {label}
+              </label>
+            ))}
+            <button
+              className="btn btn-ghost"
+              onClick={() => runRegenerate()}
+              disabled={isRegenerating}
+            >
+              {isRegenerating ? 'Updating...' : 'Regenerate Library Metadata'}
+            </button>
+          </div>
+          <div>
+            <label
+              style={{
+                color: 'var(--text-muted)',
+                fontSize: '12px',
+                display: 'block',
+                marginBottom: '5px',
+              }}
+            >
+              LAN Streaming (applies after restart)
+            </label>
+            <label style={{ display: 'flex', alignItems: 'center', gap: '8px', marginBottom: '5px' }}>
+              <input
+                type="checkbox"
+                checked={formData.streaming?.enabled ?? false}
+                onChange={(e) => handleStreamingChange({ enabled: e.target.checked })}
+              />
+              Serve the library to other devices
+            </label>
+            <input
+              type="number"
+              className="input-pill"
+              value={formData.streaming?.port ?? ''}
+              onChange={(e) => handleStreamingChange({ port: parseInt(e.target.value, 10) })}
+              min="1024"
+              max="65535"
+              style={{ width: '100%' }}
+            />
           </div>
           <button
             className="btn btn-primary"
@@ -1,4 +1,6 @@
 import React, { useState, useEffect, useRef } from 'react';
+import { useQuery, useQueryClient } from '@tanstack/react-query';
+import { getNotifications, markNotificationRead, markAllNotificationsRead } from '../../lib/api/tauri';
 
 type TopBarProps = {
   pageTitle: string;
@@ -9,12 +11,31 @@ type TopBarProps = {
 const TopBar: React.FC<TopBarProps> = ({ pageTitle, canGoBack, onGoBack }) => {
   const [notifOpen, setNotifOpen] = useState(false);
   const notifRef = useRef<HTMLDivElement>(null);
+  const queryClient = useQueryClient();
+
+  const { data: notifications = [] } = useQuery({
+    queryKey: ['notifications'],
+    queryFn: getNotifications,
+    refetchInterval: 5000,
+  });
+  const unreadCount = notifications.filter((n) => !n.read).length;
 
   const toggleNotifications = (e: React.MouseEvent) => {
     e.stopPropagation();
     setNotifOpen(!notifOpen);
   };
 
+  const handleRead = async (id: number) => {
+    await markNotificationRead(id);
+    queryClient.invalidateQueries({ queryKey: ['notifications'] });
+  };
+
+  const handleReadAll = async (e: React.MouseEvent) => {
+    e.stopPropagation();
+    await markAllNotificationsRead();
+    queryClient.invalidateQueries({ queryKey: ['notifications'] });
+  };
+
   useEffect(() => {
     const handleClickOutside = (event: MouseEvent) => {
       if (notifRef.current && !notifRef.current.contains(event.target as Node)) {
@@ -46,16 +67,28 @@ const TopBar: React.FC<TopBarProps> = ({ pageTitle, canGoBack, onGoBack }) => {
 
       <div className="notif-container" onClick={toggleNotifications} ref={notifRef}>
         <svg className="icon" viewBox="0 0 24 24" style={{ color: 'var(--secondary)' }}><path d="M12 22c1.1 0 2-.9 2-2h-4c0 1.1.9 2 2 2zm6-6v-5c0-3.07-1.63-5.64-4.5-6.32V4c0-.83-.67-1.5-1.5-1.5s-1.5.67-1.5 1.5v.68C7.64 5.36 6 7.92 6 11v5l-2 2v1h16v-1l-2-2zm-2 1H8v-6c0-2.48 1.51-4.5 4-4.5s4 2.02 4 4.5v6z" /></svg>
-        <div className="notif-badge" id="notif-dot"></div>
+        {unreadCount > 0 && <div className="notif-badge" id="notif-dot"></div>}
 
         <div className={`notif-dropdown ${notifOpen ? 'show' : ''}`} id="notif-dropdown">
-          <div className="notif-item">
-            <div className="notif-dot"></div>
-            <div>
-              <strong>Update Available</strong><br />
-              <span style={{ color: 'var(--text-muted)' }}>Version 0.0.3 is ready to install.</span>
+          {notifications.length === 0 && (
+            <div className="notif-item">
+              <span style={{ color: 'var(--text-muted)' }}>No notifications yet.</span>
+            </div>
+          )}
+          {unreadCount > 0 && (
+            <div className="notif-item" onClick={handleReadAll}>
+              <span style={{ color: 'var(--primary)' }}>Mark all as read</span>
+            </div>
+          )}
+          {notifications.map((n) => (
+            <div key={n.id} className="notif-item" onClick={() => handleRead(n.id)}>
+              {!n.read && <div className="notif-dot"></div>}
+              <div>
+                <strong>{n.title}</strong><br />
+                <span style={{ color: 'var(--text-muted)' }}>{n.body}</span>
+              </div>
             </div>
-          </div>
+          ))}
         </div>
       </div>
     </div>
@@ -0,0 +1,35 @@
+// src/hooks/use-infinite-scroll.ts
+import { useEffect, useRef, type RefObject } from 'react';
+
+// Calls `onReach` whenever the returned sentinel element scrolls into view, e.g. to load the
+// next page. `root` is the scrolling container (the viewport when omitted); the sentinel goes
+// after the last item inside it.
+export function useInfiniteScroll<T extends HTMLElement>(
+    onReach: () => void,
+    enabled: boolean,
+    root?: RefObject<HTMLElement>
+) {
+    const sentinelRef = useRef<T>(null);
+    const onReachRef = useRef(onReach);
+    onReachRef.current = onReach;
+
+    useEffect(() => {
+        const sentinel = sentinelRef.current;
+        if (!enabled || !sentinel) {
+            return;
+        }
+        const observer = new IntersectionObserver(
+            (entries) => {
+                if (entries.some((entry) => entry.isIntersecting)) {
+                    onReachRef.current();
+                }
+            },
+            // Start loading a little before the end is visible
+            { root: root?.current ?? null, rootMargin: '400px' }
+        );
+        observer.observe(sentinel);
+        return () => observer.disconnect();
+    }, [enabled, root]);
+
+    return sentinelRef;
+}
@@ -1,28 +1,56 @@
 import { invoke } from '@tauri-apps/api/core';
-import type { AnimeSearchResult, AnimeInfo, AnimeListEntry, Episode, DownloadJob, Settings } from './types';
+import { listen, type UnlistenFn } from '@tauri-apps/api/event';
+import type { AnimeSearchResult, SearchFilters, AnimeInfo, AnimeListEntry, Page, Episode, DownloadJob, Settings, Subscription, AppNotification, WatchEntry, ShowProgress, ListStatus, WatchlistEntry, MetadataReport } from './types';
 
-export const searchAnime = async (query: string): Promise<AnimeSearchResult[]> => {
-    return await invoke('search_anime', { query });
+export const searchAnime = async (query: string, refresh = false, filters?: SearchFilters): Promise<AnimeSearchResult[]> => {
+    return await invoke('search_anime', { query, filters, refresh });
 };
 
-export const getSeason = async (url: string): Promise<AnimeInfo> => {
-    return await invoke('get_season_data', { url });
+export const getSeason = async (url: string, refresh = false): Promise<AnimeInfo> => {
+    return await invoke('get_season_data', { url, refresh });
 };
 
 export const resolveLink = async (episode: Episode): Promise<string> => {
     return await invoke('resolve_link', { episode });
 };
 
-export const getNewAnime = async (): Promise<AnimeListEntry[]> => {
-    return await invoke('get_new_releases');
+export const getNewAnime = async (refresh = false): Promise<AnimeListEntry[]> => {
+    return await invoke('get_new_releases', { refresh });
 };
 
-export const getPopularAnime = async (): Promise<AnimeListEntry[]> => {
-    return await invoke('get_popular');
+export const getPopularAnime = async (refresh = false): Promise<AnimeListEntry[]> => {
+    return await invoke('get_popular', { refresh });
 };
 
-export const startDownload = async (anime_title: string, episodes: Episode[]): Promise<number> => {
-    return await invoke('start_download', { animeTitle: anime_title, episodes });
+// Paged variants for infinite scroll; `limit` defaults to 30 on the backend
+export const searchAnimePage = async (query: string, page: number, limit?: number, refresh = false, filters?: SearchFilters): Promise<Page<AnimeSearchResult>> => {
+    return await invoke('search_anime_page', { query, filters, page, limit, refresh });
+};
+
+export const getNewAnimePage = async (page: number, limit?: number, refresh = false): Promise<Page<AnimeListEntry>> => {
+    return await invoke('get_new_releases_page', { page, limit, refresh });
+};
+
+export const getPopularAnimePage = async (page: number, limit?: number, refresh = false): Promise<Page<AnimeListEntry>> => {
+    return await invoke('get_popular_page', { page, limit, refresh });
+};
+
+export const clearScrapeCache = async (): Promise<void> => {
+    return await invoke('clear_scrape_cache');
+};
+
+export const startDownload = async (
+    anime_title: string,
+    episodes: Episode[],
+    anime_url?: string,
+    cover_url?: string
+): Promise<number> => {
+    return await invoke('start_download', {
+        animeTitle: anime_title,
+        episodes,
+        animeUrl: anime_url ?? null,
+        coverUrl: cover_url ?? null,
+    });
 };
 
 export const getDownloads = async (): Promise<DownloadJob[]> => {
@@ -33,6 +61,121 @@ export const getSettings = async (): Promise<Settings> => {
     return await invoke('get_settings');
 };
 
+// Invalid values in settings.toml that were replaced by defaults at startup
+export const getSettingsErrors = async (): Promise<string[]> => {
+    return await invoke('get_settings_errors');
+};
+
 export const updateSettings = async (settings: Settings): Promise<void> => {
     return await invoke('update_settings', { settings });
 };
+
+// settings.toml was changed on disk (payload: dotted keys that changed)
+export const onSettingsChanged = (handler: (keys: string[]) => void): Promise<UnlistenFn> => {
+    return listen<string[]>('settings-changed', (event) => handler(event.payload));
+};
+
+// settings.toml was edited but is invalid; the previous settings stay in effect
+export const onSettingsRejected = (handler: (error: string) => void): Promise<UnlistenFn> => {
+    return listen<string>('settings-rejected', (event) => handler(event.payload));
+};
+
+export const getSubscriptions = async (): Promise<Subscription[]> => {
+    return await invoke('get_subscriptions');
+};
+
+export const subscribeAnime = async (url: string): Promise<Subscription> => {
+    return await invoke('subscribe_anime', { url });
+};
+
+export const unsubscribeAnime = async (url: string): Promise<boolean> => {
+    return await invoke('unsubscribe_anime', { url });
+};
+
+export const checkSubscriptions = async (): Promise<number> => {
+    return await invoke('check_subscriptions');
+};
+
+export const getNotifications = async (): Promise<AppNotification[]> => {
+    return await invoke('get_notifications');
+};
+
+export const markNotificationRead = async (id: number): Promise<boolean> => {
+    return await invoke('mark_notification_read', { id });
+};
+
+export const markAllNotificationsRead = async (): Promise<void> => {
+    return await invoke('mark_all_notifications_read');
+};
+
+export const clearNotifications = async (): Promise<void> => {
+    return await invoke('clear_notifications');
+};
+
+export const getWatchState = async (url: string): Promise<WatchEntry[]> => {
+    return await invoke('get_watch_state', { url });
+};
+
+export const markEpisodeWatched = async (url: string, anime_title: string, episode_number: number, watched: boolean): Promise<WatchEntry> => {
+    return await invoke('mark_episode_watched', { url, animeTitle: anime_title, episodeNumber: episode_number, watched });
+};
+
+export const setWatchPosition = async (url: string, anime_title: string, episode_number: number, position_secs: number, duration_secs?: number): Promise<WatchEntry> => {
+    return await invoke('set_watch_position', { url, animeTitle: anime_title, episodeNumber: episode_number, positionSecs: position_secs, durationSecs: duration_secs ?? null });
+};
+
+export const getNextEpisode = async (url: string): Promise<Episode | null> => {
+    return await invoke('get_next_episode', { url });
+};
+
+export const getContinueWatching = async (): Promise<ShowProgress[]> => {
+    return await invoke('get_continue_watching');
+};
+
+export const getWatchlist = async (status?: ListStatus): Promise<WatchlistEntry[]> => {
+    return await invoke('get_watchlist', { status: status ?? null });
+};
+
+export const getWatchlistEntry = async (url: string): Promise<WatchlistEntry | null> => {
+    return await invoke('get_watchlist_entry', { url });
+};
+
+export const setWatchlistStatus = async (url: string, title: string, status: ListStatus): Promise<WatchlistEntry> => {
+    return await invoke('set_watchlist_status', { url, title, status });
+};
+
+export const setWatchlistScore = async (url: string, score: number | null): Promise<WatchlistEntry> => {
+    return await invoke('set_watchlist_score', { url, score });
+};
+
+export const setWatchlistNotes = async (url: string, notes: string): Promise<WatchlistEntry> => {
+    return await invoke('set_watchlist_notes', { url, notes });
+};
+
+export const removeFromWatchlist = async (url: string): Promise<boolean> => {
+    return await invoke('remove_from_watchlist', { url });
+};
+
+export const exportWatchlist = async (path: string, format: 'json' | 'mal'): Promise<void> => {
+    return await invoke('export_watchlist', { path, format });
+};
+
+export const importWatchlist = async (path: string, format: 'json' | 'mal'): Promise<number> => {
+    return await invoke('import_watchlist', { path, format });
+};
+
+export const getImageCacheSize = async (): Promise<number> => {
+    return await invoke('get_image_cache_size');
+};
+
+export const clearImageCache = async (): Promise<void> => {
+    return await invoke('clear_image_cache');
+};
+
+export const regenerateMetadata = async (): Promise<MetadataReport> => {
+    return await invoke('regenerate_metadata');
+};
+
+export const exportPlaylist = async (job_id: string, format?: 'm3u8' | 'xspf'): Promise<string[]> => {
+    return await invoke('export_playlist', { jobId: job_id, format: format ?? null });
+};
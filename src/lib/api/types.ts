@@ -3,6 +3,17 @@ export interface AnimeSearchResult {
   title: string;
   url: string;
   image: string;
+  japanese_title: string | null;
+  relevance: number;
+}
+
+// Matches the filters of aura_core::SearchQuery (the text is passed separately)
+export interface SearchFilters {
+  year_from?: number | null;
+  year_to?: number | null;
+  tags?: string[];
+  kind?: 'tv' | 'movie' | 'ova' | 'special' | null;
+  min_episodes?: number | null;
 }
 
 // Matches aura_core::Episode
@@ -23,6 +34,10 @@ export interface AnimeInfo {
   tags?: string[] | null;
   description?: string | null;
   japanese_title?: string | null;
+  total_episodes?: number | null;
+  score?: number | null;
+  status?: 'airing' | 'finished' | null;
+  cover_image?: string | null;
 }
 
 // Matches aura_core::ListEntry
@@ -35,10 +50,19 @@ export interface AnimeListEntry {
   rank?: number | null;
 }
 
+// Matches aura_core::Page; `page` counts from 1
+export interface Page<T> {
+  items: T[];
+  page: number;
+  has_more: boolean;
+}
+
 export interface DownloadJob {
   id: string;
   name: string;
   tasks: DownloadTask[];
+  anime_url?: string | null;
+  cover_url?: string | null;
 }
 
 export interface DownloadTask {
@@ -50,10 +74,132 @@ export interface DownloadTask {
 }
 
 export interface Settings {
+  config_version: number;
   download_dir: string;
   max_concurrent_downloads: number;
   segments_per_file: number;
   user_agent: string;
+  subscription_check_interval_secs: number;
+  cache: CacheSettings;
+  metadata: MetadataSettings;
+  streaming: StreamingSettings;
+  daemon: DaemonSettings;
+  network: NetworkSettings;
+}
+
+// Matches aura_core::NetworkSettings (empty strings mean "not set")
+export interface NetworkSettings {
+  proxy: string;
+  proxy_username: string;
+  proxy_password: string;
+  connect_timeout_secs: number;
+  read_timeout_secs: number;
+  headers: Record<string, string>;
+  ca_bundle: string;
+  ip_version: 'auto' | 'ipv4' | 'ipv6';
+  max_requests_per_host: number;
+  min_request_interval_ms: number;
+}
+
+// Matches aura_core::StreamingSettings (applied on restart)
+export interface StreamingSettings {
+  enabled: boolean;
+  bind_address: string;
+  port: number;
+}
+
+// Matches aura_core::DaemonSettings
+export interface DaemonSettings {
+  bind_address: string;
+  port: number;
+}
+
+// Matches aura_core::MetadataSettings
+export interface MetadataSettings {
+  write_nfo: boolean;
+  save_poster: boolean;
+  jellyfin_naming: boolean;
+  write_playlists: boolean;
+}
+
+// Result of regenerate_metadata
+export interface MetadataReport {
+  shows: number;
+  episodes: number;
+  failed: string[];
+}
+
+// Matches aura_core::CacheSettings (TTLs in seconds, 0 disables caching)
+export interface CacheSettings {
+  capacity: number;
+  persist_to_disk: boolean;
+  search_ttl_secs: number;
+  season_ttl_secs: number;
+  new_ttl_secs: number;
+  popular_ttl_secs: number;
+}
+
+// Matches aura_core::Subscription
+export interface Subscription {
+  url: string;
+  title: string;
+  last_seen_episode: number;
+  added_at: number;
+  last_checked?: number | null;
 }
 
 export interface SettingsUpdateRequest extends Partial<Settings> { }
+
+// Rejection of update_settings; `field` is the dotted key of the invalid setting
+export interface SettingsSaveError {
+  field?: string | null;
+  message: string;
+}
+
+// Matches aura_core::Notification
+export interface AppNotification {
+  id: number;
+  kind: 'JobFinished' | 'TaskFailed' | 'LinkExpired' | 'NewEpisode';
+  title: string;
+  body: string;
+  created_at: number;
+  read: boolean;
+  job_id?: string | null;
+}
+
+// Matches aura_core::WatchEntry
+export interface WatchEntry {
+  anime_url: string;
+  anime_title: string;
+  episode_number: number;
+  watched: boolean;
+  position_secs: number;
+  duration_secs?: number | null;
+  updated_at: number;
+  watched_at?: number | null;
+}
+
+// Matches aura_core::ShowProgress
+export interface ShowProgress {
+  anime_url: string;
+  anime_title: string;
+  watched_episodes: number;
+  last_watched_episode?: number | null;
+  resume_episode?: number | null;
+  last_activity: number;
+}
+
+// Matches aura_core::ListStatus
+export type ListStatus = 'PlanToWatch' | 'Watching' | 'Completed' | 'OnHold' | 'Dropped';
+
+// Matches aura_core::WatchlistEntry
+export interface WatchlistEntry {
+  url: string;
+  title: string;
+  status: ListStatus;
+  score?: number | null;
+  notes: string;
+  episodes_watched: number;
+  added_at: number;
+  updated_at: number;
+}
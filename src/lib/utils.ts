@@ -1,5 +1,6 @@
 import { clsx, type ClassValue } from "clsx"
 import { twMerge } from "tailwind-merge"
+import { convertFileSrc } from "@tauri-apps/api/core"
 
 export function cn(...inputs: ClassValue[]) {
   return twMerge(clsx(inputs))
@@ -51,3 +52,10 @@ export const parseEpisodeRange = (
 
   return Array.from(selected).sort((a, b) => a - b)
 }
+
+// Serve a cover through the local image cache (aura-img protocol) instead of hotlinking it.
+// `width` requests a resized thumbnail.
+export const coverSrc = (imageUrl: string, width?: number): string => {
+  const src = convertFileSrc(imageUrl, "aura-img")
+  return width ? `${src}?w=${width}` : src
+}